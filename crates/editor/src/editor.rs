@@ -132,7 +132,8 @@ use util::{defer, maybe, post_inc, RangeExt, ResultExt, TryFutureExt};
 use workspace::notifications::NotificationId;
 use workspace::Toast;
 use workspace::{
-    searchable::SearchEvent, ItemNavHistory, SplitDirection, ViewId, Workspace, WorkspaceId,
+    searchable::SearchEvent, ClipboardRing, ItemNavHistory, SplitDirection, ViewId, Workspace,
+    WorkspaceId,
 };
 
 use crate::hover_links::find_url;
@@ -5506,6 +5507,7 @@ impl Editor {
                 s.select(selections);
             });
             this.insert("", cx);
+            cx.default_global::<ClipboardRing>().record(text.clone());
             cx.write_to_clipboard(ClipboardItem::new(text).with_metadata(clipboard_selections));
         });
     }
@@ -5545,6 +5547,7 @@ impl Editor {
             }
         }
 
+        cx.default_global::<ClipboardRing>().record(text.clone());
         cx.write_to_clipboard(ClipboardItem::new(text).with_metadata(clipboard_selections));
     }
 
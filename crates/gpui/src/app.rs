@@ -557,6 +557,18 @@ impl AppContext {
         self.platform.read_from_clipboard()
     }
 
+    /// Writes data to the primary selection, which X11 and Wayland desktops paste on middle-click.
+    #[cfg(target_os = "linux")]
+    pub fn write_to_primary(&self, item: ClipboardItem) {
+        self.platform.write_to_primary(item)
+    }
+
+    /// Reads data from the primary selection.
+    #[cfg(target_os = "linux")]
+    pub fn read_from_primary(&self) -> Option<ClipboardItem> {
+        self.platform.read_from_primary()
+    }
+
     /// Writes credentials to the platform keychain.
     pub fn write_credentials(
         &self,
@@ -629,6 +641,11 @@ impl AppContext {
         self.platform.should_auto_hide_scrollbars()
     }
 
+    /// Plays the platform's alert sound.
+    pub fn beep(&self) {
+        self.platform.beep()
+    }
+
     /// Restart the application.
     pub fn restart(&self) {
         self.platform.restart()
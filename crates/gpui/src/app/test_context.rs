@@ -256,6 +256,19 @@ impl TestAppContext {
         self.test_platform.read_from_clipboard()
     }
 
+    /// Simulates writing to the primary selection.
+    #[cfg(target_os = "linux")]
+    pub fn write_to_primary(&self, item: ClipboardItem) {
+        self.test_platform.write_to_primary(item)
+    }
+
+    /// Simulates reading from the primary selection.
+    /// This will return the most recent value from `write_to_primary`.
+    #[cfg(target_os = "linux")]
+    pub fn read_from_primary(&self) -> Option<ClipboardItem> {
+        self.test_platform.read_from_primary()
+    }
+
     /// Simulates choosing a File in the platform's "Open" dialog.
     pub fn simulate_new_path_selection(
         &self,
@@ -935,3 +948,17 @@ impl AnyWindowHandle {
         self.update(cx, |_, cx| cx.new_view(build_view)).unwrap()
     }
 }
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use crate::{ClipboardItem, TestAppContext};
+
+    #[crate::test]
+    fn test_primary_selection(cx: &mut TestAppContext) {
+        let item = ClipboardItem::new("selected text".to_string());
+        cx.write_to_primary(item.clone());
+        assert_eq!(cx.read_from_primary(), Some(item));
+        // The primary selection is separate from the clipboard
+        assert_eq!(cx.read_from_clipboard(), None);
+    }
+}
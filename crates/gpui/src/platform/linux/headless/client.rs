@@ -85,6 +85,12 @@ impl LinuxClient for HeadlessClient {
         None
     }
 
+    fn write_to_primary(&self, item: crate::ClipboardItem) {}
+
+    fn read_from_primary(&self) -> Option<crate::ClipboardItem> {
+        None
+    }
+
     fn run(&self) {
         let mut event_loop = self
             .0
@@ -58,6 +58,8 @@ pub trait LinuxClient {
     fn set_cursor_style(&self, style: CursorStyle);
     fn write_to_clipboard(&self, item: ClipboardItem);
     fn read_from_clipboard(&self) -> Option<ClipboardItem>;
+    fn write_to_primary(&self, item: ClipboardItem);
+    fn read_from_primary(&self) -> Option<ClipboardItem>;
     fn run(&self);
 }
 
@@ -387,6 +389,9 @@ impl<P: LinuxClient + 'static> Platform for P {
         false
     }
 
+    // todo(linux)
+    fn beep(&self) {}
+
     fn write_credentials(&self, url: &str, username: &str, password: &[u8]) -> Task<Result<()>> {
         let url = url.to_string();
         let username = username.to_string();
@@ -468,6 +473,14 @@ impl<P: LinuxClient + 'static> Platform for P {
     fn read_from_clipboard(&self) -> Option<ClipboardItem> {
         self.read_from_clipboard()
     }
+
+    fn write_to_primary(&self, item: ClipboardItem) {
+        self.write_to_primary(item)
+    }
+
+    fn read_from_primary(&self) -> Option<ClipboardItem> {
+        self.read_from_primary()
+    }
 }
 
 pub(super) fn is_within_click_distance(a: Point<Pixels>, b: Point<Pixels>) -> bool {
@@ -848,6 +848,10 @@ impl Platform for MacPlatform {
         }
     }
 
+    fn beep(&self) {
+        unsafe { NSBeep() }
+    }
+
     fn write_to_clipboard(&self, item: ClipboardItem) {
         let state = self.0.lock();
         unsafe {
@@ -1215,6 +1219,11 @@ unsafe fn ns_url_to_path(url: id) -> Result<PathBuf> {
     }
 }
 
+#[link(name = "AppKit", kind = "framework")]
+extern "C" {
+    fn NSBeep();
+}
+
 mod security {
     #![allow(non_upper_case_globals)]
     use super::*;
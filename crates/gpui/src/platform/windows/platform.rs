@@ -686,6 +686,10 @@ impl Platform for WindowsPlatform {
         false
     }
 
+    fn beep(&self) {
+        unsafe { MessageBeep(MB_OK) }.log_err();
+    }
+
     fn write_to_clipboard(&self, item: ClipboardItem) {
         let mut ctx = ClipboardContext::new().unwrap();
         ctx.set_contents(item.text().to_owned()).unwrap();
@@ -1,6 +1,6 @@
 use crate::Project;
 use collections::HashMap;
-use gpui::{AnyWindowHandle, Context, Entity, Model, ModelContext, WeakModel};
+use gpui::{AnyWindowHandle, AppContext, Context, Entity, Model, ModelContext, WeakModel};
 use settings::Settings;
 use smol::channel::bounded;
 use std::path::{Path, PathBuf};
@@ -8,7 +8,7 @@ use terminal::{
     terminal_settings::{self, Shell, TerminalSettings, VenvSettingsContent},
     SpawnTask, TaskState, TaskStatus, Terminal, TerminalBuilder,
 };
-use util::ResultExt;
+use util::{paths::LOCAL_ENV_RELATIVE_PATH, ResultExt};
 
 // #[cfg(target_os = "macos")]
 // use std::os::unix::ffi::OsStrExt;
@@ -18,6 +18,23 @@ pub struct Terminals {
 }
 
 impl Project {
+    /// The variables in the `.zed/env` file of the worktree a terminal starting in
+    /// `working_directory` belongs to.
+    fn project_env(
+        &self,
+        working_directory: Option<&Path>,
+        cx: &AppContext,
+    ) -> HashMap<String, String> {
+        let Some(working_directory) = working_directory else {
+            return HashMap::default();
+        };
+        self.worktrees()
+            .filter_map(|worktree| Some(worktree.read(cx).as_local()?.abs_path().clone()))
+            .find(|root| working_directory.starts_with(root))
+            .map(|root| terminal::env_file::load(&root.join(*LOCAL_ENV_RELATIVE_PATH)))
+            .unwrap_or_default()
+    }
+
     pub fn create_terminal(
         &mut self,
         working_directory: Option<PathBuf>,
@@ -35,7 +52,9 @@ impl Project {
         let python_settings = settings.detect_venv.clone();
         let (completion_tx, completion_rx) = bounded(1);
 
-        let mut env = settings.env.clone();
+        // Variables from the `env` setting override the project's
+        let mut env = self.project_env(working_directory.as_deref(), cx);
+        env.extend(settings.env.clone());
         // Alacritty uses parent project's working directory when no working directory is provided
         // https://github.com/alacritty/alacritty/blob/fd1a3cc79192d1d03839f0fd8c72e1f8d0fce42e/extra/man/alacritty.5.scd?plain=1#L47-L52
 
@@ -66,14 +85,22 @@ impl Project {
             (None, settings.shell.clone())
         };
 
+        // Tasks run their command directly rather than in a shell
+        let login_shell = settings.login_shell && spawn_task.is_none();
         let terminal = TerminalBuilder::new(
             working_directory.clone(),
             spawn_task,
             shell,
+            login_shell,
             env,
+            settings.dotenv,
+            &settings.term,
             Some(settings.blinking.clone()),
             settings.alternate_scroll,
             settings.max_scroll_history_lines,
+            settings.max_bytes_per_frame,
+            settings.word_separators.clone(),
+            settings.cursor_shape,
             window,
             completion_tx,
         )
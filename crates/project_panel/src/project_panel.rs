@@ -423,9 +423,7 @@ impl ProjectPanel {
                             .action("New Folder", Box::new(NewDirectory))
                             .separator()
                             .action("Reveal in Finder", Box::new(RevealInFinder))
-                            .when(is_dir, |menu| {
-                                menu.action("Open in Terminal…", Box::new(OpenInTerminal))
-                            })
+                            .action("Open in Terminal…", Box::new(OpenInTerminal))
                             .when(is_dir, |menu| {
                                 menu.separator()
                                     .action("Find in Folder…", Box::new(NewSearchInDirectory))
@@ -1019,14 +1017,19 @@ impl ProjectPanel {
     }
 
     fn open_in_terminal(&mut self, _: &OpenInTerminal, cx: &mut ViewContext<Self>) {
-        if let Some((worktree, entry)) = self.selected_entry(cx) {
-            let path = worktree.abs_path().join(&entry.path);
-            cx.dispatch_action(
-                workspace::OpenTerminal {
-                    working_directory: path,
-                }
-                .boxed_clone(),
-            )
+        if let Some(working_directory) = self.selected_directory(cx) {
+            cx.dispatch_action(workspace::OpenTerminal { working_directory }.boxed_clone())
+        }
+    }
+
+    /// The absolute path of the selected folder, or of the folder containing the selected file.
+    fn selected_directory(&self, cx: &AppContext) -> Option<PathBuf> {
+        let (worktree, entry) = self.selected_entry(cx)?;
+        let path = worktree.abs_path().join(&entry.path);
+        if entry.is_dir() {
+            Some(path)
+        } else {
+            path.parent().map(Path::to_path_buf)
         }
     }
 
@@ -3513,6 +3516,52 @@ mod tests {
         );
     }
 
+    #[gpui::test]
+    async fn test_selected_directory(cx: &mut gpui::TestAppContext) {
+        init_test(cx);
+
+        let fs = FakeFs::new(cx.executor().clone());
+        fs.insert_tree(
+            "/root1",
+            json!({
+                "a": {
+                    "b": { "c.txt": "" },
+                    "d.txt": "",
+                },
+            }),
+        )
+        .await;
+
+        let project = Project::test(fs.clone(), ["/root1".as_ref()], cx).await;
+        let workspace = cx.add_window(|cx| Workspace::test_new(project.clone(), cx));
+        let cx = &mut VisualTestContext::from_window(*workspace, cx);
+        let panel = workspace
+            .update(cx, |workspace, cx| ProjectPanel::new(workspace, cx))
+            .unwrap();
+
+        select_path(&panel, "root1/a/b", cx);
+        panel.update(cx, |panel, cx| {
+            assert_eq!(
+                panel.selected_directory(cx),
+                Some(PathBuf::from("/root1/a/b"))
+            );
+        });
+
+        // Files open a terminal in the folder containing them
+        select_path(&panel, "root1/a/d.txt", cx);
+        panel.update(cx, |panel, cx| {
+            assert_eq!(
+                panel.selected_directory(cx),
+                Some(PathBuf::from("/root1/a"))
+            );
+        });
+
+        select_path(&panel, "root1", cx);
+        panel.update(cx, |panel, cx| {
+            assert_eq!(panel.selected_directory(cx), Some(PathBuf::from("/root1")));
+        });
+    }
+
     fn toggle_expand_dir(
         panel: &View<ProjectPanel>,
         path: impl AsRef<Path>,
@@ -0,0 +1,100 @@
+use collections::HashMap;
+use std::{fs, path::Path};
+
+/// Reads the variables in the file at `path`, or none if it can't be read.
+pub fn load(path: &Path) -> HashMap<String, String> {
+    fs::read_to_string(path)
+        .map(|contents| parse(&contents))
+        .unwrap_or_default()
+}
+
+/// Parses `KEY=VALUE` lines, skipping blank lines, comments, and lines that aren't assignments.
+/// An `export` before the key is ignored. Single quoted values are taken as they are, while
+/// double quoted ones can contain `\n`, `\"` and `\\` escapes.
+pub fn parse(contents: &str) -> HashMap<String, String> {
+    contents.lines().filter_map(parse_line).collect()
+}
+
+fn parse_line(line: &str) -> Option<(String, String)> {
+    let line = line.trim();
+    if line.starts_with('#') {
+        return None;
+    }
+    let line = line.strip_prefix("export ").unwrap_or(line);
+    let (key, value) = line.split_once('=')?;
+    let key = key.trim();
+    let is_valid_key = key.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !is_valid_key {
+        return None;
+    }
+
+    let value = value.trim();
+    let value = if let Some(quoted) = value.strip_prefix('"') {
+        let mut unescaped = String::new();
+        let mut chars = quoted.chars();
+        loop {
+            match chars.next()? {
+                '"' => break,
+                '\\' => match chars.next()? {
+                    'n' => unescaped.push('\n'),
+                    c => unescaped.push(c),
+                },
+                c => unescaped.push(c),
+            }
+        }
+        unescaped
+    } else if let Some(quoted) = value.strip_prefix('\'') {
+        quoted.split_once('\'')?.0.to_string()
+    } else {
+        // Comments can follow unquoted values
+        match value.find(" #") {
+            Some(ix) => value[..ix].trim_end().to_string(),
+            None => value.to_string(),
+        }
+    };
+    Some((key.to_string(), value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let env = parse(
+            r#"
+            # Database
+            DATABASE_URL=postgres://localhost/dev
+            export EDITOR=vim
+            GREETING="Hello, \"world\"\nBye"
+            LITERAL='$HOME\n'
+            PORT=8080 # the default
+            EMPTY=
+            not an assignment
+            1INVALID=key
+            UNTERMINATED="oops
+            "#,
+        );
+
+        let mut env = env.into_iter().collect::<Vec<_>>();
+        env.sort();
+        assert_eq!(
+            env,
+            [
+                ("DATABASE_URL", "postgres://localhost/dev"),
+                ("EDITOR", "vim"),
+                ("EMPTY", ""),
+                ("GREETING", "Hello, \"world\"\nBye"),
+                ("LITERAL", "$HOME\\n"),
+                ("PORT", "8080"),
+            ]
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+        );
+    }
+
+    #[test]
+    fn test_load_missing_file() {
+        assert!(load(Path::new("/this/does/not/exist/.env")).is_empty());
+    }
+}
@@ -0,0 +1,143 @@
+use alacritty_terminal::index::Point as AlacPoint;
+use std::collections::VecDeque;
+
+/// Predicts the echo of typed characters, so they can be shown before a slow PTY echoes them.
+///
+/// Like mosh, predictions are only shown once the program has been seen echoing typed
+/// characters since the last reset. A program that doesn't echo, like a password prompt,
+/// never confirms a prediction and so never has its input shown. Anything that isn't plain
+/// printable text (Enter, arrows, pastes) resets the predictions.
+#[derive(Debug, Default)]
+pub(crate) struct LocalEcho {
+    /// Where the first pending prediction should be echoed.
+    anchor: AlacPoint,
+    pending: VecDeque<char>,
+    confirmed: bool,
+}
+
+impl LocalEcho {
+    pub fn predict(&mut self, text: &str, cursor: AlacPoint, columns: usize) {
+        if !text.chars().all(|c| c.is_ascii_graphic() || c == ' ') {
+            self.reset();
+            return;
+        }
+        if self.pending.is_empty() {
+            self.anchor = cursor;
+        }
+        // Stay away from line wrapping, where the echo gets harder to predict.
+        if self.anchor.column.0 + self.pending.len() + text.len() >= columns {
+            self.reset();
+            return;
+        }
+        self.pending.extend(text.chars());
+    }
+
+    pub fn reset(&mut self) {
+        self.pending.clear();
+        self.confirmed = false;
+    }
+
+    /// Drops the predictions the terminal has echoed, or all of them if the terminal
+    /// didn't echo what was predicted.
+    pub fn reconcile(&mut self, cursor: AlacPoint, char_at: impl Fn(AlacPoint) -> char) {
+        while let Some(&predicted) = self.pending.front() {
+            if cursor.line != self.anchor.line {
+                self.reset();
+                return;
+            }
+            if cursor.column <= self.anchor.column {
+                // The echo hasn't arrived yet.
+                return;
+            }
+            if char_at(self.anchor) != predicted {
+                self.reset();
+                return;
+            }
+            self.pending.pop_front();
+            self.anchor.column += 1;
+            self.confirmed = true;
+        }
+    }
+
+    /// The predictions to show, and where to show them.
+    pub fn visible(&self) -> Option<(AlacPoint, String)> {
+        if self.confirmed && !self.pending.is_empty() {
+            Some((self.anchor, self.pending.iter().collect()))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alacritty_terminal::index::{Column, Line};
+
+    fn point(column: usize) -> AlacPoint {
+        AlacPoint::new(Line(0), Column(column))
+    }
+
+    #[test]
+    fn test_predictions_need_a_confirmed_echo() {
+        let screen = "$ ls -la";
+        let char_at = |point: AlacPoint| screen.chars().nth(point.column.0).unwrap_or(' ');
+        let mut echo = LocalEcho::default();
+
+        echo.predict("l", point(2), 80);
+        assert_eq!(echo.visible(), None);
+
+        // The shell echoes the first character
+        echo.reconcile(point(3), char_at);
+        echo.predict("s", point(3), 80);
+        assert_eq!(echo.visible(), Some((point(3), "s".to_string())));
+        echo.predict(" -la", point(3), 80);
+        assert_eq!(echo.visible(), Some((point(3), "s -la".to_string())));
+
+        // Part of it arrives
+        echo.reconcile(point(5), char_at);
+        assert_eq!(echo.visible(), Some((point(5), "-la".to_string())));
+
+        echo.reconcile(point(8), char_at);
+        assert_eq!(echo.visible(), None);
+    }
+
+    #[test]
+    fn test_password_prompts_are_never_shown() {
+        let mut echo = LocalEcho::default();
+        for c in ["h", "u", "n", "t", "e", "r", "2"] {
+            echo.predict(c, point(10), 80);
+            echo.reconcile(point(10), |_| ' ');
+            assert_eq!(echo.visible(), None);
+        }
+    }
+
+    #[test]
+    fn test_mismatched_echo_resets_predictions() {
+        let mut echo = LocalEcho::default();
+        echo.predict("a", point(0), 80);
+        echo.reconcile(point(1), |_| 'a');
+        echo.predict("b", point(1), 80);
+        assert!(echo.visible().is_some());
+
+        echo.reconcile(point(2), |_| '*');
+        assert_eq!(echo.visible(), None);
+        echo.predict("c", point(2), 80);
+        assert_eq!(echo.visible(), None);
+    }
+
+    #[test]
+    fn test_control_characters_and_line_ends_reset_predictions() {
+        let mut echo = LocalEcho::default();
+        echo.predict("a", point(0), 80);
+        echo.reconcile(point(1), |_| 'a');
+        echo.predict("b", point(1), 80);
+        echo.predict("\t", point(1), 80);
+        assert_eq!(echo.visible(), None);
+
+        echo.predict("a", point(78), 80);
+        echo.reconcile(point(79), |_| 'a');
+        echo.predict("b", point(79), 80);
+        assert_eq!(echo.visible(), None);
+    }
+}
@@ -1,5 +1,6 @@
 /// The mappings defined in this file where created from reading the alacritty source
 use alacritty_terminal::term::TermMode;
+use anyhow::{anyhow, Result};
 use gpui::Keystroke;
 
 #[derive(Debug, PartialEq, Eq)]
@@ -56,6 +57,17 @@ pub fn to_esc_str(keystroke: &Keystroke, mode: &TermMode, alt_is_meta: bool) ->
         ("tab", AlacModifiers::Shift) => Some("\x1b[Z".to_string()),
         ("backspace", AlacModifiers::Alt) => Some("\x1b\x7f".to_string()),
         ("backspace", AlacModifiers::Shift) => Some("\x7f".to_string()),
+        // With alt as meta, the word motions shells bind to meta-b, meta-f and meta-d. Programs
+        // in application cursor mode get the modified keys, as they may bind them differently.
+        ("left", AlacModifiers::Alt) if alt_is_meta && !mode.contains(TermMode::APP_CURSOR) => {
+            Some("\x1bb".to_string())
+        }
+        ("right", AlacModifiers::Alt) if alt_is_meta && !mode.contains(TermMode::APP_CURSOR) => {
+            Some("\x1bf".to_string())
+        }
+        ("delete", AlacModifiers::Alt) if alt_is_meta && !mode.contains(TermMode::APP_CURSOR) => {
+            Some("\x1bd".to_string())
+        }
         ("home", AlacModifiers::Shift) if mode.contains(TermMode::ALT_SCREEN) => {
             Some("\x1b[1;2H".to_string())
         }
@@ -196,6 +208,12 @@ pub fn to_esc_str(keystroke: &Keystroke, mode: &TermMode, alt_is_meta: bool) ->
         return manual_esc_str;
     }
 
+    if modifiers == AlacModifiers::None {
+        if let Some(esc_str) = keypad_esc_str(keystroke.key.as_ref(), mode) {
+            return Some(esc_str);
+        }
+    }
+
     // Automated bindings applying modifiers
     if modifiers.any() {
         let modifier_code = modifier_code(keystroke);
@@ -251,6 +269,38 @@ pub fn to_esc_str(keystroke: &Keystroke, mode: &TermMode, alt_is_meta: bool) ->
     None
 }
 
+/// What a number pad key sends: an SS3 sequence in application keypad mode (DECKPAM), and the
+/// character on it otherwise. Number pad keys come in as the same keys as the main keyboard's,
+/// so they're only told apart when a keybinding sends them with `terminal::SendKeystroke` by
+/// these names.
+fn keypad_esc_str(key: &str, mode: &TermMode) -> Option<String> {
+    let (normal, application) = match key {
+        "kp_0" => ("0", 'p'),
+        "kp_1" => ("1", 'q'),
+        "kp_2" => ("2", 'r'),
+        "kp_3" => ("3", 's'),
+        "kp_4" => ("4", 't'),
+        "kp_5" => ("5", 'u'),
+        "kp_6" => ("6", 'v'),
+        "kp_7" => ("7", 'w'),
+        "kp_8" => ("8", 'x'),
+        "kp_9" => ("9", 'y'),
+        "kp_decimal" => (".", 'n'),
+        "kp_subtract" => ("-", 'm'),
+        "kp_add" => ("+", 'k'),
+        "kp_multiply" => ("*", 'j'),
+        "kp_divide" => ("/", 'o'),
+        "kp_equal" => ("=", 'X'),
+        "kp_enter" => ("\x0d", 'M'),
+        _ => return None,
+    };
+    Some(if mode.contains(TermMode::APP_KEYPAD) {
+        format!("\x1bO{application}")
+    } else {
+        normal.to_string()
+    })
+}
+
 ///   Code     Modifiers
 /// ---------+---------------------------
 ///    2     | Shift
@@ -276,12 +326,63 @@ fn modifier_code(keystroke: &Keystroke) -> u32 {
     modifier_code + 1
 }
 
+/// The bytes written to the PTY for a string of keys from a keybinding, with escapes like
+/// `\x1b`, `\e`, `\r`, `\n`, `\t` and `\\` replaced by what they stand for.
+pub fn unescape_keys(keys: &str) -> Result<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(keys.len());
+    let mut chars = keys.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut buffer = [0; 4];
+            bytes.extend_from_slice(c.encode_utf8(&mut buffer).as_bytes());
+            continue;
+        }
+        let byte = match chars.next() {
+            Some('x') => {
+                let digits = chars.by_ref().take(2).collect::<String>();
+                if digits.len() != 2 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+                    return Err(anyhow!("expected two hex digits after \\x in {keys:?}"));
+                }
+                u8::from_str_radix(&digits, 16)?
+            }
+            Some('e') => 0x1b,
+            Some('r') => b'\r',
+            Some('n') => b'\n',
+            Some('t') => b'\t',
+            Some('a') => 0x07,
+            Some('b') => 0x08,
+            Some('0') => 0,
+            Some('\\') => b'\\',
+            Some(other) => return Err(anyhow!("unknown escape \\{other} in {keys:?}")),
+            None => return Err(anyhow!("{keys:?} ends with a lone backslash")),
+        };
+        bytes.push(byte);
+    }
+    Ok(bytes)
+}
+
 #[cfg(test)]
 mod test {
     use gpui::Modifiers;
 
     use super::*;
 
+    #[test]
+    fn test_unescape_keys() {
+        assert_eq!(unescape_keys("\\x1b[1;5D").unwrap(), b"\x1b[1;5D");
+        assert_eq!(unescape_keys("\\eOP\\r\\n\\t").unwrap(), b"\x1bOP\r\n\t");
+        assert_eq!(unescape_keys("a\\\\b").unwrap(), b"a\\b");
+        assert_eq!(unescape_keys("\\x7F\\0").unwrap(), [0x7f, 0]);
+        assert_eq!(unescape_keys("λ").unwrap(), "λ".as_bytes());
+        assert_eq!(unescape_keys("").unwrap(), b"");
+
+        assert!(unescape_keys("\\x1").is_err());
+        assert!(unescape_keys("\\xzz").is_err());
+        assert!(unescape_keys("\\x+1").is_err());
+        assert!(unescape_keys("\\q").is_err());
+        assert!(unescape_keys("trailing\\").is_err());
+    }
+
     #[test]
     fn test_scroll_keys() {
         //These keys should be handled by the scrolling element directly
@@ -329,6 +430,70 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_word_motions() {
+        let alt_left = Keystroke::parse("alt-left").unwrap();
+        let alt_right = Keystroke::parse("alt-right").unwrap();
+        let alt_delete = Keystroke::parse("alt-delete").unwrap();
+        let alt_backspace = Keystroke::parse("alt-backspace").unwrap();
+        let none = TermMode::NONE;
+        let app_cursor = TermMode::APP_CURSOR;
+
+        // Alt as meta sends the readline word motions
+        assert_eq!(
+            to_esc_str(&alt_left, &none, true),
+            Some("\x1bb".to_string())
+        );
+        assert_eq!(
+            to_esc_str(&alt_right, &none, true),
+            Some("\x1bf".to_string())
+        );
+        assert_eq!(
+            to_esc_str(&alt_delete, &none, true),
+            Some("\x1bd".to_string())
+        );
+        assert_eq!(
+            to_esc_str(&alt_backspace, &none, true),
+            Some("\x1b\x7f".to_string())
+        );
+
+        // Otherwise, and in application cursor mode, the modified keys are sent
+        for (mode, alt_is_meta) in [(none, false), (app_cursor, true), (app_cursor, false)] {
+            assert_eq!(
+                to_esc_str(&alt_left, &mode, alt_is_meta),
+                Some("\x1b[1;3D".to_string())
+            );
+            assert_eq!(
+                to_esc_str(&alt_right, &mode, alt_is_meta),
+                Some("\x1b[1;3C".to_string())
+            );
+        }
+        assert_eq!(
+            to_esc_str(&alt_backspace, &none, false),
+            Some("\x1b\x7f".to_string())
+        );
+    }
+
+    #[test]
+    fn test_option_as_meta() {
+        // On macOS, option-a types the composed character, which comes in as the IME key
+        let alt_a = Keystroke {
+            ime_key: Some("å".to_string()),
+            ..Keystroke::parse("alt-a").unwrap()
+        };
+        let none = TermMode::NONE;
+
+        // As meta, it sends escape followed by the key instead
+        assert_eq!(to_esc_str(&alt_a, &none, true), Some("\x1ba".to_string()));
+        // Otherwise no escape is sent, and the composed character is typed as text
+        assert_eq!(to_esc_str(&alt_a, &none, false), None);
+        assert_eq!(alt_a.ime_key.as_deref(), Some("å"));
+
+        // Keys that aren't ASCII are typed as they are either way
+        let alt_e_acute = Keystroke::parse("alt-é").unwrap();
+        assert_eq!(to_esc_str(&alt_e_acute, &none, true), None);
+    }
+
     #[test]
     fn test_plain_inputs() {
         let ks = Keystroke {
@@ -378,6 +543,37 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_keypad_mode() {
+        let app_keypad = TermMode::APP_KEYPAD;
+        let none = TermMode::NONE;
+
+        let one = Keystroke::parse("kp_1").unwrap();
+        let enter = Keystroke::parse("kp_enter").unwrap();
+        let add = Keystroke::parse("kp_add").unwrap();
+
+        assert_eq!(to_esc_str(&one, &none, false), Some("1".to_string()));
+        assert_eq!(to_esc_str(&enter, &none, false), Some("\x0d".to_string()));
+        assert_eq!(to_esc_str(&add, &none, false), Some("+".to_string()));
+
+        assert_eq!(
+            to_esc_str(&one, &app_keypad, false),
+            Some("\x1bOq".to_string())
+        );
+        assert_eq!(
+            to_esc_str(&enter, &app_keypad, false),
+            Some("\x1bOM".to_string())
+        );
+        assert_eq!(
+            to_esc_str(&add, &app_keypad, false),
+            Some("\x1bOk".to_string())
+        );
+
+        // The main keyboard's keys are unaffected
+        let digit = Keystroke::parse("1").unwrap();
+        assert_eq!(to_esc_str(&digit, &app_keypad, false), None);
+    }
+
     #[test]
     fn test_ctrl_codes() {
         let letters_lower = 'a'..='z';
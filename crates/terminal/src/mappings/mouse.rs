@@ -57,8 +57,8 @@ impl AlacMouseButton {
     fn from_button(e: MouseButton) -> Self {
         match e {
             gpui::MouseButton::Left => AlacMouseButton::LeftButton,
-            gpui::MouseButton::Right => AlacMouseButton::MiddleButton,
-            gpui::MouseButton::Middle => AlacMouseButton::RightButton,
+            gpui::MouseButton::Middle => AlacMouseButton::MiddleButton,
+            gpui::MouseButton::Right => AlacMouseButton::RightButton,
             gpui::MouseButton::Navigate(_) => AlacMouseButton::Other,
         }
     }
@@ -277,3 +277,124 @@ fn sgr_mouse_report(point: AlacPoint, button: u8, pressed: bool) -> String {
 
     msg
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cell(line: i32, column: usize) -> AlacPoint {
+        AlacPoint::new(GridLine(line), GridCol(column))
+    }
+
+    #[test]
+    fn test_sgr_button_reports() {
+        let mode = TermMode::MOUSE_REPORT_CLICK | TermMode::SGR_MOUSE;
+        let report = |button, modifiers, pressed| {
+            mouse_button_report(cell(2, 4), button, modifiers, pressed, mode)
+                .map(|bytes| String::from_utf8(bytes).unwrap())
+        };
+
+        // Coordinates are 1-based, and releases end with `m`
+        assert_eq!(
+            report(MouseButton::Left, Modifiers::default(), true).as_deref(),
+            Some("\x1b[<0;5;3M")
+        );
+        assert_eq!(
+            report(MouseButton::Left, Modifiers::default(), false).as_deref(),
+            Some("\x1b[<0;5;3m")
+        );
+        assert_eq!(
+            report(MouseButton::Middle, Modifiers::default(), true).as_deref(),
+            Some("\x1b[<1;5;3M")
+        );
+        assert_eq!(
+            report(MouseButton::Right, Modifiers::default(), true).as_deref(),
+            Some("\x1b[<2;5;3M")
+        );
+        let control = Modifiers {
+            control: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            report(MouseButton::Left, control, true).as_deref(),
+            Some("\x1b[<16;5;3M")
+        );
+
+        // SGR coordinates aren't limited to what fits in a byte
+        assert_eq!(
+            mouse_button_report(
+                cell(300, 250),
+                MouseButton::Left,
+                Modifiers::default(),
+                true,
+                mode
+            ),
+            Some(b"\x1b[<0;251;301M".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_normal_button_reports() {
+        let mode = TermMode::MOUSE_REPORT_CLICK;
+        assert_eq!(
+            mouse_button_report(
+                cell(2, 4),
+                MouseButton::Left,
+                Modifiers::default(),
+                true,
+                mode
+            ),
+            Some(vec![0x1b, b'[', b'M', 32, 32 + 5, 32 + 3])
+        );
+        // Releases don't say which button was released
+        assert_eq!(
+            mouse_button_report(
+                cell(2, 4),
+                MouseButton::Left,
+                Modifiers::default(),
+                false,
+                mode
+            ),
+            Some(vec![0x1b, b'[', b'M', 32 + 3, 32 + 5, 32 + 3])
+        );
+        // Positions past 223 can't be encoded without UTF-8 or SGR
+        assert_eq!(
+            mouse_button_report(
+                cell(2, 300),
+                MouseButton::Left,
+                Modifiers::default(),
+                true,
+                mode
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_no_reports_without_mouse_mode() {
+        assert_eq!(
+            mouse_button_report(
+                cell(2, 4),
+                MouseButton::Left,
+                Modifiers::default(),
+                true,
+                TermMode::SGR_MOUSE
+            ),
+            None
+        );
+
+        let moved = MouseMoveEvent {
+            position: Point::default(),
+            pressed_button: None,
+            modifiers: Modifiers::default(),
+        };
+        // Moves without a button pressed are only reported in any-event mode (1003)
+        let mode = TermMode::MOUSE_DRAG | TermMode::SGR_MOUSE;
+        assert_eq!(mouse_moved_report(cell(0, 0), &moved, mode), None);
+        let mode = TermMode::MOUSE_MOTION | TermMode::SGR_MOUSE;
+        assert_eq!(
+            mouse_moved_report(cell(0, 0), &moved, mode),
+            Some(b"\x1b[<35;1;1M".to_vec())
+        );
+    }
+}
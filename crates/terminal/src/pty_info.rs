@@ -81,6 +81,19 @@ pub struct PtyProcessInfo {
 
 impl PtyProcessInfo {
     pub fn new(pty: &Pty) -> PtyProcessInfo {
+        Self::with_pid_getter(ProcessIdGetter::new(pty))
+    }
+
+    /// Info about a process that isn't running in a PTY, for terminals made with fake ones.
+    #[cfg(all(test, unix))]
+    pub fn for_process(pid: u32) -> PtyProcessInfo {
+        Self::with_pid_getter(ProcessIdGetter {
+            handle: -1,
+            fallback_pid: pid,
+        })
+    }
+
+    fn with_pid_getter(pid_getter: ProcessIdGetter) -> PtyProcessInfo {
         let process_refresh_kind = ProcessRefreshKind::new()
             .with_cmd(UpdateKind::Always)
             .with_cwd(UpdateKind::Always)
@@ -91,11 +104,21 @@ impl PtyProcessInfo {
         PtyProcessInfo {
             system,
             refresh_kind: process_refresh_kind,
-            pid_getter: ProcessIdGetter::new(pty),
+            pid_getter,
             current: None,
         }
     }
 
+    /// The id of the foreground process group, or of the shell if that can't be determined.
+    pub fn pid(&self) -> Option<Pid> {
+        self.pid_getter.pid()
+    }
+
+    /// The id of the process started in the terminal, usually the shell.
+    pub fn shell_pid(&self) -> u32 {
+        self.pid_getter.fallback_pid
+    }
+
     fn refresh(&mut self) -> Option<&Process> {
         let pid = self.pid_getter.pid()?;
         self.system.refresh_processes_specifics(self.refresh_kind);
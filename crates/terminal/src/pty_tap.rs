@@ -0,0 +1,1208 @@
+use alacritty_terminal::{
+    event::{Event, EventListener, OnResize, WindowSize},
+    grid::Dimensions,
+    index::Line,
+    sync::FairMutex,
+    term::TermMode,
+    tty::{ChildEvent, EventedPty, EventedReadWrite},
+    vte::{ansi::Processor, Params, Parser, Perform},
+    Term,
+};
+#[cfg(unix)]
+use lazy_static::lazy_static;
+use polling::{Event as PollingEvent, PollMode, Poller};
+use std::{
+    cmp, io, mem,
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+#[cfg(unix)]
+use std::{
+    io::{Read as _, Write as _},
+    os::unix::net::UnixStream,
+    sync::{mpsc, Weak},
+    thread,
+};
+
+/// DEC line attributes, set with `ESC # 3` to `ESC # 6`.
+/// Alacritty does not handle these, so they're picked up by the [`PtyTap`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineAttribute {
+    /// DECDWL: Every cell on the line is drawn twice as wide.
+    DoubleWidth,
+    /// DECDHL: The line shows the top half of double width, double height text.
+    DoubleHeightTop,
+    /// DECDHL: The line shows the bottom half of double width, double height text.
+    DoubleHeightBottom,
+}
+
+impl LineAttribute {
+    /// Every double sized line is twice as wide as a regular one.
+    pub const WIDTH_MULTIPLIER: f32 = 2.;
+
+    /// How many of the grid's columns fit on a line with this attribute.
+    pub fn visible_columns(columns: usize) -> usize {
+        (columns as f32 / Self::WIDTH_MULTIPLIER).floor() as usize
+    }
+}
+
+/// A shell integration mark, which shells with OSC 133 support print around their prompts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PromptMark {
+    /// `A`: The prompt starts here.
+    PromptStart,
+    /// `B`: The prompt ends and the command being typed starts here.
+    CommandStart,
+    /// `C`: The command was run, and its output starts here.
+    OutputStart,
+    /// `D`: The command finished, with the exit code if the shell reported one.
+    CommandEnd { exit_code: Option<i32> },
+}
+
+/// An escape sequence found in the PTY output that alacritty_terminal ignores.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TappedSequence {
+    /// `None` is DECSWL (`ESC # 5`), which resets the line to single width.
+    LineAttribute(Option<LineAttribute>),
+    /// The whole screen was erased or the terminal was reset, so every line is single width again.
+    ResetLineAttributes,
+    PromptMark(PromptMark),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TappedEvent {
+    pub sequence: TappedSequence,
+    /// The row the cursor was on when the sequence was parsed, see [`ScrollHistory::row`].
+    pub row: usize,
+}
+
+/// A working directory reported by the shell with OSC 7.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WorkingDirectoryReport {
+    /// The host the directory is on, which is empty for the local machine.
+    pub host: String,
+    pub path: PathBuf,
+}
+
+/// How much more history alacritty keeps than the user asked for. Once the history is full,
+/// alacritty drops a line for each one that scrolls in, which can't be told apart from nothing
+/// happening, so the history is only trimmed after the lines that scrolled in were counted.
+const HISTORY_SLACK: usize = 10_000;
+
+/// Counts the lines that scrolled off the top of the main screen, so lines can be given rows that
+/// stay the same as output scrolls them up into the history. A row counts from the first line the
+/// terminal printed, and stays valid until the line is dropped from the history.
+#[derive(Debug)]
+pub struct ScrollHistory {
+    /// The lines of history the user asked for.
+    max_lines: usize,
+    scrolled_lines: usize,
+    /// The size of the main screen's history when it was last looked at.
+    history_size: usize,
+    alt_screen: bool,
+}
+
+impl ScrollHistory {
+    pub fn new(max_lines: usize) -> Self {
+        Self {
+            max_lines,
+            scrolled_lines: 0,
+            history_size: 0,
+            alt_screen: false,
+        }
+    }
+
+    /// The history alacritty should be configured with.
+    pub fn limit(&self) -> usize {
+        self.max_lines + HISTORY_SLACK
+    }
+
+    /// The row of a line on the grid.
+    pub fn row(&self, line: Line) -> usize {
+        cmp::max(self.scrolled_lines as i32 + line.0, 0) as usize
+    }
+
+    /// The line on the grid a row is on, unless it was dropped from the history.
+    pub fn line(&self, row: usize) -> Option<Line> {
+        (row >= self.first_row()).then(|| Line(row as i32 - self.scrolled_lines as i32))
+    }
+
+    /// The row of the oldest line in the history.
+    pub fn first_row(&self) -> usize {
+        self.scrolled_lines - self.history_size
+    }
+
+    /// Counts the lines that scrolled into the history since it was last looked at.
+    pub fn update<T>(&mut self, term: &Term<T>) {
+        // The alternate screen doesn't have a history, and the main screen's doesn't change while
+        // it's hidden, other than with resizes
+        if term.mode().contains(TermMode::ALT_SCREEN) {
+            self.alt_screen = true;
+            return;
+        }
+        let history_size = term.grid().history_size();
+        if mem::take(&mut self.alt_screen) {
+            self.shift(history_size);
+        } else if history_size > self.history_size {
+            self.scrolled_lines += history_size - self.history_size;
+        }
+        // A history that shrank otherwise was cleared
+        self.history_size = history_size;
+    }
+
+    /// Follows the lines a resize moved between the screen and the history.
+    pub fn resized<T>(&mut self, term: &mut Term<T>) {
+        if !term.mode().contains(TermMode::ALT_SCREEN) {
+            self.shift(term.grid().history_size());
+            self.trim(term);
+        }
+    }
+
+    fn shift(&mut self, history_size: usize) {
+        self.scrolled_lines =
+            (self.scrolled_lines + history_size).saturating_sub(self.history_size);
+        self.history_size = history_size;
+    }
+
+    /// Whether alacritty is about to drop lines before they're counted.
+    fn is_nearly_full(&self) -> bool {
+        self.history_size > self.max_lines + HISTORY_SLACK / 2
+    }
+
+    /// Drops the oldest lines of history past those the user asked for.
+    fn trim<T>(&mut self, term: &mut Term<T>) {
+        if !self.alt_screen && self.history_size > self.max_lines {
+            let grid = term.grid_mut();
+            grid.update_history(self.max_lines);
+            grid.update_history(self.limit());
+            self.history_size = self.max_lines;
+        }
+    }
+}
+
+/// Everything the tap has seen since the terminal last drained it.
+#[derive(Debug)]
+pub struct TapState {
+    pub events: Vec<TappedEvent>,
+    pub scroll_history: ScrollHistory,
+    /// The last working directory reported, until the terminal takes it.
+    pub working_directory: Option<WorkingDirectoryReport>,
+    /// Messages programs asked to notify the user of with OSC 9, until the terminal takes them.
+    pub notifications: Vec<String>,
+}
+
+impl TapState {
+    pub fn new(max_history_lines: usize) -> Self {
+        Self {
+            events: Vec::new(),
+            scroll_history: ScrollHistory::new(max_history_lines),
+            working_directory: None,
+            notifications: Vec::new(),
+        }
+    }
+}
+
+/// Parses PTY output into alacritty's terminal, and with a parser of its own, into the sequences
+/// alacritty ignores. Both see one byte at a time, so a sequence is recorded on the row the cursor
+/// is on in the grid when it arrives.
+pub struct TapParser {
+    processor: Processor,
+    parser: Parser,
+}
+
+impl TapParser {
+    pub fn new() -> Self {
+        Self {
+            processor: Processor::new(),
+            parser: Parser::new(),
+        }
+    }
+
+    /// Parses `bytes`, returning whether any of them changed the grid, rather than being held back
+    /// by a synchronized update.
+    pub fn advance<L: EventListener>(
+        &mut self,
+        term: &mut Term<L>,
+        state: &mut TapState,
+        bytes: &[u8],
+    ) -> bool {
+        for byte in bytes {
+            self.processor.advance(term, *byte);
+            state.scroll_history.update(term);
+            self.parser.advance(
+                &mut TapPerformer {
+                    term: &mut *term,
+                    processor: &mut self.processor,
+                    state: &mut *state,
+                },
+                *byte,
+            );
+            if state.scroll_history.is_nearly_full() {
+                state.scroll_history.trim(term);
+            }
+        }
+        state.scroll_history.trim(term);
+        self.processor.sync_bytes_count() < bytes.len()
+    }
+
+    /// When a synchronized update should have ended by.
+    fn sync_deadline(&self) -> Option<Instant> {
+        self.processor.sync_timeout().sync_timeout()
+    }
+
+    /// Applies the output held back by a synchronized update.
+    fn stop_sync<L: EventListener>(&mut self, term: &mut Term<L>, state: &mut TapState) {
+        self.processor.stop_sync(term);
+        state.scroll_history.update(term);
+        state.scroll_history.trim(term);
+    }
+}
+
+struct TapPerformer<'a, L> {
+    term: &'a mut Term<L>,
+    processor: &'a mut Processor,
+    state: &'a mut TapState,
+}
+
+impl<L: EventListener> TapPerformer<'_, L> {
+    fn push(&mut self, sequence: TappedSequence) {
+        // The cursor only moves once a synchronized update ends
+        if self.processor.sync_bytes_count() > 0 {
+            self.processor.stop_sync(self.term);
+            self.state.scroll_history.update(self.term);
+        }
+        let row = self
+            .state
+            .scroll_history
+            .row(self.term.grid().cursor.point.line);
+        self.state.events.push(TappedEvent { sequence, row });
+    }
+}
+
+impl<L: EventListener> Perform for TapPerformer<'_, L> {
+    fn csi_dispatch(&mut self, params: &Params, intermediates: &[u8], _ignore: bool, action: char) {
+        // ED 2 and ED 3 erase the whole display.
+        let erases_display = params
+            .iter()
+            .next()
+            .map_or(false, |param| matches!(param, [2] | [3]));
+        if action == 'J' && intermediates.is_empty() && erases_display {
+            self.push(TappedSequence::ResetLineAttributes);
+        }
+    }
+
+    fn osc_dispatch(&mut self, params: &[&[u8]], _bell_terminated: bool) {
+        // OSC 7 ; file://host/path
+        if let [b"7", url @ ..] = params {
+            // Semicolons in the path split it into several parameters.
+            if let Some(report) = parse_file_url(&url.join(&b';')) {
+                self.state.working_directory = Some(report);
+            }
+        }
+        // OSC 9 ; message
+        if let Some(message) = parse_notification(params) {
+            self.state.notifications.push(message);
+        }
+        // OSC 133 ; A | B | C | D [; exit code]
+        if let Some(mark) = parse_prompt_mark(params) {
+            self.push(TappedSequence::PromptMark(mark));
+        }
+    }
+
+    fn esc_dispatch(&mut self, intermediates: &[u8], _ignore: bool, byte: u8) {
+        // RIS
+        if intermediates.is_empty() && byte == b'c' {
+            self.push(TappedSequence::ResetLineAttributes);
+            return;
+        }
+        if intermediates != [b'#'] {
+            return;
+        }
+        let attribute = match byte {
+            b'3' => Some(LineAttribute::DoubleHeightTop),
+            b'4' => Some(LineAttribute::DoubleHeightBottom),
+            b'5' => None,
+            b'6' => Some(LineAttribute::DoubleWidth),
+            _ => return,
+        };
+        self.push(TappedSequence::LineAttribute(attribute));
+    }
+}
+
+/// The message of an OSC 9 notification. ConEmu's OSC 9 extensions, like `9;4` for progress,
+/// start with a number and aren't notifications.
+fn parse_notification(params: &[&[u8]]) -> Option<String> {
+    let [b"9", message @ ..] = params else {
+        return None;
+    };
+    let is_extension = message.len() > 1 && message[0].iter().all(u8::is_ascii_digit);
+    let message = String::from_utf8_lossy(&message.join(&b';')).into_owned();
+    (!is_extension && !message.is_empty()).then_some(message)
+}
+
+fn parse_prompt_mark(params: &[&[u8]]) -> Option<PromptMark> {
+    let [b"133", kind, rest @ ..] = params else {
+        return None;
+    };
+    match *kind {
+        b"A" => Some(PromptMark::PromptStart),
+        b"B" => Some(PromptMark::CommandStart),
+        b"C" => Some(PromptMark::OutputStart),
+        b"D" => {
+            let exit_code = rest
+                .first()
+                .and_then(|code| std::str::from_utf8(code).ok()?.parse().ok());
+            Some(PromptMark::CommandEnd { exit_code })
+        }
+        _ => None,
+    }
+}
+
+fn parse_file_url(url: &[u8]) -> Option<WorkingDirectoryReport> {
+    let url = url.strip_prefix(b"file://")?;
+    let path_start = url.iter().position(|&byte| byte == b'/')?;
+    let host = String::from_utf8(url[..path_start].to_vec()).ok()?;
+    let path = String::from_utf8(percent_decode(&url[path_start..])).ok()?;
+    // Windows paths come as `/C:/Users/...`
+    let path = match path.strip_prefix('/') {
+        Some(windows_path) if cfg!(windows) && windows_path.get(1..2) == Some(":") => {
+            windows_path.to_string()
+        }
+        _ => path,
+    };
+    Some(WorkingDirectoryReport {
+        host,
+        path: PathBuf::from(path),
+    })
+}
+
+fn percent_decode(bytes: &[u8]) -> Vec<u8> {
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut ix = 0;
+    while ix < bytes.len() {
+        let escaped = (bytes[ix] == b'%')
+            .then(|| bytes.get(ix + 1..ix + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                ix += 3;
+            }
+            None => {
+                decoded.push(bytes[ix]);
+                ix += 1;
+            }
+        }
+    }
+    decoded
+}
+
+/// How often the budget of PTY output to read is refilled, about once per frame.
+const FRAME_INTERVAL: Duration = Duration::from_millis(16);
+
+/// Caps how much PTY output is read per frame. The terminal is locked while output is parsed,
+/// so a program flooding the terminal would otherwise keep the UI from drawing.
+#[derive(Debug)]
+struct ReadBudget {
+    max_bytes_per_frame: usize,
+    frame_start: Instant,
+    used: usize,
+}
+
+impl ReadBudget {
+    fn new(max_bytes_per_frame: usize, now: Instant) -> Self {
+        Self {
+            max_bytes_per_frame: max_bytes_per_frame.max(1),
+            frame_start: now,
+            used: 0,
+        }
+    }
+
+    /// How many bytes can still be read in the frame `now` falls in.
+    fn available(&mut self, now: Instant) -> usize {
+        if now.duration_since(self.frame_start) >= FRAME_INTERVAL {
+            self.frame_start = now;
+            self.used = 0;
+        }
+        self.max_bytes_per_frame.saturating_sub(self.used)
+    }
+
+    fn consume(&mut self, bytes: usize) {
+        self.used += bytes;
+    }
+
+    fn next_frame(&self) -> Instant {
+        self.frame_start + FRAME_INTERVAL
+    }
+}
+
+#[cfg(unix)]
+lazy_static! {
+    /// Takes the times [`Waker`]s should wake alacritty at. One thread waits for them, for
+    /// every terminal.
+    static ref WAKE_TIMER: mpsc::Sender<(Instant, Weak<UnixStream>)> = spawn_wake_timer();
+}
+
+#[cfg(unix)]
+fn spawn_wake_timer() -> mpsc::Sender<(Instant, Weak<UnixStream>)> {
+    let (deadlines_tx, deadlines_rx) = mpsc::channel::<(Instant, Weak<UnixStream>)>();
+    thread::Builder::new()
+        .name("pty-wake-timer".to_string())
+        .spawn(move || {
+            let mut pending = Vec::new();
+            loop {
+                let now = Instant::now();
+                pending.retain(|(deadline, sender): &(Instant, Weak<UnixStream>)| {
+                    if *deadline > now {
+                        return true;
+                    }
+                    // The terminal may have closed since
+                    if let Some(sender) = sender.upgrade() {
+                        (&*sender).write_all(&[0]).ok();
+                    }
+                    false
+                });
+                let received = match pending.iter().map(|(deadline, _)| *deadline).min() {
+                    Some(deadline) => {
+                        deadlines_rx.recv_timeout(deadline.saturating_duration_since(now))
+                    }
+                    None => deadlines_rx
+                        .recv()
+                        .map_err(|_| mpsc::RecvTimeoutError::Disconnected),
+                };
+                match received {
+                    Ok(deadline) => pending.push(deadline),
+                    Err(mpsc::RecvTimeoutError::Timeout) => {}
+                    Err(mpsc::RecvTimeoutError::Disconnected) => return,
+                }
+            }
+        })
+        .expect("failed to spawn the PTY wake timer");
+    deadlines_tx
+}
+
+/// Wakes alacritty's event loop when a paused [`PtyTap`] can read again, or when a synchronized
+/// update has to be ended because the program never ended it. Alacritty keeps the terminal
+/// leased for as long as it's reading, so the tap can't wait for the next frame itself: it stops
+/// the PTY from being polled instead, and a socket registered under the PTY's key becomes
+/// readable once the frame starts, which makes alacritty read again.
+#[cfg(unix)]
+struct Waker {
+    poll: Arc<Poller>,
+    /// How the PTY was registered, to register it again without read interest.
+    key: usize,
+    writable: bool,
+    mode: PollMode,
+    receiver: UnixStream,
+    /// Written to by the [`WAKE_TIMER`], which only holds on to it while it's waiting.
+    sender: Arc<UnixStream>,
+    /// The frame the waker was last asked to wake alacritty at.
+    scheduled: Option<Instant>,
+}
+
+#[cfg(unix)]
+impl Waker {
+    fn new(poll: Arc<Poller>, interest: &PollingEvent, mode: PollMode) -> io::Result<Self> {
+        let (receiver, sender) = UnixStream::pair()?;
+        receiver.set_nonblocking(true)?;
+        sender.set_nonblocking(true)?;
+        // The receiver is removed from the poller when the waker is dropped
+        unsafe { poll.add_with_mode(&receiver, PollingEvent::readable(interest.key), mode)? };
+
+        Ok(Self {
+            poll,
+            key: interest.key,
+            writable: interest.writable,
+            mode,
+            receiver,
+            sender: Arc::new(sender),
+            scheduled: None,
+        })
+    }
+
+    fn interest(&self, readable: bool) -> PollingEvent {
+        PollingEvent::new(self.key, readable, self.writable)
+    }
+
+    fn wake_at(&mut self, deadline: Instant) {
+        if self.scheduled != Some(deadline) {
+            self.scheduled = Some(deadline);
+            WAKE_TIMER
+                .send((deadline, Arc::downgrade(&self.sender)))
+                .ok();
+        }
+    }
+
+    /// Takes the wake-ups already sent, so they aren't polled again.
+    fn drain(&mut self) -> io::Result<()> {
+        let mut buf = [0; 16];
+        loop {
+            match self.receiver.read(&mut buf) {
+                Ok(0) => return Ok(()),
+                Ok(_) => {}
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => return Ok(()),
+                Err(error) => return Err(error),
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for Waker {
+    fn drop(&mut self) {
+        self.poll.delete(&self.receiver).ok();
+    }
+}
+
+/// Parses the output alacritty reads from the PTY, recording the escape sequences it doesn't
+/// support along the way. Output past the [`ReadBudget`] is left in the PTY until the next frame.
+///
+/// Alacritty's event loop only sees reads that would block: the tap parses the output into the
+/// terminal itself, so the sequences it records can be placed where the cursor was.
+pub struct PtyTap<T, L> {
+    pty: T,
+    term: Arc<FairMutex<Term<L>>>,
+    /// Tells the terminal its grid changed, like alacritty's event loop does.
+    listener: L,
+    state: Arc<FairMutex<TapState>>,
+    parser: TapParser,
+    budget: ReadBudget,
+    /// The time, which tests control.
+    now: Box<dyn Fn() -> Instant + Send>,
+    /// Whether reading stopped because the budget ran out, until the next frame.
+    paused: bool,
+    /// Resumes reading after a pause, once alacritty has registered the PTY.
+    #[cfg(unix)]
+    waker: Option<Waker>,
+}
+
+impl<T, L: EventListener> PtyTap<T, L> {
+    pub fn new(
+        pty: T,
+        term: Arc<FairMutex<Term<L>>>,
+        listener: L,
+        state: Arc<FairMutex<TapState>>,
+        max_bytes_per_frame: usize,
+    ) -> Self {
+        // Without a `Waker`, a paused PTY would be polled over and over until the next frame
+        let max_bytes_per_frame = if cfg!(unix) {
+            max_bytes_per_frame
+        } else {
+            usize::MAX
+        };
+        Self {
+            pty,
+            term,
+            listener,
+            state,
+            parser: TapParser::new(),
+            budget: ReadBudget::new(max_bytes_per_frame, Instant::now()),
+            now: Box::new(Instant::now),
+            paused: false,
+            #[cfg(unix)]
+            waker: None,
+        }
+    }
+
+    #[cfg(test)]
+    fn with_clock(mut self, now: impl Fn() -> Instant + Send + 'static) -> Self {
+        self.budget.frame_start = now();
+        self.now = Box::new(now);
+        self
+    }
+
+    /// Ends a synchronized update the program didn't end in time, showing what it held back.
+    fn end_late_sync(&mut self, now: Instant) {
+        if self
+            .parser
+            .sync_deadline()
+            .map_or(false, |deadline| deadline <= now)
+        {
+            let mut term = self.term.lock_unfair();
+            self.parser.stop_sync(&mut *term, &mut *self.state.lock());
+            self.listener.send_event(Event::Wakeup);
+        }
+    }
+}
+
+impl<T: EventedReadWrite, L> PtyTap<T, L> {
+    /// Stops alacritty from polling the PTY until the next frame.
+    fn pause_reading(&mut self) -> io::Result<()> {
+        #[cfg(unix)]
+        if let Some(waker) = &mut self.waker {
+            if !self.paused {
+                self.pty
+                    .reregister(&waker.poll, waker.interest(false), waker.mode)?;
+            }
+            waker.wake_at(self.budget.next_frame());
+        }
+        self.paused = true;
+        Ok(())
+    }
+
+    fn resume_reading(&mut self) -> io::Result<()> {
+        #[cfg(unix)]
+        if let Some(waker) = &mut self.waker {
+            self.pty
+                .reregister(&waker.poll, waker.interest(true), waker.mode)?;
+        }
+        self.paused = false;
+        Ok(())
+    }
+}
+
+impl<T: EventedReadWrite, L: EventListener> io::Read for PtyTap<T, L> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        #[cfg(unix)]
+        if let Some(waker) = &mut self.waker {
+            waker.drain()?;
+        }
+        let now = (self.now)();
+        self.end_late_sync(now);
+
+        let available = self.budget.available(now);
+        if available == 0 {
+            // Alacritty goes back to polling, and releases the terminal until the next frame
+            self.pause_reading()?;
+            return Err(io::ErrorKind::WouldBlock.into());
+        }
+        if self.paused {
+            self.resume_reading()?;
+        }
+
+        let len = buf.len().min(available);
+        let read = self.pty.reader().read(&mut buf[..len])?;
+        if read == 0 {
+            return Ok(0);
+        }
+        self.budget.consume(read);
+        let changed = {
+            let mut term = self.term.lock_unfair();
+            self.parser
+                .advance(&mut *term, &mut *self.state.lock(), &buf[..read])
+        };
+        if changed {
+            self.listener.send_event(Event::Wakeup);
+        }
+        #[cfg(unix)]
+        if let (Some(waker), Some(deadline)) = (&mut self.waker, self.parser.sync_deadline()) {
+            waker.wake_at(deadline);
+        }
+        // There's nothing left for alacritty to parse
+        Err(io::ErrorKind::WouldBlock.into())
+    }
+}
+
+impl<T: EventedReadWrite, L: EventListener> EventedReadWrite for PtyTap<T, L> {
+    type Reader = Self;
+    type Writer = T::Writer;
+
+    unsafe fn register(
+        &mut self,
+        poll: &Arc<Poller>,
+        interest: PollingEvent,
+        mode: PollMode,
+    ) -> io::Result<()> {
+        #[cfg(unix)]
+        {
+            self.waker = Some(Waker::new(poll.clone(), &interest, mode)?);
+        }
+        self.pty.register(poll, interest, mode)
+    }
+
+    fn reregister(
+        &mut self,
+        poll: &Arc<Poller>,
+        mut interest: PollingEvent,
+        mode: PollMode,
+    ) -> io::Result<()> {
+        #[cfg(unix)]
+        if let Some(waker) = &mut self.waker {
+            waker.writable = interest.writable;
+        }
+        // Alacritty registers the PTY again when it has input to write, which mustn't end a pause
+        interest.readable &= !self.paused;
+        self.pty.reregister(poll, interest, mode)
+    }
+
+    fn deregister(&mut self, poll: &Arc<Poller>) -> io::Result<()> {
+        #[cfg(unix)]
+        {
+            self.waker = None;
+        }
+        self.pty.deregister(poll)
+    }
+
+    fn reader(&mut self) -> &mut Self::Reader {
+        self
+    }
+
+    fn writer(&mut self) -> &mut Self::Writer {
+        self.pty.writer()
+    }
+}
+
+impl<T: EventedPty, L: EventListener> EventedPty for PtyTap<T, L> {
+    fn next_child_event(&mut self) -> Option<ChildEvent> {
+        self.pty.next_child_event()
+    }
+}
+
+impl<T: OnResize, L> OnResize for PtyTap<T, L> {
+    fn on_resize(&mut self, window_size: WindowSize) {
+        self.pty.on_resize(window_size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{grid_snapshot, term_with, TerminalSize};
+    use alacritty_terminal::{event::VoidListener, index::Column, term::Config};
+    use gpui::{px, size};
+    #[cfg(unix)]
+    use polling::Events;
+    use std::{io::Read as _, sync::Mutex};
+
+    /// A terminal 10 columns wide and 5 lines high, keeping `max_history_lines` of history.
+    fn small_term(max_history_lines: usize) -> (Term<VoidListener>, TapState) {
+        let state = TapState::new(max_history_lines);
+        let mut term = term_with(b"", 10, 5);
+        term.set_options(Config {
+            scrolling_history: state.scroll_history.limit(),
+            ..Config::default()
+        });
+        (term, state)
+    }
+
+    fn tap(bytes: &[u8]) -> TapState {
+        let (mut term, mut state) = small_term(100);
+        TapParser::new().advance(&mut term, &mut state, bytes);
+        state
+    }
+
+    fn marks(state: &TapState) -> Vec<(usize, PromptMark)> {
+        state
+            .events
+            .iter()
+            .map(|event| match &event.sequence {
+                TappedSequence::PromptMark(mark) => (event.row, *mark),
+                sequence => panic!("unexpected sequence {sequence:?}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_line_attributes() {
+        let state = tap(b"\x1b#6wide\r\n\x1b#3tall\r\n\x1b#4tall\r\n\x1b#5narrow");
+        assert_eq!(
+            state.events,
+            vec![
+                TappedEvent {
+                    sequence: TappedSequence::LineAttribute(Some(LineAttribute::DoubleWidth)),
+                    row: 0,
+                },
+                TappedEvent {
+                    sequence: TappedSequence::LineAttribute(Some(LineAttribute::DoubleHeightTop)),
+                    row: 1,
+                },
+                TappedEvent {
+                    sequence: TappedSequence::LineAttribute(Some(
+                        LineAttribute::DoubleHeightBottom
+                    )),
+                    row: 2,
+                },
+                TappedEvent {
+                    sequence: TappedSequence::LineAttribute(None),
+                    row: 3,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_decaln_is_not_a_line_attribute() {
+        assert!(tap(b"\x1b#8").events.is_empty());
+    }
+
+    #[test]
+    fn test_erasing_the_display_resets_line_attributes() {
+        for bytes in [&b"\x1b[2J"[..], b"\x1b[3J", b"\x1bc"] {
+            assert_eq!(
+                tap(bytes).events,
+                vec![TappedEvent {
+                    sequence: TappedSequence::ResetLineAttributes,
+                    row: 0,
+                }]
+            );
+        }
+        assert!(tap(b"\x1b[J").events.is_empty());
+    }
+
+    #[test]
+    fn test_working_directory() {
+        let state = tap(b"\x1b]7;file://laptop/home/me/My%20Project\x07$ ");
+        assert_eq!(
+            state.working_directory,
+            Some(WorkingDirectoryReport {
+                host: "laptop".to_string(),
+                path: PathBuf::from("/home/me/My Project"),
+            })
+        );
+
+        let state = tap(b"\x1b]7;file:///tmp/a;b\x1b\\");
+        assert_eq!(
+            state.working_directory.map(|report| report.path),
+            Some(PathBuf::from("/tmp/a;b"))
+        );
+
+        assert_eq!(tap(b"\x1b]7;/tmp\x07").working_directory, None);
+        assert_eq!(tap(b"\x1b]2;file:///tmp\x07").working_directory, None);
+    }
+
+    #[test]
+    fn test_notifications() {
+        let state = tap(b"\x1b]9;Build finished\x07$ \x1b]9;tests: 3; failures: 0\x1b\\");
+        assert_eq!(
+            state.notifications,
+            vec![
+                "Build finished".to_string(),
+                "tests: 3; failures: 0".to_string()
+            ]
+        );
+
+        assert!(tap(b"\x1b]9;4;1;50\x07").notifications.is_empty());
+        assert!(tap(b"\x1b]9;\x07").notifications.is_empty());
+        assert!(tap(b"\x1b]99;hi\x07").notifications.is_empty());
+    }
+
+    #[test]
+    fn test_prompt_marks() {
+        let state = tap(
+            b"\x1b]133;A\x07$ \x1b]133;B\x07ls\r\n\x1b]133;C\x07a\r\nb\r\n\x1b]133;D;0\x07\
+            \x1b]133;A;cl=m\x1b\\$ \x1b]133;B\x1b\\false\r\n\x1b]133;C\x07\x1b]133;D;1\x07\x1b]133;D\x07",
+        );
+        assert_eq!(
+            marks(&state),
+            vec![
+                (0, PromptMark::PromptStart),
+                (0, PromptMark::CommandStart),
+                (1, PromptMark::OutputStart),
+                (3, PromptMark::CommandEnd { exit_code: Some(0) }),
+                (3, PromptMark::PromptStart),
+                (3, PromptMark::CommandStart),
+                (4, PromptMark::OutputStart),
+                (4, PromptMark::CommandEnd { exit_code: Some(1) }),
+                (4, PromptMark::CommandEnd { exit_code: None }),
+            ]
+        );
+
+        assert!(tap(b"\x1b]133;Z\x07\x1b]133\x07").events.is_empty());
+    }
+
+    #[test]
+    fn test_prompt_marks_follow_the_cursor() {
+        // Output wrapping onto three lines, a prompt redrawn over itself, and one printed after
+        // moving the cursor
+        let state = tap(
+            b"\x1b]133;A\x07$ \x1b]133;C\x07abcdefghijklmnopqrstuvwxy\r\n\
+            \x1b]133;A\x07$ \r\x1b]133;A\x07$ \x1b[5;1H\x1b]133;A\x07$ ",
+        );
+        assert_eq!(
+            marks(&state),
+            vec![
+                (0, PromptMark::PromptStart),
+                (0, PromptMark::OutputStart),
+                (3, PromptMark::PromptStart),
+                (3, PromptMark::PromptStart),
+                (4, PromptMark::PromptStart),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rows_stay_on_their_lines_once_the_history_is_full() {
+        let (mut term, mut state) = small_term(3);
+        let mut parser = TapParser::new();
+        // Some prompts are parsed in the same read, and some alone
+        let output = (0..20)
+            .map(|ix| format!("\x1b]133;A\x07{ix}\r\n"))
+            .collect::<String>();
+        let (first, rest) = output.as_bytes().split_at(output.len() / 2);
+        parser.advance(&mut term, &mut state, first);
+        for byte in rest {
+            parser.advance(&mut term, &mut state, &[*byte]);
+        }
+
+        assert_eq!(term.grid().history_size(), 3);
+        let history = &state.scroll_history;
+        let snapshot = grid_snapshot(&term, 3);
+        let mut kept = Vec::new();
+        for (ix, (row, _)) in marks(&state).into_iter().enumerate() {
+            assert_eq!(row, ix);
+            if let Some(line) = history.line(row) {
+                assert_eq!(snapshot.line_text((line.0 + 3) as usize), ix.to_string());
+                kept.push(ix);
+            }
+        }
+        // The 5 lines of the screen show the last 4 prompts and the cursor's empty line
+        assert_eq!(kept, (13..20).collect::<Vec<_>>());
+        assert_eq!(history.first_row(), 13);
+        assert_eq!(history.row(Line(-3)), 13);
+    }
+
+    #[test]
+    fn test_rows_follow_resizes() {
+        let (mut term, mut state) = small_term(100);
+        let mut parser = TapParser::new();
+        parser.advance(
+            &mut term,
+            &mut state,
+            b"a\r\nb\r\nc\r\nd\r\n\x1b]133;A\x07e",
+        );
+        assert_eq!(marks(&state), vec![(4, PromptMark::PromptStart)]);
+
+        // Losing two lines moves the top ones into the history
+        term.resize(TerminalSize::new(px(10.), px(10.), size(px(100.), px(30.))));
+        state.scroll_history.resized(&mut term);
+        assert_eq!(state.scroll_history.line(4), Some(Line(2)));
+
+        // Resizing on the alternate screen moves the main screen's lines too
+        parser.advance(&mut term, &mut state, b"\x1b[?1049h");
+        term.resize(TerminalSize::new(px(10.), px(10.), size(px(100.), px(50.))));
+        state.scroll_history.resized(&mut term);
+        parser.advance(&mut term, &mut state, b"\x1b[?1049l");
+        let line = state.scroll_history.line(4).unwrap();
+        assert_eq!(term.grid()[line][Column(0)].c, 'e');
+    }
+
+    #[test]
+    fn test_percent_decode() {
+        assert_eq!(percent_decode(b"/a%20b/%e2%9c%93"), "/a b/✓".as_bytes());
+        assert_eq!(percent_decode(b"100%"), b"100%");
+        assert_eq!(percent_decode(b"%zz"), b"%zz");
+    }
+
+    #[test]
+    fn test_read_budget() {
+        let start = Instant::now();
+        let mut budget = ReadBudget::new(100, start);
+        assert_eq!(budget.available(start), 100);
+        budget.consume(60);
+        assert_eq!(budget.available(start + Duration::from_millis(5)), 40);
+        budget.consume(40);
+        assert_eq!(budget.available(start + Duration::from_millis(10)), 0);
+        assert_eq!(budget.next_frame(), start + FRAME_INTERVAL);
+        assert_eq!(budget.available(start + FRAME_INTERVAL), 100);
+
+        // A budget of nothing would never read anything
+        assert_eq!(ReadBudget::new(0, start).available(start), 1);
+    }
+
+    /// Output that's read at most `chunk_size` bytes at a time.
+    struct Trickle {
+        bytes: io::Cursor<Vec<u8>>,
+        chunk_size: usize,
+    }
+
+    impl io::Read for Trickle {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let len = buf.len().min(self.chunk_size);
+            self.bytes.read(&mut buf[..len])
+        }
+    }
+
+    struct FakePty {
+        output: Trickle,
+        input: io::Sink,
+    }
+
+    impl FakePty {
+        fn new(output: &[u8], chunk_size: usize) -> Self {
+            Self {
+                output: Trickle {
+                    bytes: io::Cursor::new(output.to_vec()),
+                    chunk_size,
+                },
+                input: io::sink(),
+            }
+        }
+    }
+
+    impl EventedReadWrite for FakePty {
+        type Reader = Trickle;
+        type Writer = io::Sink;
+
+        unsafe fn register(
+            &mut self,
+            _: &Arc<Poller>,
+            _: PollingEvent,
+            _: PollMode,
+        ) -> io::Result<()> {
+            Ok(())
+        }
+
+        fn reregister(&mut self, _: &Arc<Poller>, _: PollingEvent, _: PollMode) -> io::Result<()> {
+            Ok(())
+        }
+
+        fn deregister(&mut self, _: &Arc<Poller>) -> io::Result<()> {
+            Ok(())
+        }
+
+        fn reader(&mut self) -> &mut Self::Reader {
+            &mut self.output
+        }
+
+        fn writer(&mut self) -> &mut Self::Writer {
+            &mut self.input
+        }
+    }
+
+    /// A time that only passes when the test says so.
+    #[derive(Clone)]
+    struct FakeClock(Arc<Mutex<Instant>>);
+
+    impl FakeClock {
+        fn now(&self) -> Instant {
+            *self.0.lock().unwrap()
+        }
+
+        fn advance(&self, duration: Duration) {
+            *self.0.lock().unwrap() += duration;
+        }
+    }
+
+    type FakeTap = PtyTap<FakePty, VoidListener>;
+
+    fn fake_tap(
+        output: &[u8],
+        chunk_size: usize,
+        max_bytes_per_frame: usize,
+    ) -> (FakeTap, FakeClock) {
+        let clock = FakeClock(Arc::new(Mutex::new(Instant::now())));
+        let (term, state) = small_term(10_000);
+        let tap = PtyTap::new(
+            FakePty::new(output, chunk_size),
+            Arc::new(FairMutex::new(term)),
+            VoidListener,
+            Arc::new(FairMutex::new(state)),
+            max_bytes_per_frame,
+        );
+        let now = clock.clone();
+        (tap.with_clock(move || now.now()), clock)
+    }
+
+    /// Reads everything through the tap, like alacritty does, counting the times it paused.
+    /// The next frame starts at each pause.
+    fn read_all(tap: &mut FakeTap, clock: &FakeClock) -> usize {
+        let mut pauses = 0;
+        loop {
+            let mut buf = [0; 4096];
+            match tap.read(&mut buf) {
+                Ok(0) => return pauses,
+                Ok(read) => panic!("{read} bytes were left for alacritty to parse"),
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => {
+                    if tap.paused {
+                        pauses += 1;
+                        clock.advance(FRAME_INTERVAL);
+                    }
+                }
+                Err(error) => panic!("{error}"),
+            }
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_bursts_are_read_across_frames() {
+        let burst = b"y\r\n".repeat(500);
+        let (mut tap, clock) = fake_tap(&burst, usize::MAX, 800);
+
+        assert_eq!(read_all(&mut tap, &clock), 2);
+        // Every line was parsed, with 5 of them on the screen
+        assert_eq!(tap.term.lock().grid().history_size(), 496);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_paused_reading_resumes_at_the_next_frame() {
+        let (mut tap, clock) = fake_tap(&[b'y'; 150], usize::MAX, 100);
+        let poll = Arc::new(Poller::new().unwrap());
+        unsafe {
+            tap.register(&poll, PollingEvent::readable(0), PollMode::Level)
+                .unwrap()
+        };
+        let frame_start = clock.now();
+
+        let mut buf = [0; 4096];
+        let mut read = |tap: &mut FakeTap| {
+            let error = tap.read(&mut buf).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::WouldBlock);
+            tap.pty.output.bytes.position()
+        };
+        assert_eq!(read(&mut tap), 100);
+        assert!(!tap.paused);
+
+        // Nothing is read until the next frame, when alacritty is woken up
+        clock.advance(FRAME_INTERVAL / 2);
+        assert_eq!(read(&mut tap), 100);
+        assert!(tap.paused);
+        let waker = tap.waker.as_ref().unwrap();
+        assert_eq!(waker.scheduled, Some(frame_start + FRAME_INTERVAL));
+
+        clock.advance(FRAME_INTERVAL / 2);
+        assert_eq!(read(&mut tap), 150);
+        assert!(!tap.paused);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_waker_wakes_the_poller() {
+        let poll = Arc::new(Poller::new().unwrap());
+        let key = 7;
+        let mut waker =
+            Waker::new(poll.clone(), &PollingEvent::readable(key), PollMode::Level).unwrap();
+        let mut events = Events::new();
+
+        waker.wake_at(Instant::now());
+        poll.wait(&mut events, Some(Duration::from_secs(10)))
+            .unwrap();
+        assert!(events
+            .iter()
+            .any(|event| event.key == key && event.readable));
+
+        // Once drained, it isn't polled again
+        waker.drain().unwrap();
+        events.clear();
+        poll.wait(&mut events, Some(Duration::ZERO)).unwrap();
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn test_split_utf8_is_rendered() {
+        // Read one byte at a time, with frames ending inside characters
+        let output = "é🦀x\r\na".as_bytes().iter().copied();
+        let output = output.chain(*b"\xffb\xffc").collect::<Vec<_>>();
+        let (mut tap, clock) = fake_tap(&output, 1, 3);
+
+        let pauses = read_all(&mut tap, &clock);
+        // Other platforms don't have a budget
+        assert_eq!(pauses > 0, cfg!(unix));
+
+        let snapshot = grid_snapshot(&tap.term.lock(), 0);
+        assert_eq!(snapshot.line_text(0), "é🦀x");
+        assert_eq!(snapshot.lines[0][1].c, '🦀');
+        // Bytes that aren't UTF-8 show up as replacement characters, and the text after them is
+        // still read
+        assert_eq!(snapshot.line_text(1), "a\u{fffd}b\u{fffd}c");
+    }
+
+    #[test]
+    fn test_double_width_visible_columns() {
+        assert_eq!(LineAttribute::visible_columns(80), 40);
+        assert_eq!(LineAttribute::visible_columns(81), 40);
+    }
+}
@@ -0,0 +1,294 @@
+use alacritty_terminal::{
+    grid::Dimensions,
+    index::{Column, Line},
+    selection::SelectionRange,
+    term::cell::{Cell, Flags},
+    vte::ansi::{Color, NamedColor},
+    Term,
+};
+use gpui::{Hsla, Rgba};
+use theme::Theme;
+
+use crate::terminal_settings::TerminalColors;
+
+/// How to keep the colors and attributes of copied text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CopyFormat {
+    /// An HTML fragment, with the styles inline.
+    Html,
+    /// Text with the escape sequences that would print it the same way in a terminal.
+    Ansi,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+struct Style {
+    fg: Color,
+    bg: Color,
+    flags: Flags,
+}
+
+impl Style {
+    const FLAGS: Flags = Flags::BOLD.union(Flags::ITALIC).union(Flags::UNDERLINE);
+
+    fn of(cell: &Cell) -> Self {
+        Self {
+            fg: cell.fg,
+            bg: cell.bg,
+            flags: cell.flags & Self::FLAGS,
+        }
+    }
+
+    fn is_default(&self) -> bool {
+        self.fg == Color::Named(NamedColor::Foreground)
+            && self.bg == Color::Named(NamedColor::Background)
+            && self.flags.is_empty()
+    }
+}
+
+/// The selected text in `format`, or `None` if nothing is selected. Like plain copies, the
+/// whitespace at the ends of lines is left out.
+pub(crate) fn styled_selection<T>(
+    term: &Term<T>,
+    format: CopyFormat,
+    colors: &TerminalColors,
+    theme: &Theme,
+) -> Option<String> {
+    let range = term.selection.as_ref()?.to_range(term)?;
+    Some(styled_range(term, range, format, colors, theme))
+}
+
+/// The text in `range` in `format`, with the whitespace at the ends of lines left out.
+pub(crate) fn styled_range<T>(
+    term: &Term<T>,
+    range: SelectionRange,
+    format: CopyFormat,
+    colors: &TerminalColors,
+    theme: &Theme,
+) -> String {
+    let grid = term.grid();
+
+    let mut lines = Vec::new();
+    for line in range.start.line.0..=range.end.line.0 {
+        let line = Line(line);
+        let (start, end) = if range.is_block {
+            (range.start.column, range.end.column)
+        } else {
+            (
+                if line == range.start.line {
+                    range.start.column
+                } else {
+                    Column(0)
+                },
+                if line == range.end.line {
+                    range.end.column
+                } else {
+                    term.last_column()
+                },
+            )
+        };
+
+        let mut runs: Vec<(Style, String)> = Vec::new();
+        for column in start.0..=end.0 {
+            let cell = &grid[line][Column(column)];
+            if cell
+                .flags
+                .intersects(Flags::WIDE_CHAR_SPACER | Flags::LEADING_WIDE_CHAR_SPACER)
+            {
+                continue;
+            }
+            let style = Style::of(cell);
+            match runs.last_mut() {
+                Some((run_style, text)) if *run_style == style => text.push(cell.c),
+                _ => runs.push((style, cell.c.to_string())),
+            }
+        }
+        while let Some((_, text)) = runs.last_mut() {
+            let trimmed_len = text.trim_end().len();
+            text.truncate(trimmed_len);
+            if !text.is_empty() {
+                break;
+            }
+            runs.pop();
+        }
+
+        let wraps = !range.is_block
+            && line != range.end.line
+            && grid[line][term.last_column()]
+                .flags
+                .contains(Flags::WRAPLINE);
+        lines.push((runs, wraps));
+    }
+
+    let mut output = String::new();
+    if format == CopyFormat::Html {
+        output.push_str("<pre>");
+    }
+    for (runs, wraps) in lines {
+        for (style, text) in runs {
+            match format {
+                CopyFormat::Html => push_html_run(&mut output, style, &text, colors, theme),
+                CopyFormat::Ansi => push_ansi_run(&mut output, style, &text),
+            }
+        }
+        if !wraps {
+            output.push('\n');
+        }
+    }
+    output.truncate(output.trim_end_matches('\n').len());
+    match format {
+        CopyFormat::Html => output.push_str("</pre>"),
+        CopyFormat::Ansi => output.push_str("\x1b[0m"),
+    }
+    output
+}
+
+fn push_html_run(
+    output: &mut String,
+    style: Style,
+    text: &str,
+    colors: &TerminalColors,
+    theme: &Theme,
+) {
+    let text = html_escape(text);
+    if style.is_default() {
+        output.push_str(&text);
+        return;
+    }
+
+    let mut declarations = Vec::new();
+    if style.fg != Color::Named(NamedColor::Foreground) {
+        let color = css_color(resolve_color(style.fg, colors, theme));
+        declarations.push(format!("color: {color}"));
+    }
+    if style.bg != Color::Named(NamedColor::Background) {
+        let color = css_color(resolve_color(style.bg, colors, theme));
+        declarations.push(format!("background-color: {color}"));
+    }
+    if style.flags.contains(Flags::BOLD) {
+        declarations.push("font-weight: bold".to_string());
+    }
+    if style.flags.contains(Flags::ITALIC) {
+        declarations.push("font-style: italic".to_string());
+    }
+    if style.flags.contains(Flags::UNDERLINE) {
+        declarations.push("text-decoration: underline".to_string());
+    }
+    output.push_str(&format!(
+        "<span style=\"{}\">{text}</span>",
+        declarations.join("; ")
+    ));
+}
+
+fn push_ansi_run(output: &mut String, style: Style, text: &str) {
+    let mut parameters = vec!["0".to_string()];
+    if style.flags.contains(Flags::BOLD) {
+        parameters.push("1".to_string());
+    }
+    if style.flags.contains(Flags::ITALIC) {
+        parameters.push("3".to_string());
+    }
+    if style.flags.contains(Flags::UNDERLINE) {
+        parameters.push("4".to_string());
+    }
+    parameters.extend(sgr_color(style.fg, 30));
+    parameters.extend(sgr_color(style.bg, 40));
+    output.push_str(&format!("\x1b[{}m{text}", parameters.join(";")));
+}
+
+/// The SGR parameter selecting `color`, where `base` is 30 for the foreground and 40 for the
+/// background. The default colors, and those only Alacritty has, are left to the terminal.
+fn sgr_color(color: Color, base: u8) -> Option<String> {
+    match color {
+        Color::Named(named) => match named as usize {
+            index @ 0..=7 => Some((base as usize + index).to_string()),
+            index @ 8..=15 => Some((base as usize + 60 + index - 8).to_string()),
+            _ => None,
+        },
+        Color::Indexed(index) => Some(format!("{};5;{index}", base + 8)),
+        Color::Spec(rgb) => Some(format!("{};2;{};{};{}", base + 8, rgb.r, rgb.g, rgb.b)),
+    }
+}
+
+fn resolve_color(color: Color, colors: &TerminalColors, theme: &Theme) -> Hsla {
+    match color {
+        Color::Named(named) => colors.color_at_index(named as usize, theme),
+        Color::Indexed(index) => colors.color_at_index(index as usize, theme),
+        Color::Spec(rgb) => crate::rgba_color(rgb.r, rgb.g, rgb.b),
+    }
+}
+
+fn css_color(color: Hsla) -> String {
+    let Rgba { r, g, b, .. } = color.into();
+    let channel = |value: f32| (value * 255.).round() as u8;
+    format!("#{:02x}{:02x}{:02x}", channel(r), channel(g), channel(b))
+}
+
+fn html_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use alacritty_terminal::{
+        event::VoidListener,
+        index::{Point as AlacPoint, Side},
+        selection::{Selection, SelectionType},
+    };
+
+    use super::*;
+    use crate::term_with;
+
+    fn select_lines(term: &mut Term<VoidListener>, first: i32, last: i32) {
+        let mut selection = Selection::new(
+            SelectionType::Lines,
+            AlacPoint::new(Line(first), Column(0)),
+            Side::Left,
+        );
+        selection.update(AlacPoint::new(Line(last), Column(0)), Side::Right);
+        term.selection = Some(selection);
+    }
+
+    fn red_colors() -> TerminalColors {
+        TerminalColors::parse(
+            &[("red".to_string(), "#ff0000".to_string())]
+                .into_iter()
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_bold_red_cell_as_html() {
+        let mut term = term_with(b"\x1b[1;31mx\x1b[0m <y>", 20, 5);
+        let theme = Theme::default();
+        assert_eq!(
+            styled_selection(&term, CopyFormat::Html, &red_colors(), &theme),
+            None
+        );
+
+        select_lines(&mut term, 0, 0);
+        assert_eq!(
+            styled_selection(&term, CopyFormat::Html, &red_colors(), &theme).as_deref(),
+            Some("<pre><span style=\"color: #ff0000; font-weight: bold\">x</span> &lt;y&gt;</pre>")
+        );
+    }
+
+    #[test]
+    fn test_styled_lines_as_ansi() {
+        let mut term = term_with(b"\x1b[3;38;5;208mone\x1b[0m  \r\n\x1b[42mtwo\x1b[0m", 20, 5);
+        select_lines(&mut term, 0, 1);
+        assert_eq!(
+            styled_selection(&term, CopyFormat::Ansi, &red_colors(), &Theme::default()).as_deref(),
+            Some("\x1b[0;3;38;5;208mone\n\x1b[0;42mtwo\x1b[0m")
+        );
+    }
+}
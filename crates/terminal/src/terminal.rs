@@ -1,24 +1,29 @@
+pub mod env_file;
 pub mod mappings;
 
 pub use alacritty_terminal;
 
+mod local_echo;
 mod pty_info;
+mod pty_tap;
+mod styled_copy;
 pub mod terminal_settings;
 
 use alacritty_terminal::{
-    event::{Event as AlacTermEvent, EventListener, Notify, WindowSize},
+    event::{Event as AlacTermEvent, EventListener, Notify, OnResize, WindowSize},
     event_loop::{EventLoop, Msg, Notifier},
     grid::{Dimensions, Scroll as AlacScroll},
-    index::{Boundary, Column, Direction as AlacDirection, Line, Point as AlacPoint},
+    index::{Boundary, Column, Direction as AlacDirection, Line, Point as AlacPoint, Side},
     selection::{Selection, SelectionRange, SelectionType},
     sync::FairMutex,
     term::{
-        cell::Cell,
+        cell::{Cell, Flags},
         search::{Match, RegexIter, RegexSearch},
         Config, RenderableCursor, TermMode,
     },
-    tty::{self, setup_env},
-    vte::ansi::{ClearMode, Handler, NamedPrivateMode, PrivateMode, Rgb},
+    tty::{self, setup_env, EventedPty},
+    vi_mode::ViMotion,
+    vte::ansi::{ClearMode, CursorStyle, Handler, NamedPrivateMode, PrivateMode, Rgb},
     Term,
 };
 use anyhow::{bail, Result};
@@ -35,36 +40,68 @@ use mappings::mouse::{
 
 use collections::{HashMap, VecDeque};
 use futures::StreamExt;
-use pty_info::PtyProcessInfo;
+use lazy_static::lazy_static;
+use local_echo::LocalEcho;
+use pty_info::{ProcessInfo, PtyProcessInfo};
+pub use pty_tap::{LineAttribute, PromptMark};
+use pty_tap::{PtyTap, ScrollHistory, TapState, TappedSequence};
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use settings::Settings;
 use smol::channel::{Receiver, Sender};
+pub use styled_copy::CopyFormat;
+use styled_copy::{styled_range, styled_selection};
 use task::{RevealStrategy, TaskId};
-use terminal_settings::{AlternateScroll, Shell, TerminalBlink, TerminalSettings};
-use theme::{ActiveTheme, Theme};
-use util::truncate_and_trailoff;
+use terminal_settings::{
+    AlternateScroll, ClearBehavior, CursorShape, EnterAfterExit, OnExit, Shell, TerminalBlink,
+    TerminalSettings, TitleTruncation,
+};
+use theme::{ActiveTheme, Theme, ThemeRegistry};
+use util::{paths::HOME, truncate_and_trailoff};
 
 use std::{
     cmp::{self, min},
+    env,
     fmt::Display,
-    ops::{Deref, Index, RangeInclusive},
-    path::PathBuf,
+    mem,
+    ops::{Deref, Index, Range, RangeInclusive},
+    path::{Path, PathBuf},
     sync::Arc,
     time::Duration,
 };
 use thiserror::Error;
 
 use gpui::{
-    actions, black, px, AnyWindowHandle, AppContext, Bounds, ClipboardItem, EventEmitter, Hsla,
-    Keystroke, ModelContext, Modifiers, MouseButton, MouseDownEvent, MouseMoveEvent, MouseUpEvent,
-    Pixels, Point, Rgba, ScrollWheelEvent, Size, Task, TouchPhase,
+    actions, black, px, AnyWindowHandle, AppContext, AsyncAppContext, BackgroundExecutor, Bounds,
+    ClipboardItem, EventEmitter, Hsla, Keystroke, Model, ModelContext, Modifiers, MouseButton,
+    MouseDownEvent, MouseMoveEvent, MouseUpEvent, Pixels, Point, Rgba, ScrollWheelEvent, Size,
+    Task, TouchPhase,
 };
 
 use crate::mappings::{colors::to_alac_rgb, keys::to_esc_str};
 
 actions!(
     terminal,
-    [Clear, Copy, Paste, ShowCharacterPalette, SearchTest,]
+    [
+        Clear,
+        Copy,
+        CopyAsHtml,
+        CopyAsAnsi,
+        Paste,
+        ShowCharacterPalette,
+        SearchTest,
+        ScrollLineUp,
+        ScrollLineDown,
+        ScrollPageUp,
+        ScrollPageDown,
+        ScrollToTop,
+        ScrollToBottom,
+        JumpToPreviousPrompt,
+        JumpToNextPrompt,
+        CopyLastCommandOutput,
+        ClearScrollback,
+        ToggleCopyMode,
+    ]
 );
 
 ///Scrolling is unbearably sluggish by default. Alacritty supports a configurable
@@ -75,25 +112,96 @@ const SCROLL_MULTIPLIER: f32 = 4.;
 #[cfg(not(target_os = "macos"))]
 const SCROLL_MULTIPLIER: f32 = 1.;
 const MAX_SEARCH_LINES: usize = 100;
+const MAX_TITLE_CHARS: usize = 25;
+/// The most characters of a title set with OSC 0 or 2 that are kept.
+const MAX_OSC_TITLE_CHARS: usize = 256;
+const PTY_RESIZE_DEBOUNCE: Duration = Duration::from_millis(50);
+/// The most text a program can put on the clipboard with a single OSC 52 sequence.
+const MAX_CLIPBOARD_WRITE_LEN: usize = 1024 * 1024;
 const DEBUG_TERMINAL_WIDTH: Pixels = px(500.);
 const DEBUG_TERMINAL_HEIGHT: Pixels = px(30.);
 const DEBUG_CELL_WIDTH: Pixels = px(5.);
 const DEBUG_LINE_HEIGHT: Pixels = px(5.);
 
+const URL_REGEX: &str = r#"(ipfs:|ipns:|magnet:|mailto:|gemini://|gopher://|https://|http://|news:|file://|git://|ssh:|ftp://)[^\u{0000}-\u{001F}\u{007F}-\u{009F}<>"\s{-}\^⟨⟩`]+"#;
+/// Words that might be paths, including Windows ones like `C:\src\main.rs:1:2`.
+const WORD_REGEX: &str = r#"[\$\+\w.\[\]:/\\@\-~]+"#;
+/// A stack frame like `at main (/src/app.js:10:5)`, whose location can have spaces in it.
+const STACK_FRAME_REGEX: &str = r#"at [^()\s]+ \([^()]+:[0-9]+:[0-9]+\)"#;
+
 ///Upward flowing events, for changing the title and such
 #[derive(Clone, Debug)]
 pub enum Event {
     TitleChanged,
+    /// A program set the title with an OSC sequence, carrying the new title. It's empty when the
+    /// title was reset.
+    TitleChangedTo(String),
     BreadcrumbsChanged,
     CloseTerminal,
+    /// The shell exited, and a new one should be started in its place.
+    RestartTerminal,
     Bell,
+    /// A program asked to notify the user with OSC 9.
+    Notification(String),
+    /// The shell, or the task's command, exited with `exit_code`. `command` is what ran, as far as
+    /// it's known.
+    ProcessExited {
+        command: String,
+        exit_code: i32,
+    },
+    /// A program asked to put text on the clipboard with OSC 52.
+    ClipboardWrite(String),
     Wakeup,
+    /// New output reached the grid, carrying the [`Terminal::content_version`] it's at now.
+    ContentChanged(usize),
     BlinkChanged,
     SelectionsChanged,
     NewNavigationTarget(Option<MaybeNavigationTarget>),
     Open(MaybeNavigationTarget),
 }
 
+/// What a key does in copy mode, see [`Terminal::toggle_copy_mode`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CopyModeCommand {
+    /// Moves the copy mode cursor, extending the selection if there is one.
+    Move(ViMotion),
+    /// Starts a selection at the cursor, or drops the one there is.
+    ToggleSelection,
+    /// Copies the selection to the clipboard and leaves copy mode.
+    Yank,
+    /// Leaves copy mode, dropping the selection.
+    Exit,
+}
+
+/// A signal that can be sent to the program running in a terminal, see
+/// [`Terminal::signal_foreground`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Signal {
+    Interrupt,
+    Terminate,
+    Kill,
+    Hangup,
+}
+
+#[cfg(unix)]
+impl Signal {
+    fn number(self) -> libc::c_int {
+        match self {
+            Signal::Interrupt => libc::SIGINT,
+            Signal::Terminate => libc::SIGTERM,
+            Signal::Kill => libc::SIGKILL,
+            Signal::Hangup => libc::SIGHUP,
+        }
+    }
+}
+
+/// A match found by [`Terminal::search`], in grid coordinates.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SearchMatch {
+    pub start: AlacPoint,
+    pub end: AlacPoint,
+}
+
 #[derive(Clone, Debug)]
 pub struct PathLikeTarget {
     /// File system path, absolute or relative, existing or not.
@@ -117,7 +225,7 @@ pub enum MaybeNavigationTarget {
 enum InternalEvent {
     ColorRequest(usize, Arc<dyn Fn(Rgb) -> String + Sync + Send + 'static>),
     Resize(TerminalSize),
-    Clear,
+    Clear(ClearBehavior),
     // FocusNextMatch,
     Scroll(AlacScroll),
     ScrollToAlacPoint(AlacPoint),
@@ -126,6 +234,13 @@ enum InternalEvent {
     // Adjusted mouse position, should open
     FindHyperlink(Point<Pixels>, bool),
     Copy,
+    CopyStyled(CopyFormat),
+    JumpToPrompt(AlacDirection),
+    CopyLastCommandOutput,
+    ToggleCopyMode,
+    CopyMode(CopyModeCommand),
+    #[cfg(target_os = "linux")]
+    CopyToPrimary,
 }
 
 ///A translation struct for Alacritty to communicate with us from their event loop
@@ -147,6 +262,9 @@ pub struct TerminalSize {
     pub cell_width: Pixels,
     pub line_height: Pixels,
     pub size: Size<Pixels>,
+    /// The scale factor of the window the terminal is shown in, used to report
+    /// the size of its cells to the PTY in device pixels.
+    pub scale_factor: f32,
 }
 
 impl TerminalSize {
@@ -155,9 +273,15 @@ impl TerminalSize {
             cell_width,
             line_height,
             size,
+            scale_factor: 1.,
         }
     }
 
+    pub fn with_scale_factor(mut self, scale_factor: f32) -> Self {
+        self.scale_factor = scale_factor;
+        self
+    }
+
     pub fn num_lines(&self) -> usize {
         (self.size.height / self.line_height).floor() as usize
     }
@@ -201,8 +325,8 @@ impl From<TerminalSize> for WindowSize {
         WindowSize {
             num_lines: val.num_lines() as u16,
             num_cols: val.num_columns() as u16,
-            cell_width: f32::from(val.cell_width()) as u16,
-            cell_height: f32::from(val.line_height()) as u16,
+            cell_width: (f32::from(val.cell_width()) * val.scale_factor).round() as u16,
+            cell_height: (f32::from(val.line_height()) * val.scale_factor).round() as u16,
         }
     }
 }
@@ -232,7 +356,56 @@ pub struct TerminalError {
     pub source: std::io::Error,
 }
 
+/// The common reasons a shell can't be started, each with its own fix.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TerminalErrorCause {
+    ShellNotFound,
+    PermissionDenied,
+    DirectoryMissing,
+    Other,
+}
+
+impl TerminalErrorCause {
+    fn new(directory_missing: bool, error: std::io::ErrorKind) -> Self {
+        if directory_missing {
+            return TerminalErrorCause::DirectoryMissing;
+        }
+        match error {
+            std::io::ErrorKind::NotFound => TerminalErrorCause::ShellNotFound,
+            std::io::ErrorKind::PermissionDenied => TerminalErrorCause::PermissionDenied,
+            _ => TerminalErrorCause::Other,
+        }
+    }
+}
+
 impl TerminalError {
+    pub fn cause(&self) -> TerminalErrorCause {
+        let directory_missing = self
+            .directory
+            .as_deref()
+            .map_or(false, |directory| !directory.is_dir());
+        TerminalErrorCause::new(directory_missing, self.source.kind())
+    }
+
+    /// What to do about the error, in a sentence.
+    pub fn advice(&self) -> String {
+        match self.cause() {
+            TerminalErrorCause::ShellNotFound => format!(
+                "The shell `{}` wasn't found. Check the `terminal.shell` setting.",
+                self.fmt_shell()
+            ),
+            TerminalErrorCause::PermissionDenied => format!(
+                "The shell `{}` couldn't be run. Check that it's executable.",
+                self.fmt_shell()
+            ),
+            TerminalErrorCause::DirectoryMissing => format!(
+                "The working directory {} doesn't exist. Check the `terminal.working_directory` setting.",
+                self.fmt_directory()
+            ),
+            TerminalErrorCause::Other => "The shell couldn't be started.".to_string(),
+        }
+    }
+
     pub fn fmt_directory(&self) -> String {
         self.directory
             .clone()
@@ -280,8 +453,11 @@ impl Display for TerminalError {
 
         write!(
             f,
-            "Working directory: {} Shell command: `{}`, IOError: {}",
-            dir_string, shell, self.source
+            "{} Working directory: {} Shell command: `{}`, IOError: {}",
+            self.advice(),
+            dir_string,
+            shell,
+            self.source
         )
     }
 }
@@ -311,29 +487,43 @@ impl TerminalBuilder {
         working_directory: Option<PathBuf>,
         task: Option<TaskState>,
         shell: Shell,
-        mut env: HashMap<String, String>,
+        login_shell: bool,
+        env: HashMap<String, String>,
+        dotenv: bool,
+        term: &str,
         blink_settings: Option<TerminalBlink>,
         alternate_scroll: AlternateScroll,
         max_scroll_history_lines: Option<usize>,
+        max_bytes_per_frame: usize,
+        word_separators: String,
+        cursor_shape: CursorShape,
         window: AnyWindowHandle,
         completion_tx: Sender<()>,
     ) -> Result<TerminalBuilder> {
-        // TODO: Properly set the current locale,
-        env.entry("LC_ALL".to_string())
-            .or_insert_with(|| "en_US.UTF-8".to_string());
-
-        env.insert("ZED_TERM".to_string(), "true".to_string());
+        let env = shell_env(env, working_directory.as_deref(), dotenv, term);
+
+        let mut fallback_shell = None;
+        let shell = match shell_program(&shell) {
+            Some(program) if !program_exists(program) => {
+                match resolve_shell_program(program, system_shell(), program_exists) {
+                    Ok(fallback) => {
+                        log::warn!("shell {program:?} not found, using {fallback:?} instead");
+                        fallback_shell = Some(fallback.clone());
+                        Shell::Program(fallback)
+                    }
+                    Err(error) => bail!(TerminalError {
+                        directory: working_directory,
+                        shell,
+                        source: std::io::Error::new(std::io::ErrorKind::NotFound, error),
+                    }),
+                }
+            }
+            _ => shell,
+        };
 
         let pty_options = {
-            let alac_shell = match shell.clone() {
-                Shell::System => None,
-                Shell::Program(program) => {
-                    Some(alacritty_terminal::tty::Shell::new(program, Vec::new()))
-                }
-                Shell::WithArguments { program, args } => {
-                    Some(alacritty_terminal::tty::Shell::new(program, args))
-                }
-            };
+            let alac_shell = pty_shell(shell.clone(), login_shell)
+                .map(|(program, args)| alacritty_terminal::tty::Shell::new(program, args));
 
             alacritty_terminal::tty::Options {
                 shell: alac_shell,
@@ -346,6 +536,58 @@ impl TerminalBuilder {
         // Setup Alacritty's env
         setup_env();
 
+        //Setup the pty...
+        let pty = match tty::new(
+            &pty_options,
+            TerminalSize::default().into(),
+            window.window_id().as_u64(),
+        ) {
+            Ok(pty) => pty,
+            Err(error) => {
+                bail!(TerminalError {
+                    directory: working_directory,
+                    shell,
+                    source: error,
+                });
+            }
+        };
+
+        let pty_info = PtyProcessInfo::new(&pty);
+
+        Self::with_pty(
+            pty,
+            pty_info,
+            pty_options.hold,
+            task,
+            working_directory,
+            fallback_shell,
+            blink_settings,
+            alternate_scroll,
+            max_scroll_history_lines,
+            max_bytes_per_frame,
+            word_separators,
+            cursor_shape,
+            completion_tx,
+        )
+    }
+
+    /// Sets up the terminal around a PTY its shell was started in.
+    #[allow(clippy::too_many_arguments)]
+    fn with_pty<P: EventedPty + OnResize + Send + 'static>(
+        pty: P,
+        pty_info: PtyProcessInfo,
+        hold: bool,
+        task: Option<TaskState>,
+        working_directory: Option<PathBuf>,
+        fallback_shell: Option<String>,
+        blink_settings: Option<TerminalBlink>,
+        alternate_scroll: AlternateScroll,
+        max_scroll_history_lines: Option<usize>,
+        max_bytes_per_frame: usize,
+        word_separators: String,
+        cursor_shape: CursorShape,
+        completion_tx: Sender<()>,
+    ) -> Result<TerminalBuilder> {
         let scrolling_history = if task.is_some() {
             // Tasks like `cargo build --all` may produce a lot of output, ergo allow maximum scrolling.
             // After the task finishes, we do not allow appending to that terminal, so small tasks output should not
@@ -356,8 +598,14 @@ impl TerminalBuilder {
                 .unwrap_or(DEFAULT_SCROLL_HISTORY_LINES)
                 .min(MAX_SCROLL_HISTORY_LINES)
         };
+        let tap_state = TapState::new(scrolling_history);
         let config = Config {
-            scrolling_history,
+            scrolling_history: tap_state.scroll_history.limit(),
+            semantic_escape_chars: word_separators,
+            default_cursor_style: CursorStyle {
+                shape: cursor_shape.into(),
+                ..CursorStyle::default()
+            },
             ..Config::default()
         };
 
@@ -382,31 +630,21 @@ impl TerminalBuilder {
         }
 
         let term = Arc::new(FairMutex::new(term));
-
-        //Setup the pty...
-        let pty = match tty::new(
-            &pty_options,
-            TerminalSize::default().into(),
-            window.window_id().as_u64(),
-        ) {
-            Ok(pty) => pty,
-            Err(error) => {
-                bail!(TerminalError {
-                    directory: working_directory,
-                    shell,
-                    source: error,
-                });
-            }
-        };
-
-        let pty_info = PtyProcessInfo::new(&pty);
+        let tap_state = Arc::new(FairMutex::new(tap_state));
+        let pty = PtyTap::new(
+            pty,
+            term.clone(),
+            ZedListener(events_tx.clone()),
+            tap_state.clone(),
+            max_bytes_per_frame,
+        );
 
         //And connect them together
         let event_loop = EventLoop::new(
             term.clone(),
             ZedListener(events_tx.clone()),
             pty,
-            pty_options.hold,
+            hold,
             false,
         )?;
 
@@ -414,8 +652,9 @@ impl TerminalBuilder {
         let pty_tx = event_loop.channel();
         let _io_thread = event_loop.spawn(); // DANGER
 
-        let url_regex = RegexSearch::new(r#"(ipfs:|ipns:|magnet:|mailto:|gemini://|gopher://|https://|http://|news:|file://|git://|ssh:|ftp://)[^\u{0000}-\u{001F}\u{007F}-\u{009F}<>"\s{-}\^⟨⟩`]+"#).unwrap();
-        let word_regex = RegexSearch::new(r#"[\$\+\w.\[\]:/@\-~]+"#).unwrap();
+        let url_regex = RegexSearch::new(URL_REGEX).unwrap();
+        let word_regex = RegexSearch::new(WORD_REGEX).unwrap();
+        let stack_frame_regex = RegexSearch::new(STACK_FRAME_REGEX).unwrap();
 
         let terminal = Terminal {
             task,
@@ -426,10 +665,22 @@ impl TerminalBuilder {
             last_content: Default::default(),
             last_mouse: None,
             matches: Vec::new(),
+            active_match: None,
             selection_head: None,
             pty_info,
+            git_branch: None,
+            working_directory,
+            fallback_shell,
+            current_working_directory: None,
+            tap_state,
+            line_attributes: HashMap::default(),
+            prompt_marks: Vec::new(),
+            unseen_output: false,
+            content_version: 0,
+            local_echo: LocalEcho::default(),
             breadcrumb_text: String::new(),
             scroll_px: px(0.),
+            smooth_scroll_offset: px(0.),
             last_mouse_position: None,
             next_link_id: 0,
             selection_phase: SelectionPhase::Ended,
@@ -437,6 +688,10 @@ impl TerminalBuilder {
             hovered_word: false,
             url_regex,
             word_regex,
+            stack_frame_regex,
+            exit_status: None,
+            pty_size: None,
+            pty_resize: None,
         };
 
         Ok(TerminalBuilder {
@@ -529,12 +784,51 @@ pub struct TerminalContent {
     pub cells: Vec<IndexedCell>,
     pub mode: TermMode,
     pub display_offset: usize,
+    /// The number of lines of scrollback above the screen.
+    pub history_size: usize,
+    /// Whether the scrollbar takes up room at the right of the grid, see `scrollbar_shown`.
+    pub scrollbar_shown: bool,
     pub selection_text: Option<String>,
     pub selection: Option<SelectionRange>,
     pub cursor: RenderableCursor,
     pub cursor_char: char,
     pub size: TerminalSize,
     pub last_hovered_word: Option<HoveredWord>,
+    /// DEC line attributes of the visible lines, keyed by grid line.
+    pub line_attributes: HashMap<i32, LineAttribute>,
+    /// Typed text that's expected to be echoed at the given point, see `local_echo`.
+    pub predicted_echo: Option<(AlacPoint, String)>,
+}
+
+/// A copy of the terminal's contents, detached from its grid, for other crates to read its output.
+/// Cloning it is cheap.
+#[derive(Clone, Debug, Default)]
+pub struct GridSnapshot {
+    /// The lines of scrollback that were asked for, oldest first, followed by the screen's.
+    pub lines: Arc<[Vec<Cell>]>,
+    /// The cursor's line, as an index into `lines`.
+    pub cursor_line: usize,
+    pub cursor_column: usize,
+}
+
+impl GridSnapshot {
+    /// The text of a line, without the whitespace at its end.
+    pub fn line_text(&self, line: usize) -> String {
+        let text = self.lines[line]
+            .iter()
+            .filter(|cell| !cell.flags.contains(Flags::WIDE_CHAR_SPACER))
+            .map(|cell| cell.c)
+            .collect::<String>();
+        text.trim_end().to_string()
+    }
+
+    /// The text of every line, without the whitespace at their ends.
+    pub fn text(&self) -> String {
+        (0..self.lines.len())
+            .map(|line| self.line_text(line))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
 }
 
 #[derive(Clone)]
@@ -550,6 +844,8 @@ impl Default for TerminalContent {
             cells: Default::default(),
             mode: Default::default(),
             display_offset: Default::default(),
+            history_size: Default::default(),
+            scrollbar_shown: false,
             selection_text: Default::default(),
             selection: Default::default(),
             cursor: RenderableCursor {
@@ -559,6 +855,8 @@ impl Default for TerminalContent {
             cursor_char: Default::default(),
             size: Default::default(),
             last_hovered_word: None,
+            line_attributes: HashMap::default(),
+            predicted_echo: None,
         }
     }
 }
@@ -579,18 +877,45 @@ pub struct Terminal {
     /// This is only used for terminal hovered word checking
     last_mouse_position: Option<Point<Pixels>>,
     pub matches: Vec<RangeInclusive<AlacPoint>>,
+    /// The index in `matches` of the one last activated
+    active_match: Option<usize>,
     pub last_content: TerminalContent,
     pub selection_head: Option<AlacPoint>,
     pub breadcrumb_text: String,
     pub pty_info: PtyProcessInfo,
+    git_branch: Option<String>,
+    /// The directory the terminal was started in
+    working_directory: Option<PathBuf>,
+    /// The shell started in place of the configured one, which couldn't be found
+    fallback_shell: Option<String>,
+    /// The directory the shell last reported being in, with OSC 7
+    current_working_directory: Option<PathBuf>,
+    tap_state: Arc<FairMutex<TapState>>,
+    /// Keyed by the line's row, see [`ScrollHistory::row`].
+    line_attributes: HashMap<usize, LineAttribute>,
+    /// Shell integration marks, with the rows they're on, see [`ScrollHistory::row`].
+    prompt_marks: Vec<(usize, PromptMark)>,
+    /// Whether output arrived while scrolled up, since the terminal last showed its bottom.
+    unseen_output: bool,
+    /// Counts the times output has reached the grid, see `content_version`.
+    content_version: usize,
+    local_echo: LocalEcho,
     scroll_px: Pixels,
+    /// How far into the next line trackpad scrolling has gone, see `smooth_scroll_offset`.
+    smooth_scroll_offset: Pixels,
     next_link_id: usize,
     selection_phase: SelectionPhase,
     secondary_pressed: bool,
     hovered_word: bool,
     url_regex: RegexSearch,
     word_regex: RegexSearch,
+    stack_frame_regex: RegexSearch,
     task: Option<TaskState>,
+    /// The exit code of the shell, once it has exited.
+    exit_status: Option<i32>,
+    /// The size the PTY was last told about, or will be once resizing settles.
+    pty_size: Option<WindowSize>,
+    pty_resize: Option<Task<()>>,
 }
 
 pub struct TaskState {
@@ -630,22 +955,22 @@ impl TaskStatus {
 impl Terminal {
     fn process_event(&mut self, event: &AlacTermEvent, cx: &mut ModelContext<Self>) {
         match event {
-            AlacTermEvent::Title(title) => {
-                self.breadcrumb_text = title.to_string();
-                cx.emit(Event::BreadcrumbsChanged);
-            }
-            AlacTermEvent::ResetTitle => {
-                self.breadcrumb_text = String::new();
+            AlacTermEvent::Title(_) | AlacTermEvent::ResetTitle => {
+                let title = osc_title(event).unwrap_or_default();
+                self.breadcrumb_text = title.clone();
                 cx.emit(Event::BreadcrumbsChanged);
+                cx.emit(Event::TitleChanged);
+                cx.emit(Event::TitleChangedTo(title));
             }
             AlacTermEvent::ClipboardStore(_, data) => {
-                cx.write_to_clipboard(ClipboardItem::new(data.to_string()))
+                let allowed = TerminalSettings::get_global(cx).allow_osc52_write;
+                if let Some(text) = clipboard_write(data, allowed) {
+                    cx.emit(Event::ClipboardWrite(text));
+                }
             }
-            AlacTermEvent::ClipboardLoad(_, format) => self.write_to_pty(format(
-                &cx.read_from_clipboard()
-                    .map(|ci| ci.text().to_string())
-                    .unwrap_or_else(|| "".to_string()),
-            )),
+            // Any program, including those on remote machines, could read the clipboard this
+            // way, so it's never answered
+            AlacTermEvent::ClipboardLoad(..) => {}
             AlacTermEvent::PtyWrite(out) => self.write_to_pty(out.clone()),
             AlacTermEvent::TextAreaSizeRequest(format) => {
                 self.write_to_pty(format(self.last_content.size.into()))
@@ -661,23 +986,59 @@ impl Terminal {
                     task.status.register_terminal_exit();
                     self.completion_tx.try_send(()).ok();
                 }
-                None => cx.emit(Event::CloseTerminal),
+                None => match exit_event(TerminalSettings::get_global(cx).on_exit) {
+                    Some(event) => cx.emit(event),
+                    None => cx.notify(),
+                },
             },
             AlacTermEvent::MouseCursorDirty => {
                 //NOOP, Handled in render
             }
             AlacTermEvent::Wakeup => {
                 cx.emit(Event::Wakeup);
+                self.content_version = self.content_version.wrapping_add(1);
+                cx.emit(Event::ContentChanged(self.content_version));
+
+                let (report, notifications) = {
+                    let mut tap = self.tap_state.lock();
+                    (
+                        tap.working_directory.take(),
+                        mem::take(&mut tap.notifications),
+                    )
+                };
+                if let Some(report) = report.filter(|report| is_local_host(&report.host)) {
+                    self.current_working_directory = Some(report.path);
+                }
+                for message in notifications {
+                    cx.emit(Event::Notification(message));
+                }
 
                 if self.pty_info.has_changed() {
+                    self.refresh_git_branch(cx);
                     cx.emit(Event::TitleChanged);
                 }
+
+                // Alacritty keeps the viewport on the same lines while scrolled up, so reading
+                // the scrollback isn't interrupted unless following the output is wanted
+                if self.last_content.display_offset > 0 {
+                    if TerminalSettings::get_global(cx).scroll_on_output {
+                        self.scroll_to_bottom();
+                    } else {
+                        self.unseen_output = true;
+                    }
+                }
             }
             AlacTermEvent::ColorRequest(idx, fun_ptr) => {
                 self.events
                     .push_back(InternalEvent::ColorRequest(*idx, fun_ptr.clone()));
             }
             AlacTermEvent::ChildExit(error_code) => {
+                self.exit_status = Some(*error_code);
+                cx.emit(process_exited_event(
+                    self.task.as_ref().map(|task| task.full_label.as_str()),
+                    self.pty_info.current.as_ref(),
+                    *error_code,
+                ));
                 if let Some(task) = &mut self.task {
                     task.status.register_task_exit(*error_code);
                     self.completion_tx.try_send(()).ok();
@@ -690,10 +1051,95 @@ impl Terminal {
         self.selection_phase == SelectionPhase::Selecting
     }
 
+    /// The id of the foreground process group: the program running in the terminal, or its
+    /// shell.
+    pub fn foreground_pid(&self) -> Option<u32> {
+        self.pty_info.pid().map(|pid| pid.as_u32())
+    }
+
+    /// The name of the command running in the foreground, unless that's the shell itself.
+    pub fn running_process_name(&self) -> Option<String> {
+        let foreground_pid = self.foreground_pid()?;
+        running_process_name(
+            self.pty_info.shell_pid(),
+            foreground_pid,
+            self.pty_info
+                .current
+                .as_ref()
+                .map(|info| info.name.as_str()),
+        )
+    }
+
+    /// Sends `signal` to the foreground process group, returning whether it was delivered.
+    #[cfg(unix)]
+    pub fn signal_foreground(&self, signal: Signal) -> bool {
+        match self.foreground_pid() {
+            Some(pid) => signal_process_group(pid, signal.number()),
+            None => false,
+        }
+    }
+
     pub fn get_cwd(&self) -> Option<PathBuf> {
         self.pty_info.current.as_ref().map(|info| info.cwd.clone())
     }
 
+    /// The working directory the shell last reported. Unlike [`Self::get_cwd`], this is the
+    /// shell's directory even while another program runs in the foreground, but it's only
+    /// known for shells that emit OSC 7.
+    pub fn current_working_directory(&self) -> Option<PathBuf> {
+        self.current_working_directory.clone()
+    }
+
+    /// The exit code of the shell, once it has exited.
+    pub fn exit_status(&self) -> Option<i32> {
+        self.exit_status
+    }
+
+    /// Closes or restarts the terminal, as the `enter_after_exit` setting says, if it's being
+    /// kept open after its shell exited. Returns whether it was.
+    pub fn enter_after_exit(&mut self, cx: &mut ModelContext<Self>) -> bool {
+        let exited = self.task.is_none() && self.exit_status.is_some();
+        if exited {
+            cx.emit(enter_after_exit_event(
+                TerminalSettings::get_global(cx).enter_after_exit,
+            ));
+        }
+        exited
+    }
+
+    /// The directory the terminal was started in.
+    pub fn working_directory(&self) -> Option<PathBuf> {
+        self.working_directory.clone()
+    }
+
+    /// The shell that was started because the configured one couldn't be found, if any.
+    pub fn fallback_shell(&self) -> Option<&str> {
+        self.fallback_shell.as_deref()
+    }
+
+    /// The directory that files related to this terminal, like saved output, belong in:
+    /// the working directory of its foreground process, or the one it was started in.
+    pub fn associated_directory(&self) -> Option<PathBuf> {
+        self.get_cwd().or_else(|| self.working_directory.clone())
+    }
+
+    /// The git branch checked out in the terminal's working directory, if
+    /// `git_branch_in_title` is enabled.
+    pub fn git_branch(&self) -> Option<&str> {
+        self.git_branch.as_deref()
+    }
+
+    /// Re-reads the branch whenever the foreground process or its working directory
+    /// changes. This also covers `git checkout` and friends, since running them changes
+    /// the foreground process.
+    fn refresh_git_branch(&mut self, cx: &mut ModelContext<Self>) {
+        self.git_branch = if TerminalSettings::get_global(cx).git_branch_in_title {
+            self.get_cwd().and_then(|cwd| git_branch_for_dir(&cwd))
+        } else {
+            None
+        };
+    }
+
     ///Takes events from Alacritty and translates them to behavior on this view
     fn process_terminal_event(
         &mut self,
@@ -704,23 +1150,53 @@ impl Terminal {
         match event {
             InternalEvent::ColorRequest(index, format) => {
                 let color = term.colors()[*index].unwrap_or_else(|| {
-                    to_alac_rgb(get_color_at_index(*index, cx.theme().as_ref()))
+                    let colors = &TerminalSettings::get_global(cx).colors;
+                    to_alac_rgb(colors.color_at_index(*index, &terminal_theme(cx)))
                 });
                 self.write_to_pty(format(color))
             }
-            InternalEvent::Resize(mut new_size) => {
-                new_size.size.height = cmp::max(new_size.line_height, new_size.height());
-                new_size.size.width = cmp::max(new_size.cell_width, new_size.width());
-
+            InternalEvent::Resize(new_size) => {
+                let new_size = resize_term(term, *new_size);
+                self.tap_state.lock().scroll_history.resized(term);
+                // Reflowing moves wrapped lines to other rows, so attributes recorded by
+                // row would apply to the wrong lines
+                if new_size.num_columns() != self.last_content.size.num_columns() {
+                    self.line_attributes.clear();
+                    self.prompt_marks.clear();
+                }
                 self.last_content.size = new_size;
 
-                self.pty_tx.0.send(Msg::Resize(new_size.into())).ok();
-
-                term.resize(new_size);
+                // The grid follows every resize, but the PTY is only told about the size once
+                // resizing stops, sparing programs from redrawing for each step of a window drag
+                let window_size = WindowSize::from(new_size);
+                if self.pty_size != Some(window_size) {
+                    let pty_tx = self.pty_tx.0.clone();
+                    let resize_pty = move || {
+                        pty_tx.send(Msg::Resize(window_size)).ok();
+                    };
+                    if self.pty_size.is_none() {
+                        // Programs started with the terminal shouldn't draw at the default size
+                        resize_pty();
+                    } else {
+                        self.pty_resize = Some(debounce(cx.background_executor(), resize_pty));
+                    }
+                    self.pty_size = Some(window_size);
+                }
             }
-            InternalEvent::Clear => {
+            InternalEvent::Clear(behavior) => {
+                self.line_attributes.clear();
+                self.prompt_marks.clear();
+
                 // Clear back buffer
-                term.clear_screen(ClearMode::Saved);
+                if *behavior != ClearBehavior::Screen {
+                    clear_scrollback(term);
+                    self.tap_state.lock().scroll_history.update(term);
+                    self.unseen_output = false;
+                }
+                if *behavior == ClearBehavior::Scrollback {
+                    cx.emit(Event::Wakeup);
+                    return;
+                }
 
                 let cursor = term.grid().cursor.point;
 
@@ -752,10 +1228,19 @@ impl Terminal {
             }
             InternalEvent::Scroll(scroll) => {
                 term.scroll_display(*scroll);
+                if term.grid().display_offset() == 0 {
+                    self.unseen_output = false;
+                }
                 self.refresh_hovered_word();
             }
             InternalEvent::SetSelection(selection) => {
-                term.selection = selection.as_ref().map(|(sel, _)| sel.clone());
+                term.selection = selection.as_ref().map(|(sel, head)| {
+                    if sel.ty == SelectionType::Semantic {
+                        word_selection(term, *head)
+                    } else {
+                        sel.clone()
+                    }
+                });
 
                 if let Some((_, head)) = selection {
                     self.selection_head = Some(*head);
@@ -779,8 +1264,48 @@ impl Terminal {
             }
 
             InternalEvent::Copy => {
-                if let Some(txt) = term.selection_to_string() {
-                    cx.write_to_clipboard(ClipboardItem::new(txt))
+                if let Some(txt) = term.selection_to_string().filter(|txt| !txt.is_empty()) {
+                    cx.write_to_clipboard(ClipboardItem::new(copied_text(&txt)))
+                }
+            }
+            InternalEvent::JumpToPrompt(direction) => {
+                let top_line = Line(-(term.grid().display_offset() as i32));
+                let top_row = self.tap_state.lock().scroll_history.row(top_line);
+                if let Some(row) = adjacent_prompt_row(&self.prompt_marks, top_row, *direction) {
+                    term.scroll_display(AlacScroll::Delta(top_row as i32 - row as i32));
+                    self.refresh_hovered_word();
+                }
+            }
+            InternalEvent::ToggleCopyMode => {
+                toggle_copy_mode(term);
+                cx.emit(Event::SelectionsChanged);
+            }
+            InternalEvent::CopyMode(command) => {
+                if let Some(text) = run_copy_mode_command(term, *command) {
+                    cx.write_to_clipboard(ClipboardItem::new(copied_text(&text)));
+                }
+                cx.emit(Event::SelectionsChanged);
+            }
+            InternalEvent::CopyLastCommandOutput => {
+                let output = last_command_output_rows(&self.prompt_marks).and_then(|rows| {
+                    command_output_text(term, &self.tap_state.lock().scroll_history, rows)
+                });
+                match output {
+                    Some(text) => cx.write_to_clipboard(ClipboardItem::new(copied_text(&text))),
+                    // Without shell integration, there's no telling where the output is
+                    None => self.process_terminal_event(&InternalEvent::Copy, term, cx),
+                }
+            }
+            InternalEvent::CopyStyled(format) => {
+                let colors = &TerminalSettings::get_global(cx).colors;
+                if let Some(text) = styled_selection(term, *format, colors, &terminal_theme(cx)) {
+                    cx.write_to_clipboard(ClipboardItem::new(text))
+                }
+            }
+            #[cfg(target_os = "linux")]
+            InternalEvent::CopyToPrimary => {
+                if let Some(txt) = term.selection_to_string().filter(|txt| !txt.is_empty()) {
+                    cx.write_to_primary(ClipboardItem::new(copied_text(&txt)))
                 }
             }
             InternalEvent::ScrollToAlacPoint(point) => {
@@ -797,36 +1322,12 @@ impl Terminal {
                 )
                 .grid_clamp(term, Boundary::Grid);
 
-                let link = term.grid().index(point).hyperlink();
-                let found_word = if link.is_some() {
-                    let mut min_index = point;
-                    loop {
-                        let new_min_index = min_index.sub(term, Boundary::Cursor, 1);
-                        if new_min_index == min_index {
-                            break;
-                        } else if term.grid().index(new_min_index).hyperlink() != link {
-                            break;
-                        } else {
-                            min_index = new_min_index
-                        }
-                    }
-
-                    let mut max_index = point;
-                    loop {
-                        let new_max_index = max_index.add(term, Boundary::Cursor, 1);
-                        if new_max_index == max_index {
-                            break;
-                        } else if term.grid().index(new_max_index).hyperlink() != link {
-                            break;
-                        } else {
-                            max_index = new_max_index
-                        }
-                    }
-
-                    let url = link.unwrap().uri().to_owned();
-                    let url_match = min_index..=max_index;
-
+                let found_word = if let Some((url, url_match)) = hyperlink_at(term, point) {
                     Some((url, true, url_match))
+                } else if let Some((location, location_match)) =
+                    stack_frame_location_at(term, point, &mut self.stack_frame_regex)
+                {
+                    Some((location, false, location_match))
                 } else if let Some(word_match) = regex_match_at(term, point, &mut self.word_regex) {
                     let maybe_url_or_path =
                         term.bounds_to_string(*word_match.start(), *word_match.end());
@@ -937,16 +1438,51 @@ impl Terminal {
         res
     }
 
+    /// A copy of the screen and up to `scrollback_lines` lines of the scrollback above it. The
+    /// grid is only locked while it's copied.
+    pub fn grid_snapshot(&self, scrollback_lines: usize) -> GridSnapshot {
+        grid_snapshot(&self.term.lock(), scrollback_lines)
+    }
+
     pub fn last_content(&self) -> &TerminalContent {
         &self.last_content
     }
 
+    /// The currently selected text, or `None` when the selection is empty.
+    pub fn selection_text(&self) -> Option<&str> {
+        self.last_content
+            .selection_text
+            .as_deref()
+            .filter(|text| !text.is_empty())
+    }
+
     //To test:
     //- Activate match on terminal (scrolling and selection)
     //- Editor search snapping behavior
 
+    /// Replaces the search matches, none of which are active yet.
+    pub fn set_matches(&mut self, matches: Vec<RangeInclusive<AlacPoint>>) {
+        self.matches = matches;
+        self.active_match = None;
+    }
+
+    pub fn clear_matches(&mut self) {
+        self.set_matches(Vec::new());
+    }
+
+    pub fn active_match(&self) -> Option<usize> {
+        self.active_match
+    }
+
+    /// Which of the search matches is active out of how many, like "3/17", or `None` if there
+    /// are no matches.
+    pub fn match_count_label(&self) -> Option<String> {
+        match_count_label(self.active_match, self.matches.len())
+    }
+
     pub fn activate_match(&mut self, index: usize) {
         if let Some(search_match) = self.matches.get(index).cloned() {
+            self.active_match = Some(index);
             self.set_selection(Some((make_selection(&search_match), *search_match.end())));
 
             self.events
@@ -954,6 +1490,17 @@ impl Terminal {
         }
     }
 
+    /// Activates the match after the selection or cursor, or before it when searching to the
+    /// left, wrapping around at the ends of the scrollback.
+    pub fn activate_adjacent_match(&mut self, direction: AlacDirection) {
+        let from = self
+            .selection_head
+            .unwrap_or(self.last_content.cursor.point);
+        if let Some(index) = adjacent_match_index(&self.matches, from, direction) {
+            self.activate_match(index);
+        }
+    }
+
     pub fn select_matches(&mut self, matches: &[RangeInclusive<AlacPoint>]) {
         let matches_to_select = self
             .matches
@@ -969,12 +1516,59 @@ impl Terminal {
         }
     }
 
+    /// The scrollback and the screen, as plain text or with their colors as ANSI escapes.
+    pub fn buffer_contents(&self, preserve_ansi: bool, cx: &AppContext) -> String {
+        let term = self.term.lock();
+        if !preserve_ansi {
+            return buffer_text(&term);
+        }
+        let colors = &TerminalSettings::get_global(cx).colors;
+        all_selection(&term)
+            .to_range(&term)
+            .map(|range| styled_range(&term, range, CopyFormat::Ansi, colors, &terminal_theme(cx)))
+            .unwrap_or_default()
+    }
+
+    /// Selects the scrollback and the screen, wherever the terminal is scrolled to.
     pub fn select_all(&mut self) {
+        let term = self.term.lock();
+        let selection = all_selection(&term);
+        let end = selection.to_range(&term).map(|range| range.end);
+        drop(term);
+        if let Some(end) = end {
+            self.set_selection(Some((selection, end)));
+        }
+    }
+
+    /// The text of the line the cursor is on, including the parts of it that wrapped.
+    pub fn cursor_line(&self) -> String {
+        let term = self.term.lock();
+        let cursor = term.grid().cursor.point;
+        let start = term.line_search_left(cursor);
+        let end = term.line_search_right(cursor);
+        term.bounds_to_string(start, end).trim().to_string()
+    }
+
+    /// Finds the file locations of the errors in the terminal's output, in the order they were
+    /// printed. An error is a line matching `error_pattern`; its location is the first
+    /// `path:line` on that line or on the couple of lines after it, which is where compilers
+    /// like rustc put it.
+    pub fn error_locations(&self, error_pattern: &str) -> Result<Vec<PathLikeTarget>> {
+        let error_regex = Regex::new(error_pattern)?;
         let term = self.term.lock();
         let start = AlacPoint::new(term.topmost_line(), Column(0));
         let end = AlacPoint::new(term.bottommost_line(), term.last_column());
+        let output = term.bounds_to_string(start, end);
         drop(term);
-        self.set_selection(Some((make_selection(&(start..=end)), end)));
+
+        let terminal_dir = self.get_cwd();
+        Ok(find_error_locations(&output, &error_regex)
+            .into_iter()
+            .map(|maybe_path| PathLikeTarget {
+                maybe_path,
+                terminal_dir: terminal_dir.clone(),
+            })
+            .collect())
     }
 
     fn set_selection(&mut self, selection: Option<(Selection, AlacPoint)>) {
@@ -986,8 +1580,76 @@ impl Terminal {
         self.events.push_back(InternalEvent::Copy);
     }
 
-    pub fn clear(&mut self) {
-        self.events.push_back(InternalEvent::Clear)
+    /// Whether the cursor should be drawn: programs hide it with DECTCEM (`\x1b[?25l`) and show
+    /// it again with `\x1b[?25h`. It's always shown in copy mode.
+    pub fn cursor_visible(&self) -> bool {
+        cursor_visible(&self.last_content.mode)
+    }
+
+    /// Whether output arrived while the terminal was scrolled up, which it hasn't shown yet.
+    pub fn has_unseen_output(&self) -> bool {
+        self.unseen_output
+    }
+
+    /// The shell integration marks the shell printed with OSC 133, with the rows they're on, in the
+    /// order they were printed. Marks are dropped along with their lines once they leave the
+    /// scrollback.
+    pub fn prompt_marks(&self) -> &[(usize, PromptMark)] {
+        &self.prompt_marks
+    }
+
+    /// Scrolls the prompt before or after the top of the viewport to its top, going to the
+    /// previous one when `direction` is [`AlacDirection::Left`].
+    pub fn jump_to_prompt(&mut self, direction: AlacDirection) {
+        self.events
+            .push_back(InternalEvent::JumpToPrompt(direction));
+    }
+
+    /// Copies the output of the last command that finished, using the shell integration marks
+    /// around it. When there aren't any, the selection is copied instead.
+    pub fn copy_last_command_output(&mut self) {
+        self.events.push_back(InternalEvent::CopyLastCommandOutput);
+    }
+
+    /// Copies the selection with its colors, bold and italics, as HTML or ANSI escapes.
+    pub fn copy_styled(&mut self, format: CopyFormat) {
+        self.events.push_back(InternalEvent::CopyStyled(format));
+    }
+
+    /// Enters or leaves copy mode, where keys move a cursor over the scrollback and select text
+    /// with it instead of going to the shell. See [`copy_mode_command`] for what they do.
+    pub fn toggle_copy_mode(&mut self) {
+        self.events.push_back(InternalEvent::ToggleCopyMode);
+    }
+
+    /// Whether the terminal is in copy mode, see [`Terminal::toggle_copy_mode`].
+    pub fn copy_mode(&self) -> bool {
+        self.last_content.mode.contains(TermMode::VI)
+    }
+
+    /// Drops the scrollback, leaving what's on the screen, whatever `clear_behavior` is set to.
+    pub fn clear_scrollback(&mut self) {
+        self.clear(ClearBehavior::Scrollback, false);
+    }
+
+    /// Clears the screen and/or the scrollback. With `redraw_prompt`, the shell running in the
+    /// terminal is also asked to redraw its prompt, the way it would if `clear` was typed in it.
+    pub fn clear(&mut self, behavior: ClearBehavior, redraw_prompt: bool) {
+        self.events.push_back(InternalEvent::Clear(behavior));
+
+        if redraw_prompt
+            && behavior != ClearBehavior::Scrollback
+            && !self.last_content.mode.contains(TermMode::ALT_SCREEN)
+        {
+            let clear_input = self
+                .pty_info
+                .current
+                .as_ref()
+                .and_then(|info| shell_clear_input(&info.name));
+            if let Some(clear_input) = clear_input {
+                self.write_to_pty(clear_input.to_string());
+            }
+        }
     }
 
     ///Resize the terminal and the PTY.
@@ -1004,6 +1666,45 @@ impl Terminal {
         self.pty_tx.notify(input);
     }
 
+    /// Whether a program switched to the alternate screen, which has no scrollback.
+    pub fn is_alt_screen(&self) -> bool {
+        self.last_content.mode.contains(TermMode::ALT_SCREEN)
+    }
+
+    /// Scrolls through the scrollback, unless the alternate screen is showing.
+    fn scroll_scrollback(&mut self, scroll: AlacScroll) {
+        if !self.is_alt_screen() {
+            self.events.push_back(InternalEvent::Scroll(scroll));
+        }
+    }
+
+    pub fn scroll_line_up(&mut self) {
+        self.scroll_scrollback(AlacScroll::Delta(1));
+    }
+
+    pub fn scroll_line_down(&mut self) {
+        self.scroll_scrollback(AlacScroll::Delta(-1));
+    }
+
+    pub fn scroll_page_up(&mut self) {
+        let lines = self.last_content.size.num_lines() as i32;
+        self.scroll_scrollback(AlacScroll::Delta(lines));
+    }
+
+    pub fn scroll_page_down(&mut self) {
+        let lines = self.last_content.size.num_lines() as i32;
+        self.scroll_scrollback(AlacScroll::Delta(-lines));
+    }
+
+    pub fn scroll_to_top(&mut self) {
+        self.scroll_scrollback(AlacScroll::Top);
+    }
+
+    pub fn scroll_to_bottom(&mut self) {
+        self.events
+            .push_back(InternalEvent::Scroll(AlacScroll::Bottom));
+    }
+
     pub fn input(&mut self, input: String) {
         self.events
             .push_back(InternalEvent::Scroll(AlacScroll::Bottom));
@@ -1020,9 +1721,26 @@ impl Terminal {
         self.write_bytes_to_pty(input);
     }
 
+    /// Types text into the terminal on behalf of an integration, like a task runner running a
+    /// command in it. Unlike [`Terminal::paste`], line endings are left as they are, and the text
+    /// is only wrapped in paste markers when the running program enabled bracketed paste.
+    pub fn send_text(&mut self, text: &str) {
+        self.local_echo.reset();
+        let bracketed = self.last_content.mode.contains(TermMode::BRACKETED_PASTE);
+        self.input(injected_text(text, bracketed));
+    }
+
     pub fn try_keystroke(&mut self, keystroke: &Keystroke, alt_is_meta: bool) -> bool {
+        // Nothing typed in copy mode reaches the shell
+        if self.copy_mode() {
+            if let Some(command) = copy_mode_command(keystroke) {
+                self.events.push_back(InternalEvent::CopyMode(command));
+            }
+            return true;
+        }
         let esc = to_esc_str(keystroke, &self.last_content.mode, alt_is_meta);
         if let Some(esc) = esc {
+            self.local_echo.reset();
             self.input(esc);
             true
         } else {
@@ -1039,15 +1757,108 @@ impl Terminal {
         changed
     }
 
-    ///Paste text into the terminal
-    pub fn paste(&mut self, text: &str) {
-        let paste_text = if self.last_content.mode.contains(TermMode::BRACKETED_PASTE) {
-            format!("{}{}{}", "\x1b[200~", text.replace('\x1b', ""), "\x1b[201~")
+    /// Shows typed text before the PTY echoes it, if it's safe to predict the echo.
+    /// Call this before sending the text with [`Terminal::input`].
+    pub fn predict_echo(&mut self, text: &str) {
+        let cursor = self.last_content.cursor.point;
+        // Only predict typing at the end of a line, when nothing needs to move out of the way.
+        let rest_of_line_blank = self
+            .last_content
+            .cells
+            .iter()
+            .filter(|cell| cell.point.line == cursor.line && cell.point.column >= cursor.column)
+            .all(|cell| cell.c == ' ');
+        if self.last_content.mode.contains(TermMode::ALT_SCREEN) || !rest_of_line_blank {
+            self.local_echo.reset();
         } else {
-            text.replace("\r\n", "\r").replace('\n', "\r")
-        };
+            self.local_echo
+                .predict(text, cursor, self.last_content.size.num_columns());
+        }
+    }
+
+    /// Pastes the primary selection, as middle-clicking does on Linux.
+    #[cfg(target_os = "linux")]
+    pub fn paste_primary(&mut self, cx: &mut ModelContext<Self>) {
+        if let Some(item) = cx.read_from_primary() {
+            self.paste(item.text(), cx);
+        }
+    }
+
+    /// Whether pasting `text` would run several commands, because it spans several lines and the
+    /// running program didn't enable bracketed paste, which tells pasted newlines from typed ones.
+    pub fn paste_runs_commands(&self, text: &str) -> bool {
+        !self.last_content.mode.contains(TermMode::BRACKETED_PASTE) && is_multiline(text)
+    }
+
+    ///Paste text into the terminal
+    pub fn paste(&mut self, text: &str, cx: &mut ModelContext<Self>) {
+        self.local_echo.reset();
+
+        let settings = TerminalSettings::get_global(cx);
+        let paste_text = paste_text(
+            text,
+            self.last_content.mode.contains(TermMode::BRACKETED_PASTE),
+            settings.strip_trailing_newline_on_paste,
+        );
+
+        self.events
+            .push_back(InternalEvent::Scroll(AlacScroll::Bottom));
+        self.events.push_back(InternalEvent::SetSelection(None));
 
-        self.input(paste_text);
+        let chunks = paste_chunks(&paste_text, settings.paste_chunk_size);
+        let delay = Duration::from_millis(settings.paste_chunk_delay_ms);
+        if delay.is_zero() || chunks.len() <= 1 {
+            for chunk in chunks {
+                self.write_to_pty(chunk);
+            }
+            return;
+        }
+
+        // The bracketed paste markers wrap the whole payload, so the chunks
+        // must arrive in order even when they're spread out over time.
+        cx.spawn(|this, mut cx| async move {
+            for chunk in chunks {
+                this.update(&mut cx, |this, _| this.write_to_pty(chunk))?;
+                cx.background_executor().timer(delay).await;
+            }
+            anyhow::Ok(())
+        })
+        .detach();
+    }
+
+    /// A number that goes up each time output reaches the grid, so that tests and integrations
+    /// can wait for output with [`Terminal::wait_for_content`] rather than polling.
+    pub fn content_version(&self) -> usize {
+        self.content_version
+    }
+
+    /// Resolves once there's output newer than `version` and `predicate` accepts the terminal,
+    /// checking again each time output arrives.
+    pub async fn wait_for_content(
+        terminal: Model<Terminal>,
+        version: usize,
+        predicate: impl Fn(&Terminal) -> bool + 'static,
+        cx: &mut AsyncAppContext,
+    ) -> Result<()> {
+        let (versions_tx, mut versions_rx) = unbounded();
+        let _subscription = cx.update(|cx| {
+            cx.subscribe(&terminal, move |_, event, _| {
+                if let Event::ContentChanged(version) = event {
+                    versions_tx.unbounded_send(*version).ok();
+                }
+            })
+        })?;
+        loop {
+            let done = terminal.update(cx, |terminal, _| {
+                content_is_newer(terminal.content_version, version) && predicate(terminal)
+            })?;
+            if done {
+                return Ok(());
+            }
+            if versions_rx.next().await.is_none() {
+                bail!("The terminal was closed before its output arrived");
+            }
+        }
     }
 
     pub fn sync(&mut self, cx: &mut ModelContext<Self>) {
@@ -1058,11 +1869,75 @@ impl Terminal {
             self.process_terminal_event(&e, &mut terminal, cx)
         }
 
-        self.last_content = Self::make_content(&terminal, &self.last_content);
+        // Selections and links found on one screen mean nothing over the other's contents
+        if switched_screens(self.last_content.mode, *terminal.mode()) {
+            terminal.selection = None;
+            self.selection_head = None;
+            self.selection_phase = SelectionPhase::Ended;
+            self.last_content.last_hovered_word = None;
+            self.hovered_word = false;
+        }
+
+        let tap_state = self.tap_state.clone();
+        let mut tap = tap_state.lock();
+        self.process_tapped_sequences(&terminal, &mut tap);
+
+        let cursor = terminal.grid().cursor.point;
+        self.local_echo
+            .reconcile(cursor, |point| terminal.grid()[point].c);
+
+        self.last_content = Self::make_content(
+            &terminal,
+            &self.last_content,
+            &self.line_attributes,
+            &tap.scroll_history,
+        );
+        self.last_content.predicted_echo = self.local_echo.visible();
+    }
+
+    /// Applies the sequences the [`PtyTap`] has seen since the last sync.
+    fn process_tapped_sequences(&mut self, term: &Term<ZedListener>, tap: &mut TapState) {
+        // The main and alternate screens have separate lines
+        if switched_screens(self.last_content.mode, *term.mode()) {
+            self.line_attributes.clear();
+        }
+
+        for event in mem::take(&mut tap.events) {
+            match event.sequence {
+                TappedSequence::LineAttribute(Some(attribute)) => {
+                    self.line_attributes.insert(event.row, attribute);
+                }
+                TappedSequence::LineAttribute(None) => {
+                    self.line_attributes.remove(&event.row);
+                }
+                TappedSequence::ResetLineAttributes => self.line_attributes.clear(),
+                // Only the main screen has a history of commands
+                TappedSequence::PromptMark(mark) if !term.mode().contains(TermMode::ALT_SCREEN) => {
+                    self.prompt_marks.push((event.row, mark));
+                }
+                TappedSequence::PromptMark(_) => {}
+            }
+        }
+
+        forget_dropped_rows(
+            &tap.scroll_history,
+            &mut self.line_attributes,
+            &mut self.prompt_marks,
+        );
     }
 
-    fn make_content(term: &Term<ZedListener>, last_content: &TerminalContent) -> TerminalContent {
+    fn make_content(
+        term: &Term<ZedListener>,
+        last_content: &TerminalContent,
+        line_attributes: &HashMap<usize, LineAttribute>,
+        scroll_history: &ScrollHistory,
+    ) -> TerminalContent {
         let content = term.renderable_content();
+        let history_size = term.grid().history_size();
+        let visible_lines = -(content.display_offset as i32)
+            ..term.screen_lines() as i32 - content.display_offset as i32;
+        let line_attributes =
+            visible_line_attributes(line_attributes, scroll_history, visible_lines);
         TerminalContent {
             cells: content
                 .display_iter
@@ -1080,12 +1955,20 @@ impl Terminal {
                 .collect::<Vec<IndexedCell>>(),
             mode: content.mode,
             display_offset: content.display_offset,
+            history_size,
+            scrollbar_shown: scrollbar_shown(
+                history_size,
+                content.mode,
+                last_content.scrollbar_shown,
+            ),
             selection_text: term.selection_to_string(),
             selection: content.selection,
             cursor: content.cursor,
             cursor_char: term.grid()[content.cursor.point].c,
             size: last_content.size,
             last_hovered_word: last_content.last_hovered_word.clone(),
+            line_attributes,
+            predicted_echo: None,
         }
     }
 
@@ -1262,6 +2145,11 @@ impl Terminal {
             if e.button == MouseButton::Left && setting.copy_on_select {
                 self.copy();
             }
+            // Like native terminals, selecting text makes it the primary selection.
+            #[cfg(target_os = "linux")]
+            if e.button == MouseButton::Left {
+                self.events.push_back(InternalEvent::CopyToPrimary);
+            }
 
             //Hyperlinks
             if self.selection_phase == SelectionPhase::Ended {
@@ -1280,38 +2168,60 @@ impl Terminal {
     }
 
     ///Scroll the terminal
-    pub fn scroll_wheel(&mut self, e: &ScrollWheelEvent, origin: Point<Pixels>) {
+    pub fn scroll_wheel(
+        &mut self,
+        e: &ScrollWheelEvent,
+        origin: Point<Pixels>,
+        cx: &mut ModelContext<Self>,
+    ) {
         let mouse_mode = self.mouse_mode(e.shift);
 
         if let Some(scroll_lines) = self.determine_scroll_lines(e, mouse_mode) {
-            if mouse_mode {
-                let point = grid_point(
-                    e.position - origin,
-                    self.last_content.size,
-                    self.last_content.display_offset,
-                );
+            match wheel_scroll(self.last_content.mode, mouse_mode, e.shift) {
+                WheelScroll::Report => {
+                    let point = grid_point(
+                        e.position - origin,
+                        self.last_content.size,
+                        self.last_content.display_offset,
+                    );
 
-                if let Some(scrolls) = scroll_report(point, scroll_lines, e, self.last_content.mode)
-                {
-                    for scroll in scrolls {
-                        self.pty_tx.notify(scroll);
-                    }
-                };
-            } else if self
-                .last_content
-                .mode
-                .contains(TermMode::ALT_SCREEN | TermMode::ALTERNATE_SCROLL)
-                && !e.shift
-            {
-                self.pty_tx.notify(alt_scroll(scroll_lines))
-            } else {
-                if scroll_lines != 0 {
-                    let scroll = AlacScroll::Delta(scroll_lines);
+                    if let Some(scrolls) =
+                        scroll_report(point, scroll_lines, e, self.last_content.mode)
+                    {
+                        for scroll in scrolls {
+                            self.pty_tx.notify(scroll);
+                        }
+                    };
+                }
+                WheelScroll::ArrowKeys => self.pty_tx.notify(alt_scroll(scroll_lines)),
+                WheelScroll::Scrollback => {
+                    if scroll_lines != 0 {
+                        let scroll = AlacScroll::Delta(scroll_lines);
 
-                    self.events.push_back(InternalEvent::Scroll(scroll));
+                        self.events.push_back(InternalEvent::Scroll(scroll));
+                    }
+                    if e.delta.precise() && TerminalSettings::get_global(cx).smooth_scroll {
+                        let display_offset = self.last_content.display_offset as i32 + scroll_lines;
+                        self.smooth_scroll_offset = sub_line_scroll_offset(
+                            self.scroll_px,
+                            self.last_content.size.line_height,
+                            display_offset.clamp(0, self.last_content.history_size as i32) as usize,
+                            self.last_content.history_size,
+                        );
+                    }
+                    return;
                 }
+                WheelScroll::Ignore => {}
             }
         }
+        // Scrolling settles on a whole line once the trackpad lets go
+        self.smooth_scroll_offset = px(0.);
+    }
+
+    /// How far below its place the grid is drawn while trackpad scrolling is part way through a
+    /// line. It's positive while scrolling up into the scrollback.
+    pub fn smooth_scroll_offset(&self) -> Pixels {
+        self.smooth_scroll_offset
     }
 
     fn refresh_hovered_word(&mut self) {
@@ -1329,17 +2239,14 @@ impl Terminal {
             }
             /* Calculate the appropriate scroll lines */
             TouchPhase::Moved => {
-                let old_offset = (self.scroll_px / line_height) as i32;
-
-                self.scroll_px += e.delta.pixel_delta(line_height).y * scroll_multiplier;
-
-                let new_offset = (self.scroll_px / line_height) as i32;
-
-                // Whenever we hit the edges, reset our stored scroll to 0
-                // so we can respond to changes in direction quickly
-                self.scroll_px %= self.last_content.size.height();
-
-                Some(new_offset - old_offset)
+                let (scroll_px, lines) = accumulate_scroll(
+                    self.scroll_px,
+                    e.delta.pixel_delta(line_height).y * scroll_multiplier,
+                    line_height,
+                    self.last_content.size.height(),
+                );
+                self.scroll_px = scroll_px;
+                Some(lines)
             }
             TouchPhase::Ended => None,
         }
@@ -1358,21 +2265,30 @@ impl Terminal {
         })
     }
 
-    pub fn title(&self, truncate: bool) -> String {
-        const MAX_CHARS: usize = 25;
-        match &self.task {
-            Some(task_state) => {
-                if truncate {
-                    truncate_and_trailoff(&task_state.label, MAX_CHARS)
-                } else {
-                    task_state.full_label.clone()
-                }
-            }
-            None => self
-                .pty_info
-                .current
-                .as_ref()
-                .map(|fpi| {
+    /// Searches the scrollback and the screen, ignoring case. Unless `regex` is set, the query
+    /// is matched literally.
+    pub fn search(&self, query: &str, regex: bool) -> Vec<SearchMatch> {
+        search_term(&self.term.lock(), query, regex)
+    }
+
+    /// The title of the terminal, shortened with the given strategy, or in full if it's `None`.
+    /// A name the user gave the terminal takes precedence over everything else.
+    pub fn title(&self, user_name: Option<&str>, truncation: Option<TitleTruncation>) -> String {
+        let truncate = truncation.is_some();
+        let (osc_title, process_title) = match &self.task {
+            Some(task_state) => (
+                "",
+                Some(match truncation {
+                    Some(TitleTruncation::Middle) => {
+                        truncate_middle(&task_state.label, MAX_TITLE_CHARS)
+                    }
+                    Some(_) => truncate_and_trailoff(&task_state.label, MAX_TITLE_CHARS),
+                    None => task_state.full_label.clone(),
+                }),
+            ),
+            None => (
+                self.breadcrumb_text.as_str(),
+                self.pty_info.current.as_ref().map(|fpi| {
                     let process_file = fpi
                         .cwd
                         .file_name()
@@ -1389,18 +2305,38 @@ impl Terminal {
                             "".to_string()
                         }
                     );
-                    let (process_file, process_name) = if truncate {
-                        (
-                            truncate_and_trailoff(&process_file, MAX_CHARS),
-                            truncate_and_trailoff(&process_name, MAX_CHARS),
-                        )
+                    let process_file = match truncation {
+                        Some(TitleTruncation::End) => {
+                            truncate_and_trailoff(&process_file, MAX_TITLE_CHARS)
+                        }
+                        Some(TitleTruncation::Middle) => {
+                            truncate_middle(&compact_home(&fpi.cwd), MAX_TITLE_CHARS)
+                        }
+                        Some(TitleTruncation::Abbreviate) => {
+                            abbreviate_path(&compact_home(&fpi.cwd), MAX_TITLE_CHARS)
+                        }
+                        None => process_file,
+                    };
+                    let process_name = if truncate {
+                        truncate_and_trailoff(&process_name, MAX_TITLE_CHARS)
                     } else {
-                        (process_file, process_name)
+                        process_name
                     };
-                    format!("{process_file} — {process_name}")
-                })
-                .unwrap_or_else(|| "Terminal".to_string()),
-        }
+                    match &self.git_branch {
+                        Some(branch) => {
+                            let branch = if truncate {
+                                truncate_and_trailoff(branch, MAX_TITLE_CHARS)
+                            } else {
+                                branch.clone()
+                            };
+                            format!("{process_file} ({branch}) — {process_name}")
+                        }
+                        None => format!("{process_file} — {process_name}"),
+                    }
+                }),
+            ),
+        };
+        resolve_title(user_name, osc_title, process_title, truncate)
     }
 
     pub fn can_navigate_to_selected_word(&self) -> bool {
@@ -1438,6 +2374,27 @@ fn regex_match_at<T>(term: &Term<T>, point: AlacPoint, regex: &mut RegexSearch)
     visible_regex_match_iter(term, regex).find(|rm| rm.contains(&point))
 }
 
+/// The `path:line:column` location in the stack frame under `point`, if it's on the location.
+fn stack_frame_location_at<T>(
+    term: &Term<T>,
+    point: AlacPoint,
+    regex: &mut RegexSearch,
+) -> Option<(String, Match)> {
+    let frame_match = regex_match_at(term, point, regex)?;
+    let frame = term.bounds_to_string(*frame_match.start(), *frame_match.end());
+    let open_paren = frame.rfind('(')?;
+    let location = &frame[open_paren + 1..frame.len() - 1];
+    let start =
+        frame_match
+            .start()
+            .add(term, Boundary::Cursor, frame[..=open_paren].chars().count());
+    let end = frame_match.end().sub(term, Boundary::Cursor, 1);
+    let location_match = Match::new(start, end);
+    location_match
+        .contains(&point)
+        .then(|| (location.to_string(), location_match))
+}
+
 /// Copied from alacritty/src/display/hint.rs:
 /// Iterate over all visible regex matches.
 pub fn visible_regex_match_iter<'a, T>(
@@ -1456,6 +2413,51 @@ pub fn visible_regex_match_iter<'a, T>(
         .take_while(move |rm| rm.start().line <= viewport_end)
 }
 
+/// A selection of everything in the scrollback and on the screen, down to the last line with
+/// text on it.
+fn all_selection<T>(term: &Term<T>) -> Selection {
+    let start = AlacPoint::new(term.topmost_line(), Column(0));
+    let mut end_line = term.bottommost_line();
+    while end_line > start.line && term.grid()[end_line].is_clear() {
+        end_line = Line(end_line.0 - 1);
+    }
+    make_selection(&(start..=AlacPoint::new(end_line, term.last_column())))
+}
+
+fn grid_snapshot<T>(term: &Term<T>, scrollback_lines: usize) -> GridSnapshot {
+    let grid = term.grid();
+    let scrollback_lines = scrollback_lines.min(grid.history_size());
+    let lines = (-(scrollback_lines as i32)..term.screen_lines() as i32)
+        .map(|line| {
+            (0..term.columns())
+                .map(|column| grid[AlacPoint::new(Line(line), Column(column))].clone())
+                .collect()
+        })
+        .collect();
+    let cursor = grid.cursor.point;
+    GridSnapshot {
+        lines,
+        cursor_line: scrollback_lines + cursor.line.0 as usize,
+        cursor_column: cursor.column.0,
+    }
+}
+
+/// The scrollback and the screen as plain text, like copying all of it.
+fn buffer_text<T>(term: &Term<T>) -> String {
+    all_selection(term)
+        .to_range(term)
+        .map(|range| copied_text(&term.bounds_to_string(range.start, range.end)))
+        .unwrap_or_default()
+}
+
+/// Text copied from the terminal, without the whitespace at the ends of its lines.
+fn copied_text(text: &str) -> String {
+    text.lines()
+        .map(str::trim_end)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 fn make_selection(range: &RangeInclusive<AlacPoint>) -> Selection {
     let mut selection = Selection::new(SelectionType::Simple, *range.start(), AlacDirection::Left);
     selection.update(*range.end(), AlacDirection::Right);
@@ -1471,118 +2473,2595 @@ fn all_search_matches<'a, T>(
     RegexIter::new(start, end, AlacDirection::Right, term, regex)
 }
 
-fn content_index_for_mouse(pos: Point<Pixels>, size: &TerminalSize) -> usize {
-    let col = (pos.x / size.cell_width()).round() as usize;
-    let clamped_col = min(col, size.columns() - 1);
-    let row = (pos.y / size.line_height()).round() as usize;
-    let clamped_row = min(row, size.screen_lines() - 1);
-    clamped_row * size.columns() + clamped_col
+/// Sends `signal` to every process in the group, returning whether it was delivered.
+#[cfg(unix)]
+fn signal_process_group(pgid: u32, signal: libc::c_int) -> bool {
+    unsafe { libc::killpg(pgid as libc::pid_t, signal) == 0 }
 }
 
-/// Converts an 8 bit ANSI color to its GPUI equivalent.
-/// Accepts `usize` for compatibility with the `alacritty::Colors` interface,
-/// Other than that use case, should only be called with values in the [0,255] range
-pub fn get_color_at_index(index: usize, theme: &Theme) -> Hsla {
-    let colors = theme.colors();
+/// The bytes that paste `text` into a terminal, wrapped in paste markers when the running
+/// program enabled bracketed paste.
+///
+/// Without bracketed paste, the text is typed as if by hand, so every line ending, whether
+/// it's `\r\n`, `\n` or `\r`, becomes the `\r` that the Enter key sends. The terminal's line
+/// discipline turns that into the newline the shell reads, while sending `\r\n` as it is would
+/// run each line and then an empty one.
+fn paste_text(text: &str, bracketed_paste: bool, strip_trailing_newline: bool) -> String {
+    let text = if strip_trailing_newline {
+        text.strip_suffix("\r\n")
+            .or_else(|| text.strip_suffix(['\n', '\r']))
+            .unwrap_or(text)
+    } else {
+        text
+    };
+    if bracketed_paste {
+        format!("{}{}{}", "\x1b[200~", text.replace('\x1b', ""), "\x1b[201~")
+    } else {
+        text.replace("\r\n", "\r").replace('\n', "\r")
+    }
+}
 
-    match index {
-        // 0-15 are the same as the named colors above
-        0 => colors.terminal_ansi_black,
-        1 => colors.terminal_ansi_red,
-        2 => colors.terminal_ansi_green,
-        3 => colors.terminal_ansi_yellow,
-        4 => colors.terminal_ansi_blue,
-        5 => colors.terminal_ansi_magenta,
-        6 => colors.terminal_ansi_cyan,
-        7 => colors.terminal_ansi_white,
-        8 => colors.terminal_ansi_bright_black,
-        9 => colors.terminal_ansi_bright_red,
-        10 => colors.terminal_ansi_bright_green,
-        11 => colors.terminal_ansi_bright_yellow,
-        12 => colors.terminal_ansi_bright_blue,
-        13 => colors.terminal_ansi_bright_magenta,
-        14 => colors.terminal_ansi_bright_cyan,
-        15 => colors.terminal_ansi_bright_white,
-        // 16-231 are mapped to their RGB colors on a 0-5 range per channel
-        16..=231 => {
-            let (r, g, b) = rgb_for_index(index as u8); // Split the index into its ANSI-RGB components
-            let step = (u8::MAX as f32 / 5.).floor() as u8; // Split the RGB range into 5 chunks, with floor so no overflow
-            rgba_color(r * step, g * step, b * step) // Map the ANSI-RGB components to an RGB color
-        }
-        // 232-255 are a 24 step grayscale from black to white
-        232..=255 => {
-            let i = index as u8 - 232; // Align index to 0..24
-            let step = (u8::MAX as f32 / 24.).floor() as u8; // Split the RGB grayscale values into 24 chunks
-            rgba_color(i * step, i * step, i * step) // Map the ANSI-grayscale components to the RGB-grayscale
-        }
-        // For compatibility with the alacritty::Colors interface
-        256 => colors.text,
-        257 => colors.background,
-        258 => theme.players().local().cursor,
-        259 => colors.terminal_ansi_dim_black,
-        260 => colors.terminal_ansi_dim_red,
-        261 => colors.terminal_ansi_dim_green,
-        262 => colors.terminal_ansi_dim_yellow,
-        263 => colors.terminal_ansi_dim_blue,
-        264 => colors.terminal_ansi_dim_magenta,
-        265 => colors.terminal_ansi_dim_cyan,
-        266 => colors.terminal_ansi_dim_white,
-        267 => colors.terminal_bright_foreground,
-        268 => colors.terminal_ansi_black, // 'Dim Background', non-standard color
+/// Whether `text` has line breaks, other than trailing ones.
+fn is_multiline(text: &str) -> bool {
+    text.trim_end_matches(['\r', '\n']).contains(['\r', '\n'])
+}
 
-        _ => black(),
+/// The bytes that type `text` into a terminal. With bracketed paste, a trailing line ending is
+/// sent after the closing paste marker, so that commands still run.
+fn injected_text(text: &str, bracketed_paste: bool) -> String {
+    if !bracketed_paste {
+        return text.to_string();
     }
+    let body = text.trim_end_matches(['\r', '\n']);
+    let line_ending = if body.len() < text.len() { "\r" } else { "" };
+    format!(
+        "\x1b[200~{}\x1b[201~{line_ending}",
+        body.replace('\x1b', "")
+    )
 }
 
-/// Generates the RGB channels in [0, 5] for a given index into the 6x6x6 ANSI color cube.
-/// See: [8 bit ANSI color](https://en.wikipedia.org/wiki/ANSI_escape_code#8-bit).
-///
-/// Wikipedia gives a formula for calculating the index for a given color:
-///
-/// ```
-/// index = 16 + 36 × r + 6 × g + b (0 ≤ r, g, b ≤ 5)
-/// ```
-///
-/// This function does the reverse, calculating the `r`, `g`, and `b` components from a given index.
-fn rgb_for_index(i: u8) -> (u8, u8, u8) {
-    debug_assert!((16..=231).contains(&i));
-    let i = i - 16;
-    let r = (i - (i % 36)) / 36;
-    let g = ((i % 36) - (i % 6)) / 6;
-    let b = (i % 36) % 6;
-    (r, g, b)
+/// The program and arguments to spawn in the PTY, or `None` for the system's shell. Login shells
+/// get a `-l` argument, so they source files like `.profile` or `.zprofile`.
+fn pty_shell(shell: Shell, login_shell: bool) -> Option<(String, Vec<String>)> {
+    let (program, mut args) = match shell {
+        Shell::System if login_shell => (system_shell()?, Vec::new()),
+        Shell::System => return None,
+        Shell::Program(program) => (program, Vec::new()),
+        Shell::WithArguments { program, args } => (program, args),
+    };
+    if login_shell && cfg!(unix) && !args.iter().any(|arg| arg == "-l" || arg == "--login") {
+        args.insert(0, "-l".to_string());
+    }
+    Some((program, args))
 }
 
-pub fn rgba_color(r: u8, g: u8, b: u8) -> Hsla {
-    Rgba {
-        r: (r as f32 / 255.),
-        g: (g as f32 / 255.),
-        b: (b as f32 / 255.),
-        a: 1.,
+/// Shells tried, after the user's, when the configured one can't be found.
+const FALLBACK_SHELLS: &[&str] = &["/bin/zsh", "/bin/bash", "/bin/sh"];
+
+/// The program set to run as the shell, if it isn't the system's.
+fn shell_program(shell: &Shell) -> Option<&str> {
+    match shell {
+        Shell::System => None,
+        Shell::Program(program) | Shell::WithArguments { program, .. } => Some(program),
     }
-    .into()
 }
 
-#[cfg(test)]
-mod tests {
-    use alacritty_terminal::{
-        index::{Column, Line, Point as AlacPoint},
-        term::cell::Cell,
-    };
-    use gpui::{point, size, Pixels};
-    use rand::{distributions::Alphanumeric, rngs::ThreadRng, thread_rng, Rng};
+/// Whether `program` is a path to an executable, or the name of one on the `PATH`.
+fn program_exists(program: &str) -> bool {
+    which::which(program).is_ok()
+}
 
-    use crate::{
-        content_index_for_mouse, rgb_for_index, IndexedCell, TerminalContent, TerminalSize,
+/// The first shell that `exists`, out of the `configured` one, the user's, and
+/// [`FALLBACK_SHELLS`].
+fn resolve_shell_program(
+    configured: &str,
+    system_shell: Option<String>,
+    exists: impl Fn(&str) -> bool,
+) -> Result<String, String> {
+    let candidates = std::iter::once(configured.to_string())
+        .chain(system_shell)
+        .chain(FALLBACK_SHELLS.iter().map(|shell| shell.to_string()))
+        .collect::<Vec<_>>();
+    candidates
+        .iter()
+        .find(|shell| exists(shell))
+        .cloned()
+        .ok_or_else(|| format!("none of the shells {} exist", candidates.join(", ")))
+}
+
+/// Adds the variables in the `.env` file in `working_directory` that aren't set already.
+fn add_dotenv(env: &mut HashMap<String, String>, working_directory: &Path) {
+    for (key, value) in env_file::load(&working_directory.join(".env")) {
+        env.entry(key).or_insert(value);
+    }
+}
+
+/// The environment to start the shell with: the `env` setting, then the `.env` file in the
+/// working directory if `dotenv` is on, then the variables the terminal sets itself.
+fn shell_env(
+    mut env: HashMap<String, String>,
+    working_directory: Option<&Path>,
+    dotenv: bool,
+    term: &str,
+) -> HashMap<String, String> {
+    if let Some(working_directory) = working_directory.filter(|_| dotenv) {
+        add_dotenv(&mut env, working_directory);
+    }
+
+    // TODO: Properly set the current locale,
+    env.entry("LC_ALL".to_string())
+        .or_insert_with(|| "en_US.UTF-8".to_string());
+
+    env.insert("ZED_TERM".to_string(), "true".to_string());
+    add_term_env(&mut env, term);
+    env
+}
+
+/// The user's shell, which is what alacritty runs when there's no shell set.
+fn system_shell() -> Option<String> {
+    if cfg!(unix) {
+        env::var("SHELL").ok().filter(|shell| !shell.is_empty())
+    } else {
+        None
+    }
+}
+
+/// The text to put on the clipboard for an OSC 52 sequence alacritty decoded, if writing to
+/// the clipboard is `allowed` and the text isn't too long.
+fn clipboard_write(text: &str, allowed: bool) -> Option<String> {
+    (allowed && text.len() <= MAX_CLIPBOARD_WRITE_LEN).then(|| text.to_string())
+}
+
+/// The event for the terminal's process exiting, naming it by the task's label, or by the last
+/// foreground process's command line.
+fn process_exited_event(
+    task_label: Option<&str>,
+    process: Option<&ProcessInfo>,
+    exit_code: i32,
+) -> Event {
+    let command = match (task_label, process) {
+        (Some(label), _) => label.to_string(),
+        (None, Some(process)) if !process.argv.is_empty() => process.argv.join(" "),
+        (None, Some(process)) => process.name.clone(),
+        (None, None) => "The shell".to_string(),
     };
+    Event::ProcessExited { command, exit_code }
+}
 
-    #[test]
-    fn test_rgb_for_index() {
-        // Test every possible value in the color cube.
-        for i in 16..=231 {
-            let (r, g, b) = rgb_for_index(i);
-            assert_eq!(i, 16 + 36 * r + 6 * g + b);
+/// The event a terminal that isn't running a task emits when its shell exits, if any.
+fn exit_event(on_exit: OnExit) -> Option<Event> {
+    match on_exit {
+        OnExit::Close => Some(Event::CloseTerminal),
+        OnExit::KeepOpen => None,
+        OnExit::Restart => Some(Event::RestartTerminal),
+    }
+}
+
+fn enter_after_exit_event(enter_after_exit: EnterAfterExit) -> Event {
+    match enter_after_exit {
+        EnterAfterExit::Close => Event::CloseTerminal,
+        EnterAfterExit::Restart => Event::RestartTerminal,
+    }
+}
+
+/// The selection made by double-clicking at `point`: the word under it, bounded by the
+/// terminal's semantic escape characters, or the whole run of blanks when `point` is blank.
+fn word_selection<T>(term: &Term<T>, point: AlacPoint) -> Selection {
+    let line = &term.grid()[point.line];
+    let is_blank = |column: Column| matches!(line[column].c, ' ' | '\t');
+    if !is_blank(point.column) {
+        return Selection::new(SelectionType::Semantic, point, AlacDirection::Left);
+    }
+
+    let mut start = point.column;
+    while start > Column(0) && is_blank(start - 1) {
+        start -= 1;
+    }
+    let mut end = point.column;
+    while end < term.last_column() && is_blank(end + 1) {
+        end += 1;
+    }
+
+    let mut selection = Selection::new(
+        SelectionType::Simple,
+        AlacPoint::new(point.line, start),
+        AlacDirection::Left,
+    );
+    selection.update(AlacPoint::new(point.line, end), AlacDirection::Right);
+    selection
+}
+
+/// The URI of the OSC 8 hyperlink at `point`, and the cells it spans. A link can continue
+/// across lines, like when it wraps.
+fn hyperlink_at<T>(term: &Term<T>, point: AlacPoint) -> Option<(String, Match)> {
+    let link = term.grid().index(point).hyperlink()?;
+    let same_link = |point: AlacPoint| term.grid().index(point).hyperlink().as_ref() == Some(&link);
+
+    let mut min_index = point;
+    loop {
+        let new_min_index = min_index.sub(term, Boundary::Cursor, 1);
+        if new_min_index == min_index || !same_link(new_min_index) {
+            break;
+        }
+        min_index = new_min_index;
+    }
+
+    let mut max_index = point;
+    loop {
+        let new_max_index = max_index.add(term, Boundary::Cursor, 1);
+        if new_max_index == max_index || !same_link(new_max_index) {
+            break;
         }
+        max_index = new_max_index;
+    }
+
+    Some((link.uri().to_owned(), min_index..=max_index))
+}
+
+fn search_term<T>(term: &Term<T>, query: &str, regex: bool) -> Vec<SearchMatch> {
+    if query.is_empty() {
+        return Vec::new();
+    }
+    let pattern = if regex {
+        query.to_string()
+    } else {
+        regex::escape(query)
+    };
+    let Ok(mut searcher) = RegexSearch::new(&format!("(?i){pattern}")) else {
+        return Vec::new();
+    };
+    all_search_matches(term, &mut searcher)
+        .map(|search_match| SearchMatch {
+            start: *search_match.start(),
+            end: *search_match.end(),
+        })
+        .collect()
+}
+
+/// Forgets the attributes and marks of lines that were dropped from the scrollback, since their
+/// rows are never shown again.
+fn forget_dropped_rows(
+    history: &ScrollHistory,
+    line_attributes: &mut HashMap<usize, LineAttribute>,
+    prompt_marks: &mut Vec<(usize, PromptMark)>,
+) {
+    let first_row = history.first_row();
+    line_attributes.retain(|row, _| *row >= first_row);
+    prompt_marks.retain(|(row, _)| *row >= first_row);
+}
+
+/// The attributes of the lines on the grid's `visible_lines`, keyed by line.
+fn visible_line_attributes(
+    line_attributes: &HashMap<usize, LineAttribute>,
+    history: &ScrollHistory,
+    visible_lines: Range<i32>,
+) -> HashMap<i32, LineAttribute> {
+    line_attributes
+        .iter()
+        .filter_map(|(row, attribute)| Some((history.line(*row)?.0, *attribute)))
+        .filter(|(line, _)| visible_lines.contains(line))
+        .collect()
+}
+
+/// The rows holding the output of the last command that finished. The output starts on the row of its `C` mark, and ends on the row before its `D`
+/// mark, where the next prompt is printed.
+fn last_command_output_rows(marks: &[(usize, PromptMark)]) -> Option<RangeInclusive<usize>> {
+    let is_end = |mark: &PromptMark| matches!(mark, PromptMark::CommandEnd { .. });
+    let end_ix = marks.iter().rposition(|(_, mark)| is_end(mark))?;
+    let end_row = marks[end_ix].0;
+    let start_row = marks[..end_ix]
+        .iter()
+        .rev()
+        .take_while(|(_, mark)| !is_end(mark))
+        .find(|(_, mark)| *mark == PromptMark::OutputStart)?
+        .0;
+    (end_row > start_row).then(|| start_row..=end_row - 1)
+}
+
+/// The text on `rows`, leaving out those that are no longer in the scrollback.
+fn command_output_text<T>(
+    term: &Term<T>,
+    history: &ScrollHistory,
+    rows: RangeInclusive<usize>,
+) -> Option<String> {
+    let start_row = cmp::max(*rows.start(), history.first_row());
+    let start_line = history.line(start_row)?;
+    let end_line = history.line(*rows.end())?;
+    if end_line < start_line {
+        return None;
+    }
+    Some(term.bounds_to_string(
+        AlacPoint::new(start_line, Column(0)),
+        AlacPoint::new(end_line, term.last_column()),
+    ))
+}
+
+/// The row of the nearest prompt before `top_row` for [`AlacDirection::Left`], or after it for
+/// [`AlacDirection::Right`].
+fn adjacent_prompt_row(
+    marks: &[(usize, PromptMark)],
+    top_row: usize,
+    direction: AlacDirection,
+) -> Option<usize> {
+    let prompt_rows = marks
+        .iter()
+        .filter(|(_, mark)| *mark == PromptMark::PromptStart)
+        .map(|(row, _)| *row);
+    match direction {
+        AlacDirection::Left => prompt_rows.filter(|row| *row < top_row).max(),
+        AlacDirection::Right => prompt_rows.filter(|row| *row > top_row).min(),
+    }
+}
+
+/// `directory` if it still exists, or else the home directory, so that a terminal can be
+/// started again after its directory was deleted.
+pub fn existing_directory_or_home(directory: Option<PathBuf>) -> Option<PathBuf> {
+    directory
+        .filter(|directory| directory.is_dir())
+        .or_else(dirs::home_dir)
+}
+
+/// `TERM` values whose terminfo entries describe what the terminal supports. Others are still
+/// used, but may be missing from the system or leave programs thinking features are missing.
+const KNOWN_TERMS: &[&str] = &[
+    "xterm-256color",
+    "xterm-direct",
+    "xterm",
+    "alacritty",
+    "screen-256color",
+    "tmux-256color",
+];
+
+/// Sets `TERM` in the shell's environment, unless it was set already, and `COLORTERM` too
+/// when the `TERM` is one with more than 16 colors, to advertise 24-bit color.
+fn add_term_env(env: &mut HashMap<String, String>, term: &str) {
+    if !KNOWN_TERMS.contains(&term) {
+        log::warn!("unknown terminal TERM {term:?}, using it anyway");
+    }
+    let term = env
+        .entry("TERM".to_string())
+        .or_insert_with(|| term.to_string())
+        .clone();
+    let colorful = term.ends_with("-256color") || term.ends_with("-direct") || term == "alacritty";
+    if colorful {
+        env.entry("COLORTERM".to_string())
+            .or_insert_with(|| "truecolor".to_string());
+    }
+}
+
+/// Drops the lines scrolled off the top of the screen, scrolling back down to the screen if
+/// they were being looked at.
+fn clear_scrollback<T: EventListener>(term: &mut Term<T>) {
+    term.clear_screen(ClearMode::Saved);
+    term.scroll_display(AlacScroll::Bottom);
+}
+
+/// Whether `current` is a later content version than `version`, allowing for the counter
+/// wrapping around.
+fn content_is_newer(current: usize, version: usize) -> bool {
+    current != version && current.wrapping_sub(version) < usize::MAX / 2
+}
+
+/// Adds a wheel `delta` to the pixels scrolled so far, returning the new total and how many whole
+/// lines it moved the display by. Deltas smaller than a line add up until they make one.
+fn accumulate_scroll(
+    scroll_px: Pixels,
+    delta: Pixels,
+    line_height: Pixels,
+    viewport_height: Pixels,
+) -> (Pixels, i32) {
+    let old_offset = (scroll_px / line_height) as i32;
+    let scroll_px = scroll_px + delta;
+    let new_offset = (scroll_px / line_height) as i32;
+
+    // Whenever we hit the edges, reset our stored scroll to 0
+    // so we can respond to changes in direction quickly
+    (scroll_px % viewport_height, new_offset - old_offset)
+}
+
+/// The part of a line that `scroll_px` has scrolled past the whole lines it moved the display by.
+/// There's nothing to show beyond the top of the scrollback or the bottom of the screen, so the
+/// offset is dropped there.
+fn sub_line_scroll_offset(
+    scroll_px: Pixels,
+    line_height: Pixels,
+    display_offset: usize,
+    history_size: usize,
+) -> Pixels {
+    let offset = scroll_px % line_height;
+    if (offset > px(0.) && display_offset >= history_size)
+        || (offset < px(0.) && display_offset == 0)
+    {
+        px(0.)
+    } else {
+        offset
+    }
+}
+
+fn cursor_visible(mode: &TermMode) -> bool {
+    mode.intersects(TermMode::SHOW_CURSOR | TermMode::VI)
+}
+
+/// The scrollbar is only shown once there's scrollback to scroll through. The alternate screen
+/// has no scrollback of its own, so it keeps whatever the primary screen had, rather than
+/// resizing the terminal each time a full screen program starts or quits.
+fn scrollbar_shown(history_size: usize, mode: TermMode, was_shown: bool) -> bool {
+    if mode.contains(TermMode::ALT_SCREEN) {
+        was_shown
+    } else {
+        history_size > 0
+    }
+}
+
+/// Whether the terminal moved between its main and alternate screens, which don't share any lines.
+fn switched_screens(previous: TermMode, current: TermMode) -> bool {
+    previous.contains(TermMode::ALT_SCREEN) != current.contains(TermMode::ALT_SCREEN)
+}
+
+/// What a key does in copy mode: vi's motions, arrows, home and end move the cursor, `v` starts
+/// or drops a selection, `y` copies it, and escape or `q` leave. Other keys do nothing.
+pub fn copy_mode_command(keystroke: &Keystroke) -> Option<CopyModeCommand> {
+    let modifiers = &keystroke.modifiers;
+    if modifiers.control || modifiers.alt || modifiers.platform || modifiers.function {
+        return None;
+    }
+    let motion = match keystroke.ime_key.as_deref().unwrap_or(&keystroke.key) {
+        "h" | "left" => ViMotion::Left,
+        "j" | "down" => ViMotion::Down,
+        "k" | "up" => ViMotion::Up,
+        "l" | "right" => ViMotion::Right,
+        "w" => ViMotion::WordRight,
+        "b" => ViMotion::WordLeft,
+        "e" => ViMotion::WordRightEnd,
+        "0" | "home" => ViMotion::First,
+        "^" => ViMotion::FirstOccupied,
+        "$" | "end" => ViMotion::Last,
+        "H" => ViMotion::High,
+        "M" => ViMotion::Middle,
+        "L" => ViMotion::Low,
+        "v" => return Some(CopyModeCommand::ToggleSelection),
+        "y" => return Some(CopyModeCommand::Yank),
+        "escape" | "q" => return Some(CopyModeCommand::Exit),
+        _ => return None,
+    };
+    Some(CopyModeCommand::Move(motion))
+}
+
+/// Enters copy mode with its cursor where the terminal's is, or leaves it.
+fn toggle_copy_mode<T: EventListener>(term: &mut Term<T>) {
+    if term.mode().contains(TermMode::VI) {
+        term.selection = None;
+    }
+    term.toggle_vi_mode();
+}
+
+/// Runs a copy mode command, returning the text to put on the clipboard for a yank.
+fn run_copy_mode_command<T: EventListener>(
+    term: &mut Term<T>,
+    command: CopyModeCommand,
+) -> Option<String> {
+    if !term.mode().contains(TermMode::VI) {
+        return None;
+    }
+    match command {
+        CopyModeCommand::Move(motion) => term.vi_motion(motion),
+        CopyModeCommand::ToggleSelection => {
+            if term.selection.take().is_none() {
+                // The selection covers the cell under the cursor from the start
+                let point = term.vi_mode_cursor.point;
+                let mut selection = Selection::new(SelectionType::Simple, point, Side::Left);
+                selection.update(point, Side::Right);
+                term.selection = Some(selection);
+            }
+        }
+        CopyModeCommand::Yank => {
+            let text = term.selection_to_string().filter(|text| !text.is_empty());
+            toggle_copy_mode(term);
+            return text;
+        }
+        CopyModeCommand::Exit => toggle_copy_mode(term),
+    }
+    None
+}
+
+fn match_count_label(active_match: Option<usize>, match_count: usize) -> Option<String> {
+    if match_count == 0 {
+        return None;
+    }
+    let position = active_match.map_or(0, |index| index + 1);
+    Some(format!("{position}/{match_count}"))
+}
+
+fn adjacent_match_index(
+    matches: &[RangeInclusive<AlacPoint>],
+    from: AlacPoint,
+    direction: AlacDirection,
+) -> Option<usize> {
+    if matches.is_empty() {
+        return None;
+    }
+    let index = match direction {
+        AlacDirection::Right => matches
+            .iter()
+            .position(|search_match| *search_match.start() > from)
+            .unwrap_or(0),
+        AlacDirection::Left => matches
+            .iter()
+            .rposition(|search_match| *search_match.end() < from)
+            .unwrap_or(matches.len() - 1),
+    };
+    Some(index)
+}
+
+/// Finds the branch checked out for the repository containing `dir` by reading `.git/HEAD`.
+/// Returns the abbreviated commit sha when the HEAD is detached.
+fn git_branch_for_dir(dir: &Path) -> Option<String> {
+    let dot_git = dir
+        .ancestors()
+        .map(|ancestor| ancestor.join(".git"))
+        .find(|dot_git| dot_git.exists())?;
+
+    // Worktrees and submodules use a `.git` file pointing to the actual git directory.
+    let git_dir = if dot_git.is_file() {
+        let contents = std::fs::read_to_string(&dot_git).ok()?;
+        let git_dir = Path::new(contents.trim().strip_prefix("gitdir:")?.trim());
+        dot_git.parent()?.join(git_dir)
+    } else {
+        dot_git
+    };
+
+    let head = std::fs::read_to_string(git_dir.join("HEAD")).ok()?;
+    parse_git_head(&head)
+}
+
+fn parse_git_head(head: &str) -> Option<String> {
+    let head = head.trim();
+    if let Some(reference) = head.strip_prefix("ref:") {
+        let reference = reference.trim();
+        Some(
+            reference
+                .strip_prefix("refs/heads/")
+                .unwrap_or(reference)
+                .to_string(),
+        )
+    } else if head.len() >= 7 && head.chars().all(|c| c.is_ascii_hexdigit()) {
+        Some(head[..7].to_string())
+    } else {
+        None
+    }
+}
+
+/// Whether a host reported in an OSC 7 URL is this machine. Shells in an SSH session report
+/// directories on the remote host, which we can't use.
+fn is_local_host(host: &str) -> bool {
+    host.is_empty()
+        || host == "localhost"
+        || sysinfo::System::host_name().map_or(false, |name| name.eq_ignore_ascii_case(host))
+}
+
+/// How many lines after an error line to look for its location.
+const ERROR_LOCATION_LOOKAHEAD: usize = 2;
+
+lazy_static! {
+    /// A `path:line` or `path:line:column`, where the path has at least one letter in it so
+    /// timestamps don't match.
+    static ref PATH_WITH_POSITION_REGEX: Regex =
+        Regex::new(r#"[^\s:"'`()\[\]<>]*[A-Za-z][^\s:"'`()\[\]<>]*:\d+(:\d+)?"#).unwrap();
+}
+
+fn find_error_locations(output: &str, error_regex: &Regex) -> Vec<String> {
+    let lines = output.lines().collect::<Vec<_>>();
+    let mut locations = Vec::new();
+    for (ix, line) in lines.iter().enumerate() {
+        if !error_regex.is_match(line) {
+            continue;
+        }
+        let following_lines = lines[ix + 1..]
+            .iter()
+            .take(ERROR_LOCATION_LOOKAHEAD)
+            .take_while(|line| !error_regex.is_match(line));
+        if let Some(location) = std::iter::once(line)
+            .chain(following_lines)
+            .find_map(|line| PATH_WITH_POSITION_REGEX.find(line))
+        {
+            locations.push(location.as_str().to_string());
+        }
+    }
+    locations
+}
+
+/// Splits a paste into chunks of at most `chunk_size` bytes, without
+/// breaking up any characters.
+fn paste_chunks(text: &str, chunk_size: usize) -> Vec<String> {
+    // Every chunk must fit at least one character.
+    let chunk_size = chunk_size.max(4);
+    let mut chunks = Vec::new();
+    let mut rest = text;
+    while !rest.is_empty() {
+        let mut end = rest.len().min(chunk_size);
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        let (chunk, tail) = rest.split_at(end);
+        chunks.push(chunk.to_string());
+        rest = tail;
+    }
+    chunks
+}
+
+/// Runs `f` once [`PTY_RESIZE_DEBOUNCE`] has passed, unless the returned task is dropped first.
+fn debounce(executor: &BackgroundExecutor, f: impl FnOnce() + Send + 'static) -> Task<()> {
+    let timer = executor.timer(PTY_RESIZE_DEBOUNCE);
+    executor.spawn(async move {
+        timer.await;
+        f();
+    })
+}
+
+/// What scrolling the mouse wheel does.
+#[derive(Debug, PartialEq)]
+enum WheelScroll {
+    /// Report the wheel to the program, which asked for mouse events.
+    Report,
+    /// Send the program arrow keys, as it's on the alternate screen and asked for them.
+    ArrowKeys,
+    /// Scroll through the scrollback.
+    Scrollback,
+    /// Nothing, as the alternate screen has no scrollback to scroll through.
+    Ignore,
+}
+
+fn wheel_scroll(mode: TermMode, mouse_mode: bool, shift: bool) -> WheelScroll {
+    if mouse_mode {
+        WheelScroll::Report
+    } else if !mode.contains(TermMode::ALT_SCREEN) {
+        WheelScroll::Scrollback
+    } else if mode.contains(TermMode::ALTERNATE_SCROLL) && !shift {
+        WheelScroll::ArrowKeys
+    } else {
+        WheelScroll::Ignore
+    }
+}
+
+/// Resizes the grid to `new_size`, which is first made large enough for at least one cell.
+/// Lines on the main screen that were wrapped, including those in the scrollback, are reflowed
+/// to the new width, and the cursor stays on the same character. Returns the size used.
+fn resize_term<T>(term: &mut Term<T>, mut new_size: TerminalSize) -> TerminalSize {
+    new_size.size.height = cmp::max(new_size.line_height, new_size.height());
+    new_size.size.width = cmp::max(new_size.cell_width, new_size.width());
+    term.resize(new_size);
+    new_size
+}
+
+/// The name of the foreground process, when it's not the shell the terminal was started with.
+fn running_process_name(
+    shell_pid: u32,
+    foreground_pid: u32,
+    foreground_name: Option<&str>,
+) -> Option<String> {
+    if foreground_pid == shell_pid {
+        return None;
+    }
+    Some(foreground_name.map_or_else(|| format!("process {foreground_pid}"), str::to_string))
+}
+
+/// What to send a shell to make it clear the screen and redraw its prompt, if `process_name` is a
+/// known shell. Most line editors redraw on Ctrl-L, but `cmd` needs an actual command.
+fn shell_clear_input(process_name: &str) -> Option<&'static str> {
+    let process_name = process_name
+        .strip_suffix(".exe")
+        .unwrap_or(process_name)
+        .trim_start_matches('-');
+    match process_name {
+        "bash" | "zsh" | "fish" | "sh" | "dash" | "ksh" | "nu" | "elvish" | "xonsh" | "pwsh"
+        | "powershell" => Some("\x0c"),
+        "cmd" => Some("cls\r"),
+        _ => None,
+    }
+}
+
+/// Replaces the user's home directory at the start of the path with `~`.
+fn compact_home(path: &Path) -> String {
+    match path.strip_prefix(HOME.as_path()) {
+        Ok(relative) if relative.as_os_str().is_empty() => "~".to_string(),
+        Ok(relative) => format!("~/{}", relative.to_string_lossy()),
+        Err(_) => path.to_string_lossy().to_string(),
+    }
+}
+
+/// Makes a title a program set safe to show in a tab: whitespace like newlines becomes spaces,
+/// other control characters are removed, and it's cut off past [`MAX_OSC_TITLE_CHARS`]. Alacritty
+/// already leaves out the parts of the title that aren't valid UTF-8.
+fn sanitize_title(title: &str) -> String {
+    let title = title
+        .chars()
+        .filter_map(|c| match c {
+            c if c.is_control() && c.is_whitespace() => Some(' '),
+            c if c.is_control() => None,
+            c => Some(c),
+        })
+        .take(MAX_OSC_TITLE_CHARS)
+        .collect::<String>();
+    title.trim().to_string()
+}
+
+/// The title an alacritty event sets, empty when it resets the title.
+fn osc_title(event: &AlacTermEvent) -> Option<String> {
+    match event {
+        AlacTermEvent::Title(title) => Some(sanitize_title(title)),
+        AlacTermEvent::ResetTitle => Some(String::new()),
+        _ => None,
+    }
+}
+
+/// Picks a terminal's title: the name the user gave it, then the title the program running in it
+/// set with OSC 0 or 2, then the title made from its foreground process, then "Terminal".
+fn resolve_title(
+    user_name: Option<&str>,
+    osc_title: &str,
+    process_title: Option<String>,
+    truncate: bool,
+) -> String {
+    let set_title = [user_name, Some(osc_title)]
+        .into_iter()
+        .flatten()
+        .map(str::trim)
+        .find(|title| !title.is_empty());
+    match set_title {
+        Some(title) if truncate => truncate_and_trailoff(title, MAX_TITLE_CHARS),
+        Some(title) => title.to_string(),
+        None => process_title.unwrap_or_else(|| "Terminal".to_string()),
+    }
+}
+
+/// Cuts characters out of the middle of the string if it's longer than `max_chars`,
+/// so that both of its ends stay visible.
+fn truncate_middle(s: &str, max_chars: usize) -> String {
+    debug_assert!(max_chars >= 5);
+
+    let char_count = s.chars().count();
+    if char_count <= max_chars {
+        return s.to_string();
+    }
+    let kept = max_chars - 1;
+    let head = kept - kept / 2;
+    let tail = kept / 2;
+    let start: String = s.chars().take(head).collect();
+    let end: String = s.chars().skip(char_count - tail).collect();
+    format!("{start}…{end}")
+}
+
+/// Shortens every directory but the last to its first character (keeping the leading `.` of
+/// hidden directories). If that isn't enough, the directories between the first and the
+/// last are left out entirely.
+fn abbreviate_path(path: &str, max_chars: usize) -> String {
+    let components = path.split('/').collect::<Vec<_>>();
+    let Some((last, rest)) = components.split_last() else {
+        return path.to_string();
+    };
+    if rest.is_empty() {
+        return truncate_and_trailoff(last, max_chars);
+    }
+
+    let abbreviated = rest
+        .iter()
+        .map(|component| {
+            let prefix_len = if component.starts_with('.') { 2 } else { 1 };
+            component.chars().take(prefix_len).collect::<String>()
+        })
+        .chain(Some(last.to_string()))
+        .collect::<Vec<_>>()
+        .join("/");
+    if abbreviated.chars().count() <= max_chars {
+        return abbreviated;
+    }
+
+    let collapsed = if rest.len() > 1 {
+        format!("{}/…/{last}", rest[0])
+    } else {
+        format!("{}/{last}", rest[0])
+    };
+    if collapsed.chars().count() <= max_chars {
+        collapsed
+    } else {
+        truncate_and_trailoff(last, max_chars)
+    }
+}
+
+fn content_index_for_mouse(pos: Point<Pixels>, size: &TerminalSize) -> usize {
+    let col = (pos.x / size.cell_width()).round() as usize;
+    let clamped_col = min(col, size.columns() - 1);
+    let row = (pos.y / size.line_height()).round() as usize;
+    let clamped_row = min(row, size.screen_lines() - 1);
+    clamped_row * size.columns() + clamped_col
+}
+
+/// The theme the terminal's colors come from: the one named by the `theme` setting, or the
+/// editor's when it's unset or isn't installed.
+pub fn terminal_theme(cx: &AppContext) -> Arc<Theme> {
+    let name = TerminalSettings::get_global(cx).theme.as_deref();
+    theme_named(name, &ThemeRegistry::global(cx), cx.theme())
+}
+
+fn theme_named(name: Option<&str>, registry: &ThemeRegistry, fallback: &Arc<Theme>) -> Arc<Theme> {
+    name.and_then(|name| registry.get(name).ok())
+        .unwrap_or_else(|| fallback.clone())
+}
+
+/// Logs that the `theme` setting names a theme that isn't installed, unless it's the one in
+/// `warned_name`, which tracks the theme last warned about.
+pub fn warn_about_missing_theme(warned_name: &mut Option<String>, cx: &AppContext) {
+    let name = TerminalSettings::get_global(cx).theme.as_deref();
+    if let Some(warning) = missing_theme_warning(name, &ThemeRegistry::global(cx), warned_name) {
+        log::warn!("{warning}");
+    }
+}
+
+fn missing_theme_warning(
+    name: Option<&str>,
+    registry: &ThemeRegistry,
+    warned_name: &mut Option<String>,
+) -> Option<String> {
+    let error = name.and_then(|name| registry.get(name).err());
+    let Some((name, error)) = name.zip(error) else {
+        *warned_name = None;
+        return None;
+    };
+    if warned_name.as_deref() == Some(name) {
+        return None;
+    }
+    *warned_name = Some(name.to_string());
+    Some(format!("invalid terminal theme: {error}"))
+}
+
+/// Converts an 8 bit ANSI color to its GPUI equivalent.
+/// Accepts `usize` for compatibility with the `alacritty::Colors` interface,
+/// Other than that use case, should only be called with values in the [0,255] range
+pub fn get_color_at_index(index: usize, theme: &Theme) -> Hsla {
+    let colors = theme.colors();
+
+    match index {
+        // 0-15 are the same as the named colors above
+        0 => colors.terminal_ansi_black,
+        1 => colors.terminal_ansi_red,
+        2 => colors.terminal_ansi_green,
+        3 => colors.terminal_ansi_yellow,
+        4 => colors.terminal_ansi_blue,
+        5 => colors.terminal_ansi_magenta,
+        6 => colors.terminal_ansi_cyan,
+        7 => colors.terminal_ansi_white,
+        8 => colors.terminal_ansi_bright_black,
+        9 => colors.terminal_ansi_bright_red,
+        10 => colors.terminal_ansi_bright_green,
+        11 => colors.terminal_ansi_bright_yellow,
+        12 => colors.terminal_ansi_bright_blue,
+        13 => colors.terminal_ansi_bright_magenta,
+        14 => colors.terminal_ansi_bright_cyan,
+        15 => colors.terminal_ansi_bright_white,
+        // 16-231 are mapped to their RGB colors on a 0-5 range per channel
+        16..=231 => {
+            let (r, g, b) = rgb_for_index(index as u8); // Split the index into its ANSI-RGB components
+            let step = (u8::MAX as f32 / 5.).floor() as u8; // Split the RGB range into 5 chunks, with floor so no overflow
+            rgba_color(r * step, g * step, b * step) // Map the ANSI-RGB components to an RGB color
+        }
+        // 232-255 are a 24 step grayscale from black to white
+        232..=255 => {
+            let i = index as u8 - 232; // Align index to 0..24
+            let step = (u8::MAX as f32 / 24.).floor() as u8; // Split the RGB grayscale values into 24 chunks
+            rgba_color(i * step, i * step, i * step) // Map the ANSI-grayscale components to the RGB-grayscale
+        }
+        // For compatibility with the alacritty::Colors interface
+        256 => colors.text,
+        257 => colors.background,
+        258 => theme.players().local().cursor,
+        259 => colors.terminal_ansi_dim_black,
+        260 => colors.terminal_ansi_dim_red,
+        261 => colors.terminal_ansi_dim_green,
+        262 => colors.terminal_ansi_dim_yellow,
+        263 => colors.terminal_ansi_dim_blue,
+        264 => colors.terminal_ansi_dim_magenta,
+        265 => colors.terminal_ansi_dim_cyan,
+        266 => colors.terminal_ansi_dim_white,
+        267 => colors.terminal_bright_foreground,
+        268 => colors.terminal_ansi_black, // 'Dim Background', non-standard color
+
+        _ => black(),
+    }
+}
+
+/// Generates the RGB channels in [0, 5] for a given index into the 6x6x6 ANSI color cube.
+/// See: [8 bit ANSI color](https://en.wikipedia.org/wiki/ANSI_escape_code#8-bit).
+///
+/// Wikipedia gives a formula for calculating the index for a given color:
+///
+/// ```
+/// index = 16 + 36 × r + 6 × g + b (0 ≤ r, g, b ≤ 5)
+/// ```
+///
+/// This function does the reverse, calculating the `r`, `g`, and `b` components from a given index.
+fn rgb_for_index(i: u8) -> (u8, u8, u8) {
+    debug_assert!((16..=231).contains(&i));
+    let i = i - 16;
+    let r = (i - (i % 36)) / 36;
+    let g = ((i % 36) - (i % 6)) / 6;
+    let b = (i % 36) % 6;
+    (r, g, b)
+}
+
+pub fn rgba_color(r: u8, g: u8, b: u8) -> Hsla {
+    Rgba {
+        r: (r as f32 / 255.),
+        g: (g as f32 / 255.),
+        b: (b as f32 / 255.),
+        a: 1.,
+    }
+    .into()
+}
+
+/// A terminal of `columns` by `lines` cells that's been sent `bytes`, as if a shell printed them.
+#[cfg(any(test, feature = "test-support"))]
+pub fn term_with(
+    bytes: &[u8],
+    columns: usize,
+    lines: usize,
+) -> Term<alacritty_terminal::event::VoidListener> {
+    let (cell_width, line_height) = (px(5.), px(10.));
+    let terminal_size = TerminalSize::new(
+        line_height,
+        cell_width,
+        gpui::size(cell_width * columns as f32, line_height * lines as f32),
+    );
+    let mut term = Term::new(
+        Config::default(),
+        &terminal_size,
+        alacritty_terminal::event::VoidListener,
+    );
+    let mut processor: alacritty_terminal::vte::ansi::Processor =
+        alacritty_terminal::vte::ansi::Processor::new();
+    for byte in bytes {
+        processor.advance(&mut term, *byte);
+    }
+    term
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(unix)]
+    use alacritty_terminal::{
+        event::OnResize,
+        tty::{ChildEvent, EventedPty, EventedReadWrite},
+    };
+    use alacritty_terminal::{
+        event::{Event as AlacTermEvent, EventListener, VoidListener, WindowSize},
+        grid::{Dimensions, Scroll as AlacScroll},
+        index::{Column, Direction as AlacDirection, Line, Point as AlacPoint},
+        term::{
+            cell::Cell,
+            search::RegexSearch,
+            selection::{Selection, SelectionType},
+            Config, TermMode,
+        },
+        vi_mode::ViMotion,
+        vte::ansi::{CursorShape as AlacCursorShape, CursorStyle, Processor},
+        Term,
+    };
+    use collections::HashMap;
+    use gpui::{point, px, size, Keystroke, Pixels, TestAppContext};
+    #[cfg(unix)]
+    use polling::{Event as PollingEvent, PollMode, Poller};
+    use rand::{distributions::Alphanumeric, rngs::ThreadRng, thread_rng, Rng};
+    use regex::Regex;
+    #[cfg(unix)]
+    use std::{
+        io::{self, Read as _},
+        os::unix::net::UnixStream,
+        time::Duration,
+    };
+    use std::{
+        path::PathBuf,
+        sync::{Arc, Mutex},
+    };
+    use theme::{Theme, ThemeRegistry};
+
+    use crate::{
+        abbreviate_path, accumulate_scroll, add_dotenv, add_term_env, adjacent_match_index,
+        adjacent_prompt_row, all_selection, buffer_text, clear_scrollback, clipboard_write,
+        command_output_text, content_index_for_mouse, content_is_newer, copied_text,
+        copy_mode_command, cursor_visible, enter_after_exit_event, existing_directory_or_home,
+        exit_event, find_error_locations, forget_dropped_rows, get_color_at_index, grid_snapshot,
+        hyperlink_at, injected_text, is_multiline, last_command_output_rows,
+        mappings::keys::to_esc_str,
+        match_count_label, missing_theme_warning, osc_title, parse_git_head, paste_chunks,
+        paste_text, process_exited_event,
+        pty_info::ProcessInfo,
+        pty_shell,
+        pty_tap::TapParser,
+        regex_match_at, resize_term, resolve_shell_program, resolve_title, rgb_for_index,
+        rgba_color, run_copy_mode_command, running_process_name, sanitize_title, scrollbar_shown,
+        search_term, shell_clear_input, shell_env, stack_frame_location_at, sub_line_scroll_offset,
+        term_with,
+        terminal_settings::{
+            parse_color, CursorShape, EnterAfterExit, OnExit, Shell, TerminalColors,
+        },
+        theme_named, toggle_copy_mode, truncate_middle, visible_line_attributes, wheel_scroll,
+        word_selection, CopyModeCommand, Event, HoveredWord, IndexedCell, LineAttribute,
+        PromptMark, ScrollHistory, SearchMatch, SelectionPhase, TapState, TappedSequence,
+        TerminalContent, TerminalErrorCause, TerminalSize, WheelScroll, MAX_OSC_TITLE_CHARS,
+        PTY_RESIZE_DEBOUNCE, STACK_FRAME_REGEX, WORD_REGEX,
+    };
+
+    #[test]
+    fn test_rgb_for_index() {
+        // Test every possible value in the color cube.
+        for i in 16..=231 {
+            let (r, g, b) = rgb_for_index(i);
+            assert_eq!(i, 16 + 36 * r + 6 * g + b);
+        }
+    }
+
+    #[test]
+    fn test_parse_git_head() {
+        assert_eq!(
+            parse_git_head("ref: refs/heads/main\n"),
+            Some("main".to_string())
+        );
+        assert_eq!(
+            parse_git_head("ref: refs/heads/feature/terminal-title"),
+            Some("feature/terminal-title".to_string())
+        );
+        assert_eq!(
+            parse_git_head("d06c880f3b1e2a0c9d8e7f6a5b4c3d2e1f0a9b8c\n"),
+            Some("d06c880".to_string())
+        );
+        assert_eq!(parse_git_head(""), None);
+        assert_eq!(parse_git_head("garbage"), None);
+    }
+
+    #[test]
+    fn test_add_dotenv() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join(".env"),
+            "# Local overrides\nAPI_URL=\"http://localhost:3000\"\nLOG_LEVEL=debug\n",
+        )
+        .unwrap();
+
+        let mut env = collections::HashMap::default();
+        env.insert("LOG_LEVEL".to_string(), "info".to_string());
+        add_dotenv(&mut env, dir.path());
+        assert_eq!(env["API_URL"], "http://localhost:3000");
+        assert_eq!(env["LOG_LEVEL"], "info");
+
+        // Without a `.env` file, nothing changes
+        let empty_dir = tempfile::tempdir().unwrap();
+        add_dotenv(&mut env, empty_dir.path());
+        assert_eq!(env.len(), 2);
+    }
+
+    #[test]
+    fn test_dotenv_reaches_the_shell_env() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join(".env"), "GREETING=hello-from-dotenv\n").unwrap();
+        let greeting = |env: HashMap<String, String>| env.get("GREETING").cloned();
+
+        let env = shell_env(HashMap::default(), Some(dir.path()), true, "xterm-256color");
+        assert_eq!(greeting(env).as_deref(), Some("hello-from-dotenv"));
+
+        // The `env` setting wins over the file
+        let settings_env = HashMap::from_iter([("GREETING".to_string(), "hi".to_string())]);
+        let env = shell_env(settings_env, Some(dir.path()), true, "xterm-256color");
+        assert_eq!(greeting(env).as_deref(), Some("hi"));
+
+        // Only when the setting is on, and there's a working directory to find the file in
+        let env = shell_env(
+            HashMap::default(),
+            Some(dir.path()),
+            false,
+            "xterm-256color",
+        );
+        assert_eq!(greeting(env), None);
+        let env = shell_env(HashMap::default(), None, true, "xterm-256color");
+        assert_eq!(greeting(env), None);
+    }
+
+    #[test]
+    fn test_running_process_name() {
+        // Only the shell is running
+        assert_eq!(running_process_name(100, 100, Some("zsh")), None);
+        assert_eq!(running_process_name(100, 100, None), None);
+
+        // A command started from the shell is in the foreground
+        assert_eq!(
+            running_process_name(100, 123, Some("vim")),
+            Some("vim".to_string())
+        );
+        assert_eq!(
+            running_process_name(100, 123, None),
+            Some("process 123".to_string())
+        );
+    }
+
+    #[test]
+    fn test_pty_shell() {
+        let with_arguments = |args: &[&str]| Shell::WithArguments {
+            program: "zsh".to_string(),
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+        };
+        let expected = |args: &[&str]| {
+            Some((
+                "zsh".to_string(),
+                args.iter().map(|arg| arg.to_string()).collect(),
+            ))
+        };
+
+        assert_eq!(pty_shell(Shell::System, false), None);
+        assert_eq!(
+            pty_shell(Shell::Program("zsh".to_string()), false),
+            expected(&[])
+        );
+        assert_eq!(
+            pty_shell(with_arguments(&["-o", "vi"]), false),
+            expected(&["-o", "vi"])
+        );
+        if cfg!(unix) {
+            assert_eq!(
+                pty_shell(Shell::Program("zsh".to_string()), true),
+                expected(&["-l"])
+            );
+            assert_eq!(
+                pty_shell(with_arguments(&["-o", "vi"]), true),
+                expected(&["-l", "-o", "vi"])
+            );
+            assert_eq!(
+                pty_shell(with_arguments(&["--login"]), true),
+                expected(&["--login"])
+            );
+        }
+    }
+
+    #[test]
+    fn test_shell_clear_input() {
+        assert_eq!(shell_clear_input("zsh"), Some("\x0c"));
+        assert_eq!(shell_clear_input("-bash"), Some("\x0c"));
+        assert_eq!(shell_clear_input("pwsh.exe"), Some("\x0c"));
+        assert_eq!(shell_clear_input("cmd.exe"), Some("cls\r"));
+        assert_eq!(shell_clear_input("vim"), None);
+        assert_eq!(shell_clear_input("cargo"), None);
+    }
+
+    #[test]
+    fn test_find_error_locations() {
+        let error_regex = Regex::new(r"(?i)\berror\b").unwrap();
+        let output = "\
+   Compiling zed v0.1.0
+warning: unused variable: `x`
+ --> src/lib.rs:1:5
+error[E0425]: cannot find value `y` in this scope
+ --> src/main.rs:2:13
+  |
+main.c:3:5: error: expected ';' before '}' token
+Error: something went wrong at 12:30:45
+error: could not compile `zed` (bin \"zed\") due to 2 previous errors
+";
+        assert_eq!(
+            find_error_locations(output, &error_regex),
+            vec!["src/main.rs:2:13".to_string(), "main.c:3:5".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_window_size_is_in_device_pixels() {
+        let terminal_size = crate::TerminalSize::new(
+            Pixels::from(17.5),
+            Pixels::from(8.25),
+            size(Pixels::from(825.), Pixels::from(350.)),
+        );
+        let window_size = WindowSize::from(terminal_size);
+        assert_eq!((window_size.num_cols, window_size.num_lines), (100, 20));
+        assert_eq!((window_size.cell_width, window_size.cell_height), (8, 18));
+
+        let window_size = WindowSize::from(terminal_size.with_scale_factor(2.));
+        assert_eq!((window_size.num_cols, window_size.num_lines), (100, 20));
+        assert_eq!((window_size.cell_width, window_size.cell_height), (17, 35));
+    }
+
+    #[test]
+    fn test_osc52_clipboard_write() {
+        struct ClipboardListener(Arc<Mutex<Vec<String>>>);
+
+        impl EventListener for ClipboardListener {
+            fn send_event(&self, event: AlacTermEvent) {
+                if let AlacTermEvent::ClipboardStore(_, text) = event {
+                    self.0.lock().unwrap().push(text);
+                }
+            }
+        }
+
+        let stored = Arc::new(Mutex::new(Vec::new()));
+        let terminal_size = TerminalSize::new(px(10.), px(5.), size(px(100.), px(50.)));
+        let mut term = Term::new(
+            Config::default(),
+            &terminal_size,
+            ClipboardListener(stored.clone()),
+        );
+        let mut processor: Processor = Processor::new();
+        for byte in b"\x1b]52;c;aGVsbG8sIHdvcmxkIQ==\x07" {
+            processor.advance(&mut term, *byte);
+        }
+
+        let stored = stored.lock().unwrap().clone();
+        assert_eq!(stored, ["hello, world!"]);
+        assert_eq!(
+            clipboard_write(&stored[0], true).as_deref(),
+            Some("hello, world!")
+        );
+        assert_eq!(clipboard_write(&stored[0], false), None);
+        assert_eq!(clipboard_write(&"x".repeat(2 * 1024 * 1024), true), None);
+    }
+
+    #[test]
+    fn test_osc_title_changes() {
+        struct TitleListener(Arc<Mutex<Vec<String>>>);
+
+        impl EventListener for TitleListener {
+            fn send_event(&self, event: AlacTermEvent) {
+                if let Some(title) = osc_title(&event) {
+                    self.0.lock().unwrap().push(title);
+                }
+            }
+        }
+
+        let titles = Arc::new(Mutex::new(Vec::new()));
+        let terminal_size = TerminalSize::new(px(10.), px(5.), size(px(100.), px(50.)));
+        let mut term = Term::new(
+            Config::default(),
+            &terminal_size,
+            TitleListener(titles.clone()),
+        );
+        let mut processor: Processor = Processor::new();
+        for byte in b"\x1b]0;hello\x07\x1b]2;vim main.rs\x07" {
+            processor.advance(&mut term, *byte);
+        }
+
+        assert_eq!(*titles.lock().unwrap(), ["hello", "vim main.rs"]);
+        assert_eq!(osc_title(&AlacTermEvent::ResetTitle).as_deref(), Some(""));
+        assert_eq!(osc_title(&AlacTermEvent::Wakeup), None);
+    }
+
+    #[test]
+    fn test_osc_title_sanitized() {
+        let long_title = "x".repeat(100_000);
+        let title = osc_title(&AlacTermEvent::Title(long_title)).unwrap();
+        assert_eq!(title.chars().count(), MAX_OSC_TITLE_CHARS);
+
+        let title = "vim\u{7}\u{1b}[2J\u{9b}main.rs\r\n\tsrc\u{0}";
+        assert_eq!(
+            osc_title(&AlacTermEvent::Title(title.to_string())).as_deref(),
+            Some("vim[2Jmain.rs   src")
+        );
+        assert_eq!(sanitize_title("\n\n"), "");
+        assert_eq!(sanitize_title("héllo wörld"), "héllo wörld");
+    }
+
+    #[test]
+    fn test_last_command_output() {
+        let term = term_with(b"$ ls   \r\na.txt  \r\nb.txt\r\n$ ", 20, 5);
+        let mut marks = vec![
+            (0, PromptMark::PromptStart),
+            (0, PromptMark::CommandStart),
+            (1, PromptMark::OutputStart),
+            (3, PromptMark::CommandEnd { exit_code: Some(0) }),
+            (3, PromptMark::PromptStart),
+            (3, PromptMark::CommandStart),
+        ];
+
+        let rows = last_command_output_rows(&marks).unwrap();
+        assert_eq!(rows, 1..=2);
+        let text = command_output_text(&term, &ScrollHistory::new(100), rows).unwrap();
+        assert_eq!(copied_text(&text), "a.txt\nb.txt");
+
+        // A command that's still running hasn't got an end yet, so the last finished one is used
+        marks.push((3, PromptMark::OutputStart));
+        assert_eq!(last_command_output_rows(&marks), Some(1..=2));
+
+        // Commands without output, and shells without integration
+        let no_output = [
+            (0, PromptMark::OutputStart),
+            (0, PromptMark::CommandEnd { exit_code: None }),
+        ];
+        assert_eq!(last_command_output_rows(&no_output), None);
+        assert_eq!(last_command_output_rows(&[]), None);
+    }
+
+    #[test]
+    fn test_last_command_output_wraps() {
+        let mut tap = TapState::new(100);
+        let mut term = term_with(b"", 10, 5);
+        term.set_options(Config {
+            scrolling_history: tap.scroll_history.limit(),
+            ..Config::default()
+        });
+        // The first file name takes three lines, and the output scrolls the prompt off the screen
+        TapParser::new().advance(
+            &mut term,
+            &mut tap,
+            b"\x1b]133;A\x07$ \x1b]133;B\x07ls\r\n\x1b]133;C\x07a_very_long_file_name.txt\r\n\
+            b.txt\r\n\x1b]133;D;0\x07\x1b]133;A\x07$ ",
+        );
+        let marks = tap
+            .events
+            .iter()
+            .filter_map(|event| match event.sequence {
+                TappedSequence::PromptMark(mark) => Some((event.row, mark)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        let rows = last_command_output_rows(&marks).unwrap();
+        assert_eq!(rows, 1..=4);
+        let text = command_output_text(&term, &tap.scroll_history, rows).unwrap();
+        assert_eq!(copied_text(&text), "a_very_long_file_name.txt\nb.txt");
+    }
+
+    #[test]
+    fn test_line_attributes_in_a_full_scrollback() {
+        let mut tap = TapState::new(3);
+        let mut term = term_with(b"", 20, 5);
+        term.set_options(Config {
+            scrolling_history: tap.scroll_history.limit(),
+            ..Config::default()
+        });
+        // Every third line is double width, and most of them scroll out of the history
+        let output = (0..12)
+            .map(|ix| match ix % 3 {
+                0 => format!("\x1b#6{ix}\r\n"),
+                _ => format!("{ix}\r\n"),
+            })
+            .collect::<String>();
+        TapParser::new().advance(&mut term, &mut tap, output.as_bytes());
+        let mut line_attributes = tap
+            .events
+            .iter()
+            .filter_map(|event| match event.sequence {
+                TappedSequence::LineAttribute(Some(attribute)) => Some((event.row, attribute)),
+                _ => None,
+            })
+            .collect::<HashMap<_, _>>();
+        assert_eq!(line_attributes.len(), 4);
+
+        forget_dropped_rows(&tap.scroll_history, &mut line_attributes, &mut Vec::new());
+        let mut rows = line_attributes.keys().copied().collect::<Vec<_>>();
+        rows.sort();
+        assert_eq!(rows, [6, 9]);
+
+        // Scrolled to the top of the history, the attributes are on the lines they were set on
+        let visible = visible_line_attributes(&line_attributes, &tap.scroll_history, -3..2);
+        assert_eq!(
+            visible,
+            HashMap::from_iter([
+                (-2, LineAttribute::DoubleWidth),
+                (1, LineAttribute::DoubleWidth)
+            ])
+        );
+        let snapshot = grid_snapshot(&term, 3);
+        assert_eq!(snapshot.line_text(1), "6");
+        assert_eq!(snapshot.line_text(4), "9");
+    }
+
+    #[test]
+    fn test_adjacent_prompt_row() {
+        let marks = [
+            (2, PromptMark::PromptStart),
+            (2, PromptMark::CommandStart),
+            (3, PromptMark::OutputStart),
+            (10, PromptMark::CommandEnd { exit_code: Some(0) }),
+            (10, PromptMark::PromptStart),
+            (25, PromptMark::PromptStart),
+        ];
+        let previous = |top_row| adjacent_prompt_row(&marks, top_row, AlacDirection::Left);
+        let next = |top_row| adjacent_prompt_row(&marks, top_row, AlacDirection::Right);
+
+        assert_eq!(previous(30), Some(25));
+        assert_eq!(previous(25), Some(10));
+        assert_eq!(previous(5), Some(2));
+        assert_eq!(previous(2), None);
+        assert_eq!(next(0), Some(2));
+        assert_eq!(next(2), Some(10));
+        assert_eq!(next(25), None);
+        assert_eq!(adjacent_prompt_row(&[], 5, AlacDirection::Left), None);
+    }
+
+    #[test]
+    fn test_search() {
+        let term = term_with(b"Hello world\r\nhello again\r\n(hello)", 20, 5);
+        let point = |line, column| AlacPoint::new(Line(line), Column(column));
+
+        assert_eq!(
+            search_term(&term, "hello", false),
+            vec![
+                SearchMatch {
+                    start: point(0, 0),
+                    end: point(0, 4)
+                },
+                SearchMatch {
+                    start: point(1, 0),
+                    end: point(1, 4)
+                },
+                SearchMatch {
+                    start: point(2, 1),
+                    end: point(2, 5)
+                },
+            ]
+        );
+        assert_eq!(
+            search_term(&term, r"w\w+", true),
+            vec![SearchMatch {
+                start: point(0, 6),
+                end: point(0, 10)
+            }]
+        );
+        assert_eq!(search_term(&term, "(hello)", false).len(), 1);
+        assert!(search_term(&term, "", false).is_empty());
+        assert!(search_term(&term, "(unclosed", true).is_empty());
+    }
+
+    #[cfg(unix)]
+    #[gpui::test]
+    fn test_pty_is_resized_once_resizing_stops(cx: &mut TestAppContext) {
+        let (terminal, mut shell) = fake_terminal(cx);
+        let resize = |columns: usize, cx: &mut TestAppContext| {
+            terminal.update(cx, |terminal, cx| {
+                let width = px(5.) * columns as f32;
+                terminal.set_size(TerminalSize::new(px(10.), px(5.), size(width, px(50.))));
+                terminal.sync(cx);
+            });
+        };
+
+        // The first size is sent right away, and the rest once a window drag stops
+        resize(20, cx);
+        for columns in [30, 40, 50] {
+            resize(columns, cx);
+            cx.executor().advance_clock(PTY_RESIZE_DEBOUNCE / 2);
+            cx.executor().run_until_parked();
+        }
+        cx.executor().advance_clock(PTY_RESIZE_DEBOUNCE);
+        cx.executor().run_until_parked();
+
+        // Alacritty resizes the PTY before writing the input sent after the resizes
+        terminal.update(cx, |terminal, _| terminal.input("done".to_string()));
+        shell.expect_input("done");
+        let columns = shell
+            .resizes
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|size| size.num_cols)
+            .collect::<Vec<_>>();
+        assert_eq!(columns, [20, 50]);
+    }
+
+    #[test]
+    fn test_wheel_scroll_on_alt_screen() {
+        let mut term = term_with(b"", 10, 5);
+        let mut processor: Processor = Processor::new();
+        let mut output = |term: &mut Term<VoidListener>, bytes: &[u8]| {
+            for byte in bytes {
+                processor.advance(term, *byte);
+            }
+        };
+
+        assert_eq!(
+            wheel_scroll(*term.mode(), false, false),
+            WheelScroll::Scrollback
+        );
+
+        output(&mut term, b"\x1b[?1049h");
+        assert_eq!(
+            wheel_scroll(*term.mode(), false, false),
+            WheelScroll::ArrowKeys
+        );
+        // Shift asks for scrolling, but there's no scrollback on the alternate screen
+        assert_eq!(wheel_scroll(*term.mode(), false, true), WheelScroll::Ignore);
+        assert_eq!(wheel_scroll(*term.mode(), true, false), WheelScroll::Report);
+
+        output(&mut term, b"\x1b[?1007l");
+        assert_eq!(
+            wheel_scroll(*term.mode(), false, false),
+            WheelScroll::Ignore
+        );
+
+        output(&mut term, b"\x1b[?1049l");
+        assert_eq!(
+            wheel_scroll(*term.mode(), false, false),
+            WheelScroll::Scrollback
+        );
+    }
+
+    #[test]
+    fn test_reflow_on_resize() {
+        let line = "abcdefghijklmnopqrstuvw";
+        let mut term = term_with(line.as_bytes(), 10, 8);
+        let point = |line, column| AlacPoint::new(Line(line), Column(column));
+        let text = |term: &Term<VoidListener>| {
+            let end = point(term.screen_lines() as i32 - 1, term.columns() - 1);
+            term.bounds_to_string(point(0, 0), end)
+                .trim_end()
+                .to_string()
+        };
+        assert_eq!(text(&term), line);
+        assert_eq!(term.grid().cursor.point, point(2, 3));
+
+        let new_size = resize_term(
+            &mut term,
+            TerminalSize::new(px(10.), px(5.), size(px(20.), px(80.))),
+        );
+        assert_eq!(new_size.num_columns(), 4);
+        assert_eq!(text(&term), line);
+        assert_eq!(term.grid().cursor.point, point(5, 3));
+
+        resize_term(
+            &mut term,
+            TerminalSize::new(px(10.), px(5.), size(px(50.), px(80.))),
+        );
+        assert_eq!(text(&term), line);
+        assert_eq!(term.grid().cursor.point, point(2, 3));
+
+        // Sizes too small for a single cell are clamped
+        let new_size = resize_term(
+            &mut term,
+            TerminalSize::new(px(10.), px(5.), size(px(0.), px(0.))),
+        );
+        assert_eq!((new_size.num_columns(), new_size.num_lines()), (1, 1));
+    }
+
+    #[test]
+    fn test_osc_8_hyperlinks() {
+        let output = "see \x1b]8;;https://zed.dev\x1b\\the zed site\x1b]8;;\x1b\\ and \
+            \x1b]8;id=1;file:///tmp\x07tmp\x1b]8;;\x07";
+        let term = term_with(output.as_bytes(), 10, 5);
+        let point = |line, column| AlacPoint::new(Line(line), Column(column));
+
+        // The link wraps onto the second line
+        let expected = Some(("https://zed.dev".to_string(), point(0, 4)..=point(1, 5)));
+        assert_eq!(hyperlink_at(&term, point(0, 4)), expected);
+        assert_eq!(hyperlink_at(&term, point(1, 2)), expected);
+        assert_eq!(hyperlink_at(&term, point(0, 3)), None);
+        assert_eq!(hyperlink_at(&term, point(1, 6)), None);
+        assert_eq!(
+            hyperlink_at(&term, point(2, 2)),
+            Some(("file:///tmp".to_string(), point(2, 1)..=point(2, 3)))
+        );
+    }
+
+    #[test]
+    fn test_path_like_words() {
+        let output = "Cargo.toml src/main.rs:42 src/main.rs:42:10 C:\\src\\lib.rs:3:1 (~/a.rs)";
+        let term = term_with(output.as_bytes(), 80, 5);
+        let mut word_regex = RegexSearch::new(WORD_REGEX).unwrap();
+        let mut word_at = |column| {
+            let word_match = regex_match_at(
+                &term,
+                AlacPoint::new(Line(0), Column(column)),
+                &mut word_regex,
+            )?;
+            Some(term.bounds_to_string(*word_match.start(), *word_match.end()))
+        };
+
+        assert_eq!(word_at(3).as_deref(), Some("Cargo.toml"));
+        assert_eq!(word_at(12).as_deref(), Some("src/main.rs:42"));
+        assert_eq!(word_at(26).as_deref(), Some("src/main.rs:42:10"));
+        assert_eq!(word_at(45).as_deref(), Some(r"C:\src\lib.rs:3:1"));
+        assert_eq!(word_at(63).as_deref(), Some("~/a.rs"));
+        assert_eq!(word_at(10), None);
+    }
+
+    #[test]
+    fn test_stack_frame_locations() {
+        let output = "    at main (/src/my app.js:10:5) src/main.rs:10:5-10:20";
+        let term = term_with(output.as_bytes(), 80, 5);
+        let mut stack_frame_regex = RegexSearch::new(STACK_FRAME_REGEX).unwrap();
+        let mut location_at = |column| {
+            stack_frame_location_at(
+                &term,
+                AlacPoint::new(Line(0), Column(column)),
+                &mut stack_frame_regex,
+            )
+            .map(|(location, _)| location)
+        };
+        assert_eq!(location_at(14).as_deref(), Some("/src/my app.js:10:5"));
+        assert_eq!(location_at(22).as_deref(), Some("/src/my app.js:10:5"));
+        // The function's name and the parentheses aren't part of the location
+        assert_eq!(location_at(8), None);
+        assert_eq!(location_at(12), None);
+        assert_eq!(location_at(32), None);
+
+        // Ranges are found whole, to be split when they're opened
+        let mut word_regex = RegexSearch::new(WORD_REGEX).unwrap();
+        let word_match =
+            regex_match_at(&term, AlacPoint::new(Line(0), Column(40)), &mut word_regex).unwrap();
+        assert_eq!(
+            term.bounds_to_string(*word_match.start(), *word_match.end()),
+            "src/main.rs:10:5-10:20"
+        );
+    }
+
+    #[test]
+    fn test_word_selection() {
+        let mut term = term_with(b"foo:bar   baz 0123456789", 20, 5);
+        term.set_options(Config {
+            semantic_escape_chars: ": ".to_string(),
+            ..Config::default()
+        });
+        let point = |line, column| AlacPoint::new(Line(line), Column(column));
+        let mut word_at = |point| {
+            term.selection = Some(word_selection(&term, point));
+            term.selection_to_string()
+        };
+
+        assert_eq!(word_at(point(0, 1)).as_deref(), Some("foo"));
+        assert_eq!(word_at(point(0, 5)).as_deref(), Some("bar"));
+        assert_eq!(word_at(point(0, 8)).as_deref(), Some("   "));
+        assert_eq!(word_at(point(0, 13)).as_deref(), Some(" "));
+        // Words and lines continue onto the rows they wrap onto
+        assert_eq!(word_at(point(1, 2)).as_deref(), Some("0123456789"));
+
+        term.selection = Some(Selection::new(
+            SelectionType::Lines,
+            point(1, 2),
+            AlacDirection::Left,
+        ));
+        assert_eq!(
+            term.selection_to_string().as_deref(),
+            Some("foo:bar   baz 0123456789")
+        );
+    }
+
+    #[test]
+    fn test_select_all() {
+        let output = (0..8)
+            .map(|line| format!("line {line} \x1b[41m \x1b[0m\r\n"))
+            .collect::<String>();
+        let mut term = term_with(output.as_bytes(), 20, 5);
+        term.scroll_display(AlacScroll::Delta(2));
+
+        term.selection = Some(all_selection(&term));
+        let text = term.selection_to_string().unwrap();
+        assert_eq!(
+            copied_text(&text),
+            (0..8)
+                .map(|line| format!("line {line}"))
+                .collect::<Vec<_>>()
+                .join("\n")
+        );
+        assert_eq!(copied_text("a  \r\n\tb\t\n"), "a\n\tb");
+    }
+
+    #[test]
+    fn test_buffer_text() {
+        let output = (0..8)
+            .map(|line| format!("\x1b[1mline\x1b[0m {line}   \r\n"))
+            .collect::<String>();
+        let mut term = term_with(output.as_bytes(), 20, 5);
+
+        // The scrollback is included, without colors or the whitespace at the ends of lines
+        let expected = (0..8)
+            .map(|line| format!("line {line}"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert_eq!(buffer_text(&term), expected);
+        term.scroll_display(AlacScroll::Delta(2));
+        assert_eq!(buffer_text(&term), expected);
+    }
+
+    #[test]
+    fn test_cursor_visibility() {
+        let mut term = term_with(b"", 20, 5);
+        let mut processor: Processor = Processor::new();
+        assert!(cursor_visible(term.mode()));
+
+        for byte in b"\x1b[?25l" {
+            processor.advance(&mut term, *byte);
+        }
+        assert!(!cursor_visible(term.mode()));
+        assert_eq!(
+            term.renderable_content().cursor.shape,
+            AlacCursorShape::Hidden
+        );
+
+        // Copy mode shows its own cursor
+        toggle_copy_mode(&mut term);
+        assert!(cursor_visible(term.mode()));
+        toggle_copy_mode(&mut term);
+
+        for byte in b"\x1b[?25h" {
+            processor.advance(&mut term, *byte);
+        }
+        assert!(cursor_visible(term.mode()));
+        assert_ne!(
+            term.renderable_content().cursor.shape,
+            AlacCursorShape::Hidden
+        );
+    }
+
+    #[test]
+    fn test_content_is_newer() {
+        assert!(content_is_newer(1, 0));
+        assert!(content_is_newer(5, 2));
+        assert!(!content_is_newer(2, 2));
+        assert!(!content_is_newer(2, 5));
+        // Output after the counter wraps is still newer
+        assert!(content_is_newer(0, usize::MAX));
+        assert!(content_is_newer(1, usize::MAX - 1));
+    }
+
+    #[test]
+    fn test_smooth_scroll() {
+        let (line_height, viewport_height) = (px(10.), px(100.));
+        let mut scroll_px = px(0.);
+        let mut display_offset = 0;
+        for _ in 0..3 {
+            let (accumulated, lines) =
+                accumulate_scroll(scroll_px, px(3.), line_height, viewport_height);
+            scroll_px = accumulated;
+            display_offset += lines;
+        }
+        // Nine pixels is still short of a line, and is drawn as an offset instead
+        assert_eq!(display_offset, 0);
+        assert_eq!(
+            sub_line_scroll_offset(scroll_px, line_height, 0, 50),
+            px(9.)
+        );
+
+        let (scroll_px, lines) = accumulate_scroll(scroll_px, px(3.), line_height, viewport_height);
+        assert_eq!(lines, 1);
+        assert_eq!(
+            sub_line_scroll_offset(scroll_px, line_height, 1, 50),
+            px(2.)
+        );
+
+        // There's nothing to scroll to past the top of the scrollback or below the screen
+        assert_eq!(sub_line_scroll_offset(px(2.), line_height, 50, 50), px(0.));
+        assert_eq!(sub_line_scroll_offset(px(-2.), line_height, 0, 50), px(0.));
+        assert_eq!(sub_line_scroll_offset(px(-2.), line_height, 1, 50), px(-2.));
+    }
+
+    #[cfg(unix)]
+    #[gpui::test]
+    fn test_switched_screens(cx: &mut TestAppContext) {
+        let (terminal, _shell) = fake_terminal(cx);
+        let output = |bytes: &[u8], cx: &mut TestAppContext| {
+            terminal.update(cx, |terminal, cx| {
+                let mut processor: Processor = Processor::new();
+                let mut term = terminal.term.lock();
+                for byte in bytes {
+                    processor.advance(&mut *term, *byte);
+                }
+                drop(term);
+                terminal.sync(cx);
+            });
+        };
+        let start = AlacPoint::new(Line(0), Column(0));
+        let select_and_hover = |cx: &mut TestAppContext| {
+            terminal.update(cx, |terminal, _| {
+                terminal.term.lock().selection = Some(Selection::new(
+                    SelectionType::Simple,
+                    start,
+                    AlacDirection::Left,
+                ));
+                terminal.selection_head = Some(start);
+                terminal.selection_phase = SelectionPhase::Selecting;
+                terminal.last_content.last_hovered_word = Some(HoveredWord {
+                    word: "main.rs".to_string(),
+                    word_match: start..=AlacPoint::new(Line(0), Column(6)),
+                    id: 1,
+                });
+                terminal.hovered_word = true;
+            });
+        };
+        let selected_and_hovered = |cx: &mut TestAppContext| {
+            terminal.read_with(cx, |terminal, _| {
+                [
+                    terminal.term.lock().selection.is_some(),
+                    terminal.selection_head.is_some(),
+                    terminal.selection_phase == SelectionPhase::Selecting,
+                    terminal.last_content.last_hovered_word.is_some(),
+                    terminal.hovered_word,
+                ]
+            })
+        };
+
+        // Saving the cursor alone stays on the same screen
+        select_and_hover(cx);
+        output(b"\x1b[?1048h", cx);
+        assert_eq!(selected_and_hovered(cx), [true; 5]);
+
+        // A selection being dragged out, and the word under the pointer, end with either screen
+        for sequence in [
+            b"\x1b[?1049h",
+            b"\x1b[?1049l",
+            b"\x1b[?1047h",
+            b"\x1b[?1047l",
+        ] {
+            select_and_hover(cx);
+            output(sequence, cx);
+            assert_eq!(selected_and_hovered(cx), [false; 5]);
+        }
+    }
+
+    #[test]
+    fn test_grid_snapshot() {
+        let output = (1..=12)
+            .map(|line| format!("line {line}"))
+            .collect::<Vec<_>>()
+            .join("\r\n");
+        let mut term = term_with(format!("{output}\r\n$ ls").as_bytes(), 20, 10);
+
+        let screen = grid_snapshot(&term, 0);
+        assert_eq!(screen.lines.len(), 10);
+        assert_eq!(screen.line_text(0), "line 4");
+        assert_eq!(screen.line_text(9), "$ ls");
+        assert_eq!((screen.cursor_line, screen.cursor_column), (9, 4));
+
+        // Only the scrollback there is can be included
+        let all = grid_snapshot(&term, 100);
+        assert_eq!(all.lines.len(), 13);
+        assert!(all.text().starts_with("line 1\nline 2\n"));
+        assert_eq!((all.cursor_line, all.cursor_column), (12, 4));
+
+        // The snapshot doesn't change with the grid
+        let mut processor: Processor = Processor::new();
+        for byte in b"\x1b[2J" {
+            processor.advance(&mut term, *byte);
+        }
+        assert_eq!(screen.line_text(9), "$ ls");
+    }
+
+    #[test]
+    fn test_scrollbar_shown() {
+        let mut term = term_with(b"", 20, 5);
+        let mut processor: Processor = Processor::new();
+        let mut advance = |term: &mut Term<VoidListener>, bytes: &[u8]| {
+            for byte in bytes {
+                processor.advance(term, *byte);
+            }
+        };
+        let shown = |term: &Term<VoidListener>, was_shown| {
+            scrollbar_shown(term.grid().history_size(), *term.mode(), was_shown)
+        };
+        assert!(!shown(&term, false));
+
+        advance(&mut term, "line\r\n".repeat(15).as_bytes());
+        assert!(shown(&term, false));
+
+        // The alternate screen has no scrollback, but keeps the scrollbar
+        advance(&mut term, b"\x1b[?1049h");
+        assert_eq!(term.grid().history_size(), 0);
+        assert!(shown(&term, true));
+        assert!(!shown(&term, false));
+
+        advance(&mut term, b"\x1b[?1049l\x1b[3J");
+        assert!(!shown(&term, true));
+    }
+
+    #[test]
+    fn test_keys_follow_application_modes() {
+        let up = Keystroke::parse("up").unwrap();
+        let keypad_one = Keystroke::parse("kp_1").unwrap();
+
+        // DECCKM and DECKPAM on
+        let mut term = term_with(b"\x1b[?1h\x1b=", 20, 5);
+        assert_eq!(
+            to_esc_str(&up, term.mode(), false).as_deref(),
+            Some("\x1bOA")
+        );
+        assert_eq!(
+            to_esc_str(&keypad_one, term.mode(), false).as_deref(),
+            Some("\x1bOq")
+        );
+
+        // And off again
+        let mut processor: Processor = Processor::new();
+        for byte in b"\x1b[?1l\x1b>" {
+            processor.advance(&mut term, *byte);
+        }
+        assert_eq!(
+            to_esc_str(&up, term.mode(), false).as_deref(),
+            Some("\x1b[A")
+        );
+        assert_eq!(
+            to_esc_str(&keypad_one, term.mode(), false).as_deref(),
+            Some("1")
+        );
+    }
+
+    #[test]
+    fn test_copy_mode() {
+        let mut term = term_with(b"hello\r\nworld", 20, 5);
+
+        // Commands do nothing outside of copy mode
+        let up = CopyModeCommand::Move(ViMotion::Up);
+        assert_eq!(run_copy_mode_command(&mut term, up), None);
+        assert_eq!(
+            term.vi_mode_cursor.point,
+            AlacPoint::new(Line(0), Column(0))
+        );
+
+        toggle_copy_mode(&mut term);
+        assert!(term.mode().contains(TermMode::VI));
+        assert_eq!(
+            term.vi_mode_cursor.point,
+            AlacPoint::new(Line(1), Column(5))
+        );
+
+        run_copy_mode_command(&mut term, up);
+        run_copy_mode_command(&mut term, CopyModeCommand::Move(ViMotion::Left));
+        assert_eq!(
+            term.vi_mode_cursor.point,
+            AlacPoint::new(Line(0), Column(4))
+        );
+
+        run_copy_mode_command(&mut term, CopyModeCommand::ToggleSelection);
+        assert_eq!(term.selection_to_string().as_deref(), Some("o"));
+        for _ in 0..3 {
+            run_copy_mode_command(&mut term, CopyModeCommand::Move(ViMotion::Left));
+        }
+        assert_eq!(term.selection_to_string().as_deref(), Some("ello"));
+
+        // Toggling the selection again drops it
+        run_copy_mode_command(&mut term, CopyModeCommand::ToggleSelection);
+        assert!(term.selection.is_none());
+        run_copy_mode_command(&mut term, CopyModeCommand::ToggleSelection);
+        run_copy_mode_command(&mut term, CopyModeCommand::Move(ViMotion::Last));
+        assert_eq!(
+            run_copy_mode_command(&mut term, CopyModeCommand::Yank).as_deref(),
+            Some("ello")
+        );
+        assert!(!term.mode().contains(TermMode::VI));
+        assert!(term.selection.is_none());
+
+        toggle_copy_mode(&mut term);
+        run_copy_mode_command(&mut term, CopyModeCommand::ToggleSelection);
+        run_copy_mode_command(&mut term, CopyModeCommand::Exit);
+        assert!(!term.mode().contains(TermMode::VI));
+        assert!(term.selection.is_none());
+    }
+
+    #[test]
+    fn test_copy_mode_keys() {
+        let command = |keystroke: &str| copy_mode_command(&Keystroke::parse(keystroke).unwrap());
+        assert_eq!(command("k"), Some(CopyModeCommand::Move(ViMotion::Up)));
+        assert_eq!(command("left"), Some(CopyModeCommand::Move(ViMotion::Left)));
+        assert_eq!(command("v"), Some(CopyModeCommand::ToggleSelection));
+        assert_eq!(command("y"), Some(CopyModeCommand::Yank));
+        assert_eq!(command("escape"), Some(CopyModeCommand::Exit));
+        assert_eq!(command("ctrl-c"), None);
+        assert_eq!(command("x"), None);
+    }
+
+    #[test]
+    fn test_clear_scrollback() {
+        let output = (0..12)
+            .map(|line| format!("line {line}"))
+            .collect::<Vec<_>>()
+            .join("\r\n");
+        let mut term = term_with(output.as_bytes(), 20, 5);
+        term.scroll_display(AlacScroll::Delta(3));
+        assert_eq!(term.grid().history_size(), 7);
+        assert_eq!(term.grid().display_offset(), 3);
+
+        clear_scrollback(&mut term);
+        assert_eq!(term.grid().history_size(), 0);
+        assert_eq!(term.grid().display_offset(), 0);
+        let screen = (7..12)
+            .map(|line| format!("line {line}"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert_eq!(buffer_text(&term), screen);
+    }
+
+    #[test]
+    fn test_scrolling() {
+        let output = (0..20)
+            .map(|line| format!("line {line}\r\n"))
+            .collect::<String>();
+        let mut term = term_with(output.as_bytes(), 20, 5);
+        let first_visible_line = |term: &Term<VoidListener>| {
+            let line = Line(-(term.grid().display_offset() as i32));
+            term.bounds_to_string(
+                AlacPoint::new(line, Column(0)),
+                AlacPoint::new(line, term.last_column()),
+            )
+        };
+        assert_eq!(first_visible_line(&term).trim_end(), "line 16");
+
+        term.scroll_display(AlacScroll::Delta(3));
+        assert_eq!(term.grid().display_offset(), 3);
+        assert_eq!(first_visible_line(&term).trim_end(), "line 13");
+
+        // A page is as many lines as fit on the screen
+        term.scroll_display(AlacScroll::Delta(term.screen_lines() as i32));
+        assert_eq!(first_visible_line(&term).trim_end(), "line 8");
+
+        term.scroll_display(AlacScroll::Bottom);
+        assert_eq!(first_visible_line(&term).trim_end(), "line 16");
+
+        // Output doesn't move the viewport while scrolled up
+        term.scroll_display(AlacScroll::Delta(3));
+        let mut processor: Processor = Processor::new();
+        for line in 20..25 {
+            for byte in format!("line {line}\r\n").as_bytes() {
+                processor.advance(&mut term, *byte);
+            }
+        }
+        assert_eq!(term.grid().display_offset(), 8);
+        assert_eq!(first_visible_line(&term).trim_end(), "line 13");
+    }
+
+    #[test]
+    fn test_resolve_shell_program() {
+        fn exists(shells: &'static [&'static str]) -> impl Fn(&str) -> bool {
+            move |shell| shells.contains(&shell)
+        }
+        assert_eq!(
+            resolve_shell_program("fish", Some("/usr/bin/zsh".into()), exists(&["fish"])),
+            Ok("fish".to_string())
+        );
+        assert_eq!(
+            resolve_shell_program(
+                "/missing/fish",
+                Some("/usr/bin/zsh".into()),
+                exists(&["/usr/bin/zsh", "/bin/sh"])
+            ),
+            Ok("/usr/bin/zsh".to_string())
+        );
+        assert_eq!(
+            resolve_shell_program("/missing/fish", None, exists(&["/bin/bash", "/bin/sh"])),
+            Ok("/bin/bash".to_string())
+        );
+        assert_eq!(
+            resolve_shell_program("/missing/fish", Some("/missing/zsh".into()), exists(&[])),
+            Err(
+                "none of the shells /missing/fish, /missing/zsh, /bin/zsh, /bin/bash, /bin/sh exist"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_missing_directory_falls_back_to_home() {
+        let existing = std::env::temp_dir();
+        assert_eq!(
+            existing_directory_or_home(Some(existing.clone())),
+            Some(existing.clone())
+        );
+        let missing = existing.join("zed-terminal-test-directory-that-was-deleted");
+        assert!(!missing.exists());
+        assert_eq!(existing_directory_or_home(Some(missing)), dirs::home_dir());
+        assert_eq!(existing_directory_or_home(None), dirs::home_dir());
+
+        use std::io::ErrorKind;
+        assert_eq!(
+            TerminalErrorCause::new(true, ErrorKind::NotFound),
+            TerminalErrorCause::DirectoryMissing
+        );
+        assert_eq!(
+            TerminalErrorCause::new(false, ErrorKind::NotFound),
+            TerminalErrorCause::ShellNotFound
+        );
+        assert_eq!(
+            TerminalErrorCause::new(false, ErrorKind::PermissionDenied),
+            TerminalErrorCause::PermissionDenied
+        );
+        assert_eq!(
+            TerminalErrorCause::new(false, ErrorKind::Other),
+            TerminalErrorCause::Other
+        );
+    }
+
+    #[test]
+    fn test_term_env() {
+        let env = shell_env(HashMap::default(), None, false, "xterm-direct");
+        assert_eq!(env.get("TERM").map(String::as_str), Some("xterm-direct"));
+        assert_eq!(env.get("COLORTERM").map(String::as_str), Some("truecolor"));
+
+        // Without 256 colors, 24-bit color isn't advertised either
+        let mut env = collections::HashMap::default();
+        add_term_env(&mut env, "vt100");
+        assert_eq!(env.get("TERM").map(String::as_str), Some("vt100"));
+        assert_eq!(env.get("COLORTERM"), None);
+
+        // Setting TERM in the env setting wins
+        let mut env =
+            collections::HashMap::from_iter([("TERM".to_string(), "screen-256color".to_string())]);
+        add_term_env(&mut env, "xterm-256color");
+        assert_eq!(env.get("TERM").map(String::as_str), Some("screen-256color"));
+        assert_eq!(env.get("COLORTERM").map(String::as_str), Some("truecolor"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_signal_process_group() {
+        use crate::signal_process_group;
+        use std::os::unix::process::{CommandExt, ExitStatusExt};
+
+        let mut child = std::process::Command::new("sleep")
+            .arg("30")
+            .process_group(0)
+            .spawn()
+            .unwrap();
+        assert!(signal_process_group(child.id(), libc::SIGTERM));
+        assert_eq!(child.wait().unwrap().signal(), Some(libc::SIGTERM));
+        // The process group is gone now
+        assert!(!signal_process_group(child.id(), libc::SIGTERM));
+    }
+
+    #[test]
+    fn test_paste_text() {
+        assert_eq!(
+            paste_text("ls\nrm -rf \x1btarget\n", true, false),
+            "\x1b[200~ls\nrm -rf target\n\x1b[201~"
+        );
+        assert_eq!(paste_text("ls\r\npwd\n", false, false), "ls\rpwd\r");
+        assert_eq!(paste_text("ls\rpwd\r\n\r\n", false, false), "ls\rpwd\r\r");
+
+        // Only a single trailing line ending is stripped
+        assert_eq!(paste_text("ls\r\npwd\r\n", false, true), "ls\rpwd");
+        assert_eq!(paste_text("ls\n\n", false, true), "ls\r");
+        assert_eq!(paste_text("ls\r", false, true), "ls");
+        assert_eq!(paste_text("ls", false, true), "ls");
+        assert_eq!(
+            paste_text("ls\r\npwd\r\n", true, true),
+            "\x1b[200~ls\r\npwd\x1b[201~"
+        );
+    }
+
+    #[test]
+    fn test_is_multiline() {
+        assert!(!is_multiline("cargo test"));
+        assert!(!is_multiline("cargo test\r\n"));
+        assert!(is_multiline("cd crates\ncargo test"));
+        assert!(is_multiline("cd crates\r\ncargo test\n"));
+    }
+
+    #[test]
+    fn test_injected_text() {
+        assert_eq!(injected_text("echo hi\n", false), "echo hi\n");
+        assert_eq!(
+            injected_text("echo hi\n", true),
+            "\x1b[200~echo hi\x1b[201~\r"
+        );
+        assert_eq!(
+            injected_text("a\nb\x1b[A", true),
+            "\x1b[200~a\nb[A\x1b[201~"
+        );
+    }
+
+    /// A PTY made of a socket, with a [`FakeShell`] at its other end.
+    #[cfg(unix)]
+    struct SocketPty {
+        socket: UnixStream,
+        resizes: Arc<Mutex<Vec<WindowSize>>>,
+    }
+
+    #[cfg(unix)]
+    impl EventedReadWrite for SocketPty {
+        type Reader = UnixStream;
+        type Writer = UnixStream;
+
+        unsafe fn register(
+            &mut self,
+            poll: &Arc<Poller>,
+            interest: PollingEvent,
+            mode: PollMode,
+        ) -> io::Result<()> {
+            poll.add_with_mode(&self.socket, interest, mode)
+        }
+
+        fn reregister(
+            &mut self,
+            poll: &Arc<Poller>,
+            interest: PollingEvent,
+            mode: PollMode,
+        ) -> io::Result<()> {
+            poll.modify_with_mode(&self.socket, interest, mode)
+        }
+
+        fn deregister(&mut self, poll: &Arc<Poller>) -> io::Result<()> {
+            poll.delete(&self.socket)
+        }
+
+        fn reader(&mut self) -> &mut Self::Reader {
+            &mut self.socket
+        }
+
+        fn writer(&mut self) -> &mut Self::Writer {
+            &mut self.socket
+        }
+    }
+
+    #[cfg(unix)]
+    impl EventedPty for SocketPty {
+        fn next_child_event(&mut self) -> Option<ChildEvent> {
+            None
+        }
+    }
+
+    #[cfg(unix)]
+    impl OnResize for SocketPty {
+        fn on_resize(&mut self, window_size: WindowSize) {
+            self.resizes.lock().unwrap().push(window_size);
+        }
+    }
+
+    /// The program on the other end of a [`SocketPty`].
+    #[cfg(unix)]
+    struct FakeShell {
+        socket: UnixStream,
+        resizes: Arc<Mutex<Vec<WindowSize>>>,
+    }
+
+    #[cfg(unix)]
+    impl FakeShell {
+        /// Reads what the terminal wrote to the PTY, which alacritty does on its own thread.
+        fn expect_input(&mut self, input: &str) {
+            let mut read = vec![0; input.len()];
+            self.socket.read_exact(&mut read).unwrap();
+            assert_eq!(String::from_utf8_lossy(&read), input);
+        }
+    }
+
+    /// A terminal whose shell is played by the test.
+    #[cfg(unix)]
+    fn fake_terminal(cx: &mut TestAppContext) -> (gpui::Model<crate::Terminal>, FakeShell) {
+        use crate::{
+            pty_info::PtyProcessInfo, terminal_settings::AlternateScroll, TerminalBuilder,
+        };
+        use settings::SettingsStore;
+
+        cx.update(|cx| {
+            let store = SettingsStore::test(cx);
+            cx.set_global(store);
+            crate::init(cx);
+        });
+        let (socket, shell_socket) = UnixStream::pair().unwrap();
+        socket.set_nonblocking(true).unwrap();
+        shell_socket
+            .set_read_timeout(Some(Duration::from_secs(10)))
+            .unwrap();
+        let resizes = Arc::new(Mutex::new(Vec::new()));
+        let pty = SocketPty {
+            socket,
+            resizes: resizes.clone(),
+        };
+        let (completion_tx, _) = smol::channel::unbounded();
+        let builder = TerminalBuilder::with_pty(
+            pty,
+            PtyProcessInfo::for_process(std::process::id()),
+            false,
+            None,
+            None,
+            None,
+            None,
+            AlternateScroll::Off,
+            None,
+            usize::MAX,
+            String::new(),
+            CursorShape::default(),
+            completion_tx,
+        )
+        .unwrap();
+        let shell = FakeShell {
+            socket: shell_socket,
+            resizes,
+        };
+        (cx.new_model(|cx| builder.subscribe(cx)), shell)
+    }
+
+    #[cfg(unix)]
+    #[gpui::test]
+    fn test_send_text(cx: &mut TestAppContext) {
+        let (terminal, mut shell) = fake_terminal(cx);
+        terminal.update(cx, |terminal, _| terminal.send_text("echo hi\n"));
+        shell.expect_input("echo hi\n");
+
+        // Shells that asked for bracketed paste get the text as a paste, run by the line ending
+        terminal.update(cx, |terminal, _| {
+            terminal.last_content.mode.insert(TermMode::BRACKETED_PASTE);
+            terminal.send_text("echo hi\n");
+        });
+        shell.expect_input("\x1b[200~echo hi\x1b[201~\r");
+    }
+
+    #[test]
+    fn test_parse_color() {
+        let red = rgba_color(0xff, 0, 0);
+        assert_eq!(parse_color("black", "#ff0000").unwrap(), (0, red));
+        assert_eq!(parse_color("bright_white", "#ff0000").unwrap(), (15, red));
+        assert_eq!(parse_color("208", "#f00").unwrap(), (208, red));
+        assert_eq!(
+            parse_color("foreground", "#ff0000ff").unwrap(),
+            (TerminalColors::FOREGROUND, red)
+        );
+        assert!(parse_color("bright_foreground", "#ff0000").is_err());
+        assert!(parse_color("256", "#ff0000").is_err());
+        assert!(parse_color("red", "red").is_err());
+    }
+
+    #[test]
+    fn test_terminal_colors_override_theme() {
+        let theme = Theme::default();
+        let colors = TerminalColors::parse(
+            &[
+                ("red", "#ff0000"),
+                ("16", "#00ff00"),
+                ("background", "#0000ff"),
+                ("bright_red", "not a color"),
+            ]
+            .into_iter()
+            .map(|(slot, color)| (slot.to_string(), color.to_string()))
+            .collect(),
+        );
+
+        assert_eq!(colors.color_at_index(1, &theme), rgba_color(0xff, 0, 0));
+        assert_eq!(colors.color_at_index(16, &theme), rgba_color(0, 0xff, 0));
+        assert_eq!(colors.background(&theme), rgba_color(0, 0, 0xff));
+        // Invalid colors and colors that aren't set fall back to the theme's
+        assert_eq!(colors.get(9), None);
+        assert_eq!(
+            colors.color_at_index(9, &theme),
+            get_color_at_index(9, &theme)
+        );
+        assert_eq!(
+            TerminalColors::default().background(&theme),
+            theme.colors().terminal_background
+        );
+    }
+
+    #[test]
+    fn test_terminal_theme() {
+        let registry = ThemeRegistry::default();
+        let mut editor_theme = Theme::default();
+        editor_theme.name = "Editor".into();
+        editor_theme.styles.colors.terminal_ansi_red = rgba_color(0xff, 0, 0);
+        let editor_theme = Arc::new(editor_theme);
+        let terminal_theme = registry.get("One Dark").unwrap();
+        let colors = TerminalColors::default();
+
+        let theme = theme_named(Some("One Dark"), &registry, &editor_theme);
+        assert_eq!(
+            colors.color_at_index(1, &theme),
+            terminal_theme.colors().terminal_ansi_red
+        );
+        assert_ne!(
+            colors.color_at_index(1, &theme),
+            editor_theme.colors().terminal_ansi_red
+        );
+
+        // The editor's theme is used when there's no such theme, or none is set
+        for name in [Some("Nonexistent"), None] {
+            let theme = theme_named(name, &registry, &editor_theme);
+            assert_eq!(colors.color_at_index(1, &theme), rgba_color(0xff, 0, 0));
+        }
+    }
+
+    #[test]
+    fn test_missing_theme_warning() {
+        let registry = ThemeRegistry::default();
+        let mut warned_name = None;
+        let mut warns = |name| missing_theme_warning(name, &registry, &mut warned_name).is_some();
+
+        // Each missing theme is warned about once in a row, as settings change
+        assert!(warns(Some("Nonexistent")));
+        assert!(!warns(Some("Nonexistent")));
+        assert!(warns(Some("Also Nonexistent")));
+        assert!(warns(Some("Nonexistent")));
+
+        // Until the setting names a theme that's there, or none
+        assert!(!warns(Some("One Dark")));
+        assert!(warns(Some("Nonexistent")));
+        assert!(!warns(None));
+        assert!(warns(Some("Nonexistent")));
+    }
+
+    #[test]
+    fn test_cursor_shape() {
+        let mut term = term_with(b"", 20, 5);
+        term.set_options(Config {
+            default_cursor_style: CursorStyle {
+                shape: CursorShape::Bar.into(),
+                ..CursorStyle::default()
+            },
+            ..Config::default()
+        });
+        let mut processor: Processor = Processor::new();
+        let mut advance = |term: &mut Term<VoidListener>, bytes: &[u8]| {
+            for byte in bytes {
+                processor.advance(term, *byte);
+            }
+        };
+        assert_eq!(term.cursor_style().shape, AlacCursorShape::Beam);
+
+        // DECSCUSR 4: steady underline
+        advance(&mut term, b"\x1b[4 q");
+        assert_eq!(term.cursor_style().shape, AlacCursorShape::Underline);
+        assert!(!term.cursor_style().blinking);
+
+        // DECSCUSR 1: blinking block
+        advance(&mut term, b"\x1b[1 q");
+        assert_eq!(term.cursor_style().shape, AlacCursorShape::Block);
+        assert!(term.cursor_style().blinking);
+
+        // DECSCUSR 0 goes back to the shape from the settings
+        advance(&mut term, b"\x1b[0 q");
+        assert_eq!(term.cursor_style().shape, AlacCursorShape::Beam);
+    }
+
+    #[test]
+    fn test_process_exited_event() {
+        let process = ProcessInfo {
+            name: "npm".to_string(),
+            cwd: PathBuf::from("/project"),
+            argv: vec!["npm".to_string(), "test".to_string()],
+        };
+        assert!(matches!(
+            process_exited_event(None, Some(&process), 1),
+            Event::ProcessExited { command, exit_code: 1 } if command == "npm test"
+        ));
+        assert!(matches!(
+            process_exited_event(Some("cargo test -p terminal"), Some(&process), 101),
+            Event::ProcessExited { command, exit_code: 101 } if command == "cargo test -p terminal"
+        ));
+        let process = ProcessInfo {
+            argv: Vec::new(),
+            ..process
+        };
+        assert!(matches!(
+            process_exited_event(None, Some(&process), 0),
+            Event::ProcessExited { command, exit_code: 0 } if command == "npm"
+        ));
+    }
+
+    #[test]
+    fn test_enter_after_exit_event() {
+        assert!(matches!(
+            enter_after_exit_event(EnterAfterExit::Close),
+            Event::CloseTerminal
+        ));
+        assert!(matches!(
+            enter_after_exit_event(EnterAfterExit::Restart),
+            Event::RestartTerminal
+        ));
+    }
+
+    #[test]
+    fn test_exit_event() {
+        assert!(matches!(
+            exit_event(OnExit::Close),
+            Some(Event::CloseTerminal)
+        ));
+        assert!(exit_event(OnExit::KeepOpen).is_none());
+        assert!(matches!(
+            exit_event(OnExit::Restart),
+            Some(Event::RestartTerminal)
+        ));
+    }
+
+    #[test]
+    fn test_adjacent_match_index() {
+        let point = |line, column| AlacPoint::new(Line(line), Column(column));
+        let matches = [
+            point(0, 0)..=point(0, 4),
+            point(1, 0)..=point(1, 4),
+            point(2, 1)..=point(2, 5),
+        ];
+
+        let next = |from| adjacent_match_index(&matches, from, AlacDirection::Right);
+        assert_eq!(next(point(0, 4)), Some(1));
+        assert_eq!(next(point(1, 2)), Some(2));
+        assert_eq!(next(point(2, 5)), Some(0));
+
+        let previous = |from| adjacent_match_index(&matches, from, AlacDirection::Left);
+        assert_eq!(previous(point(1, 4)), Some(0));
+        assert_eq!(previous(point(0, 4)), Some(2));
+
+        assert_eq!(
+            adjacent_match_index(&[], point(0, 0), AlacDirection::Right),
+            None
+        );
+    }
+
+    #[test]
+    fn test_match_count_cycles() {
+        let point = |line, column| AlacPoint::new(Line(line), Column(column));
+        let matches = [
+            point(-2, 0)..=point(-2, 2),
+            point(0, 3)..=point(0, 5),
+            point(3, 0)..=point(3, 2),
+        ];
+        assert_eq!(match_count_label(None, 0), None);
+        assert_eq!(match_count_label(None, 3).as_deref(), Some("0/3"));
+
+        // Like FindNext, starting from the cursor and then from the end of each match
+        let mut from = point(1, 0);
+        let mut labels = Vec::new();
+        for _ in 0..4 {
+            let active = adjacent_match_index(&matches, from, AlacDirection::Right).unwrap();
+            from = *matches[active].end();
+            labels.push(match_count_label(Some(active), matches.len()).unwrap());
+        }
+        assert_eq!(labels, ["3/3", "1/3", "2/3", "3/3"]);
+
+        let previous = adjacent_match_index(&matches, *matches[0].end(), AlacDirection::Left);
+        assert_eq!(
+            match_count_label(previous, matches.len()).as_deref(),
+            Some("3/3")
+        );
+    }
+
+    #[test]
+    fn test_paste_chunks() {
+        let text = "héllo wörld 🦀\n".repeat(1000);
+        let chunks = paste_chunks(&text, 4096);
+        assert!(chunks.len() > 1);
+        assert!(chunks.iter().all(|chunk| chunk.len() <= 4096));
+        assert_eq!(chunks.concat(), text);
+
+        assert_eq!(paste_chunks("ls", 4096), vec!["ls".to_string()]);
+        assert!(paste_chunks("", 4096).is_empty());
+        assert_eq!(paste_chunks("🦀🦀", 1).concat(), "🦀🦀");
+    }
+
+    #[test]
+    fn test_truncate_middle() {
+        assert_eq!(truncate_middle("~/projects/zed", 25), "~/projects/zed");
+        assert_eq!(
+            truncate_middle("~/projects/zed/crates/terminal_view/src", 25),
+            "~/projects/z…nal_view/src"
+        );
+        assert_eq!(truncate_middle("abcdefghij", 5), "ab…ij");
+        assert_eq!(truncate_middle("αβγδεζηθικ", 6), "αβγ…ικ");
+    }
+
+    #[test]
+    fn test_resolve_title() {
+        let process_title = || Some("zed — vim main.rs".to_string());
+
+        assert_eq!(
+            resolve_title(Some("server"), "vim", process_title(), false),
+            "server"
+        );
+        assert_eq!(resolve_title(None, "vim", process_title(), false), "vim");
+        assert_eq!(
+            resolve_title(Some("  "), " ", process_title(), false),
+            "zed — vim main.rs"
+        );
+        assert_eq!(resolve_title(None, "", None, false), "Terminal");
+        assert_eq!(
+            resolve_title(None, "a title that is much too long for a tab", None, true),
+            "a title that is much too …"
+        );
+    }
+
+    #[test]
+    fn test_abbreviate_path() {
+        assert_eq!(abbreviate_path("~", 25), "~");
+        assert_eq!(abbreviate_path("~/projects", 25), "~/projects");
+        assert_eq!(
+            abbreviate_path("~/projects/zed/crates/terminal_view/src", 25),
+            "~/p/z/c/t/src"
+        );
+        assert_eq!(
+            abbreviate_path("/usr/local/share/.config/fish", 25),
+            "/u/l/s/.c/fish"
+        );
+        assert_eq!(
+            abbreviate_path("~/a/b/c/d/e/f/g/h/i/j/k/l/m/src", 25),
+            "~/…/src"
+        );
+        assert_eq!(
+            abbreviate_path("~/projects/a_directory_with_a_very_long_name", 25),
+            "a_directory_with_a_very_l…"
+        );
     }
 
     #[test]
@@ -1602,6 +5081,7 @@ mod tests {
                     Pixels::from(cell_size * (viewport_cells as f32)),
                     Pixels::from(cell_size * (viewport_cells as f32)),
                 ),
+                scale_factor: 1.,
             };
 
             let cells = get_cells(size, &mut rng);
@@ -1638,6 +5118,7 @@ mod tests {
             cell_width: Pixels::from(10.),
             line_height: Pixels::from(10.),
             size: size(Pixels::from(100.), Pixels::from(100.)),
+            scale_factor: 1.,
         };
 
         let cells = get_cells(size, &mut rng);
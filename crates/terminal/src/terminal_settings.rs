@@ -1,5 +1,6 @@
+use alacritty_terminal::vte::ansi::CursorShape as AlacCursorShape;
 use collections::HashMap;
-use gpui::{px, AbsoluteLength, AppContext, FontFeatures, Pixels};
+use gpui::{px, AbsoluteLength, AppContext, Edges, FontFeatures, Hsla, Pixels, Rgba};
 use schemars::{
     gen::SchemaGenerator,
     schema::{InstanceType, RootSchema, Schema, SchemaObject},
@@ -9,6 +10,7 @@ use serde_derive::{Deserialize, Serialize};
 use serde_json::Value;
 use settings::{SettingsJsonSchemaParams, SettingsSources};
 use std::path::PathBuf;
+use theme::Theme;
 
 #[derive(Copy, Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
@@ -26,12 +28,17 @@ pub struct Toolbar {
 #[derive(Deserialize)]
 pub struct TerminalSettings {
     pub shell: Shell,
+    pub login_shell: bool,
+    pub dotenv: bool,
     pub working_directory: WorkingDirectory,
     pub font_size: Option<Pixels>,
     pub font_family: Option<String>,
     pub line_height: TerminalLineHeight,
+    pub cell_width: f32,
+    pub padding: TerminalPadding,
     pub font_features: Option<FontFeatures>,
     pub env: HashMap<String, String>,
+    pub term: String,
     pub blinking: TerminalBlink,
     pub alternate_scroll: AlternateScroll,
     pub option_as_meta: bool,
@@ -42,6 +49,40 @@ pub struct TerminalSettings {
     pub detect_venv: VenvSettings,
     pub max_scroll_history_lines: Option<usize>,
     pub toolbar: Toolbar,
+    pub git_branch_in_title: bool,
+    pub double_size_lines: bool,
+    pub title_truncation: TitleTruncation,
+    pub show_invisibles: bool,
+    pub clear_behavior: ClearBehavior,
+    pub clear_redraws_prompt: bool,
+    pub local_echo: bool,
+    pub paste_chunk_size: usize,
+    pub paste_chunk_delay_ms: u64,
+    pub max_bytes_per_frame: usize,
+    pub error_pattern: String,
+    pub snap_to_device_pixels: bool,
+    pub search_enter_behavior: SearchEnterBehavior,
+    pub background_image: Option<BackgroundImage>,
+    pub word_separators: String,
+    pub on_exit: OnExit,
+    pub enter_after_exit: EnterAfterExit,
+    pub confirm_multiline_paste: bool,
+    pub confirm_close_with_running_process: bool,
+    pub allow_osc52_write: bool,
+    pub dim_inactive: bool,
+    pub scroll_on_output: bool,
+    pub smooth_scroll: bool,
+    pub strip_trailing_newline_on_paste: bool,
+    pub ligatures: bool,
+    pub focus_follows_mouse: bool,
+    pub cursor_blink_interval_ms: u64,
+    pub bold_is_bright: bool,
+    pub cursor_shape: CursorShape,
+    pub bell: TerminalBell,
+    pub notify_on_bell: bool,
+    pub notify_on_failure: bool,
+    pub theme: Option<String>,
+    pub colors: TerminalColors,
 }
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema)]
@@ -94,6 +135,16 @@ pub struct TerminalSettingsContent {
     ///
     /// Default: system
     pub shell: Option<Shell>,
+    /// Whether to start the shell as a login shell, passing it `-l` so it sources
+    /// files like `.profile` or `.zprofile`. Not used for tasks.
+    ///
+    /// Default: false
+    pub login_shell: Option<bool>,
+    /// Whether to load the variables in the `.env` file in the terminal's working
+    /// directory. Variables set by `env` take precedence over them.
+    ///
+    /// Default: false
+    pub dotenv: Option<bool>,
     /// What working directory to use when launching the terminal
     ///
     /// Default: current_project_directory
@@ -112,12 +163,27 @@ pub struct TerminalSettingsContent {
     ///
     /// Default: comfortable
     pub line_height: Option<TerminalLineHeight>,
+    /// How much wider or narrower than the font's characters the terminal's
+    /// cells are, between 0.5 and 2.
+    ///
+    /// Default: 1.0
+    pub cell_width: Option<f32>,
+    /// The space between the terminal's text and its edges, in pixels. Either a
+    /// single value for all sides, or `top`, `right`, `bottom` and `left`.
+    ///
+    /// Default: 0
+    pub padding: Option<TerminalPadding>,
     pub font_features: Option<FontFeatures>,
     /// Any key-value pairs added to this list will be added to the terminal's
     /// environment. Use `:` to separate multiple values.
     ///
     /// Default: {}
     pub env: Option<HashMap<String, String>>,
+    /// The `TERM` the shell is started with, telling programs what the terminal
+    /// supports. Setting `TERM` in `env` takes precedence.
+    ///
+    /// Default: xterm-256color
+    pub term: Option<String>,
     /// Sets the cursor blinking behavior in the terminal.
     ///
     /// Default: terminal_controlled
@@ -129,7 +195,9 @@ pub struct TerminalSettingsContent {
     ///
     /// Default: off
     pub alternate_scroll: Option<AlternateScroll>,
-    /// Sets whether the option key behaves as the meta key.
+    /// Sets whether the option key behaves as the meta key, sending escape
+    /// followed by the key instead of the character it composes. It applies to
+    /// both option keys, as the platform doesn't report which one is held.
     ///
     /// Default: false
     pub option_as_meta: Option<bool>,
@@ -163,6 +231,183 @@ pub struct TerminalSettingsContent {
     pub max_scroll_history_lines: Option<usize>,
     /// Toolbar related settings
     pub toolbar: Option<ToolbarContent>,
+    /// Whether to show the git branch of the terminal's working directory
+    /// in the terminal's tab title.
+    ///
+    /// Default: false
+    pub git_branch_in_title: Option<bool>,
+    /// Whether to draw lines marked as double width or double height
+    /// (DECDWL and DECDHL) at double size. When disabled, these lines
+    /// are drawn like any other line.
+    ///
+    /// Default: true
+    pub double_size_lines: Option<bool>,
+    /// How to shorten the working directory shown in long tab titles.
+    ///
+    /// Default: end
+    pub title_truncation: Option<TitleTruncation>,
+    /// Whether to draw trailing whitespace as dots, and control and other
+    /// invisible characters as symbols. This only affects how the output is
+    /// drawn, not the text that gets copied.
+    ///
+    /// Default: false
+    pub show_invisibles: Option<bool>,
+    /// What the `terminal::Clear` action clears. Typing `clear` in a shell is
+    /// not affected by this setting: its behavior depends on the shell.
+    ///
+    /// Default: screen_and_scrollback
+    pub clear_behavior: Option<ClearBehavior>,
+    /// Whether the `terminal::Clear` action also asks the shell to redraw its
+    /// prompt, so it ends up at the top of the cleared screen. This only
+    /// happens when a known shell is in the foreground.
+    ///
+    /// Default: false
+    pub clear_redraws_prompt: Option<bool>,
+    /// Whether to show typed characters before the terminal echoes them,
+    /// which makes slow remote sessions feel more responsive. Predictions
+    /// are underlined until the real echo arrives, and are only made once
+    /// the program is known to echo what's typed.
+    ///
+    /// Default: false
+    pub local_echo: Option<bool>,
+    /// The most bytes of a paste to write to the terminal at once. Large
+    /// pastes are split into chunks of this size, so programs reading them
+    /// aren't overwhelmed.
+    ///
+    /// Default: 4096
+    pub paste_chunk_size: Option<usize>,
+    /// How long to wait between the chunks of a large paste, in milliseconds.
+    ///
+    /// Default: 0
+    pub paste_chunk_delay_ms: Option<u64>,
+    /// The most bytes of output to read from the terminal's process per frame.
+    /// Output from programs printing faster than this is read over the following
+    /// frames, so the terminal stays responsive. None of it is lost.
+    /// Existing terminals will not pick up this change until they are recreated.
+    ///
+    /// Default: 1048576
+    pub max_bytes_per_frame: Option<usize>,
+    /// A regular expression matching the lines of output that report errors.
+    /// The `terminal::GoToFirstErrorInOutput` and `terminal::GoToLastErrorInOutput`
+    /// actions open the `path:line` locations of these errors.
+    ///
+    /// Default: "(?i)\\berror\\b"
+    pub error_pattern: Option<String>,
+    /// Whether to round the size of the terminal's cells to whole device pixels,
+    /// so glyphs line up with the display's pixel grid. The cells are re-snapped
+    /// whenever the window moves to a display with a different scale factor.
+    ///
+    /// Default: false
+    pub snap_to_device_pixels: Option<bool>,
+    /// What pressing Enter in the terminal panel's search bar does.
+    ///
+    /// Default: select_next
+    pub search_enter_behavior: Option<SearchEnterBehavior>,
+    /// An image to show behind the terminal's text, where the default
+    /// background color would otherwise be.
+    ///
+    /// Default: null
+    pub background_image: Option<BackgroundImage>,
+    /// The characters that end a word when double-clicking to select it.
+    ///
+    /// Default: ",│`|:\"' ()[]{}<>\t"
+    pub word_separators: Option<String>,
+    /// What happens to a terminal when its shell exits.
+    ///
+    /// Default: close
+    pub on_exit: Option<OnExit>,
+    /// What pressing enter does in a terminal that's kept open after its
+    /// shell exited.
+    ///
+    /// Default: close
+    pub enter_after_exit: Option<EnterAfterExit>,
+    /// Whether to ask before pasting several lines into a program that would run
+    /// each of them as a command, because it didn't enable bracketed paste.
+    ///
+    /// Default: false
+    pub confirm_multiline_paste: Option<bool>,
+    /// Whether to ask before closing a terminal in which a command other than the
+    /// shell is running.
+    ///
+    /// Default: false
+    pub confirm_close_with_running_process: Option<bool>,
+    /// Whether programs running in the terminal, including those on remote
+    /// machines, can put text on the clipboard with OSC 52. Reading the clipboard
+    /// this way is never allowed.
+    ///
+    /// Default: true
+    pub allow_osc52_write: Option<bool>,
+    /// Whether to dim terminals that aren't focused, so the focused one
+    /// stands out among several.
+    ///
+    /// Default: false
+    pub dim_inactive: Option<bool>,
+    /// Whether new output scrolls the terminal back to the bottom while it's
+    /// scrolled up. Otherwise, it stays put until it's scrolled down or typed in.
+    ///
+    /// Default: false
+    pub scroll_on_output: Option<bool>,
+    /// Whether trackpads scroll the scrollback smoothly, part way through
+    /// a line, rather than a whole line at a time.
+    ///
+    /// Default: false
+    pub smooth_scroll: Option<bool>,
+    /// Whether to leave out the line ending at the end of pasted text, so that
+    /// a pasted command can be looked over before it's run.
+    ///
+    /// Default: false
+    pub strip_trailing_newline_on_paste: Option<bool>,
+    /// Whether to shape runs of adjacent, alike cells together, so that the
+    /// font's ligatures form. Which ones form is set by `font_features`.
+    ///
+    /// Default: false
+    pub ligatures: Option<bool>,
+    /// Whether to focus a terminal when the pointer stays over it for a moment,
+    /// such as to move between split panes without clicking.
+    ///
+    /// Default: false
+    pub focus_follows_mouse: Option<bool>,
+    /// How long the cursor stays on, and then off, while blinking, in milliseconds.
+    ///
+    /// Default: 500
+    pub cursor_blink_interval_ms: Option<u64>,
+    /// Whether bold text in one of the 8 standard colors is drawn in its bright
+    /// variant, as some older programs expect.
+    ///
+    /// Default: false
+    pub bold_is_bright: Option<bool>,
+    /// The shape of the cursor, until a program running in the terminal changes it.
+    /// Whether it blinks is set by `blinking`.
+    ///
+    /// Default: block
+    pub cursor_shape: Option<CursorShape>,
+    /// How a program ringing the terminal's bell is shown, besides the bell the
+    /// terminal's tab shows until the terminal gets input.
+    ///
+    /// Default: none
+    pub bell: Option<TerminalBell>,
+    /// Whether to show a notification when a program asks for one with OSC 9 while
+    /// the terminal isn't focused, or rings the bell while the window isn't focused.
+    ///
+    /// Default: false
+    pub notify_on_bell: Option<bool>,
+    /// Whether to show a notification when the shell, or a task's command, exits
+    /// with a nonzero code while the terminal isn't focused.
+    ///
+    /// Default: false
+    pub notify_on_failure: Option<bool>,
+    /// The name of a theme to take the terminal's colors from, instead of
+    /// the editor's theme. The editor's theme is used when the named one
+    /// isn't installed.
+    ///
+    /// Default: null
+    pub theme: Option<String>,
+    /// Colors to use instead of the theme's, as hex strings. The keys are
+    /// the names of the 16 ANSI colors (`black`, `bright_red`...), indices
+    /// into the 256 color palette, `foreground` or `background`.
+    ///
+    /// Default: {}
+    pub colors: Option<HashMap<String, String>>,
 }
 
 impl settings::Settings for TerminalSettings {
@@ -236,6 +481,52 @@ impl TerminalLineHeight {
     }
 }
 
+/// The space between the terminal's text and its edges, in pixels.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(untagged)]
+pub enum TerminalPadding {
+    /// The same space on every side.
+    Uniform(f32),
+    /// The space on each side, with the ones left out being 0.
+    Sides {
+        #[serde(default)]
+        top: f32,
+        #[serde(default)]
+        right: f32,
+        #[serde(default)]
+        bottom: f32,
+        #[serde(default)]
+        left: f32,
+    },
+}
+
+impl Default for TerminalPadding {
+    fn default() -> Self {
+        TerminalPadding::Uniform(0.)
+    }
+}
+
+impl TerminalPadding {
+    /// The padding on each side, with negative values treated as 0.
+    pub fn edges(&self) -> Edges<Pixels> {
+        let (top, right, bottom, left) = match *self {
+            TerminalPadding::Uniform(padding) => (padding, padding, padding, padding),
+            TerminalPadding::Sides {
+                top,
+                right,
+                bottom,
+                left,
+            } => (top, right, bottom, left),
+        };
+        Edges {
+            top: px(top.max(0.)),
+            right: px(right.max(0.)),
+            bottom: px(bottom.max(0.)),
+            left: px(left.max(0.)),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum TerminalBlink {
@@ -248,6 +539,103 @@ pub enum TerminalBlink {
     On,
 }
 
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TerminalBell {
+    /// Only show the bell in the terminal's tab.
+    #[default]
+    None,
+    /// Flash the terminal's background.
+    Visual,
+    /// Play the system's alert sound.
+    Audible,
+    /// Flash the background and play the alert sound.
+    Both,
+}
+
+impl TerminalBell {
+    pub fn is_visual(self) -> bool {
+        matches!(self, TerminalBell::Visual | TerminalBell::Both)
+    }
+
+    pub fn is_audible(self) -> bool {
+        matches!(self, TerminalBell::Audible | TerminalBell::Both)
+    }
+}
+
+/// Colors that replace the theme's in the terminal, by their index in its palette: the 256
+/// ANSI colors, then [`TerminalColors::FOREGROUND`] and [`TerminalColors::BACKGROUND`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TerminalColors(HashMap<usize, Hsla>);
+
+impl TerminalColors {
+    pub const FOREGROUND: usize = 256;
+    pub const BACKGROUND: usize = 257;
+
+    /// Parses the colors from the settings, skipping and warning about the invalid ones.
+    pub fn parse(colors: &HashMap<String, String>) -> Self {
+        let mut parsed = HashMap::default();
+        for (slot, color) in colors {
+            match parse_color(slot, color) {
+                Ok((index, color)) => {
+                    parsed.insert(index, color);
+                }
+                Err(error) => log::warn!("invalid terminal color {slot:?}: {error}"),
+            }
+        }
+        Self(parsed)
+    }
+
+    pub fn get(&self, index: usize) -> Option<Hsla> {
+        self.0.get(&index).copied()
+    }
+
+    /// The color at the index in the palette, falling back to the theme's color for it.
+    pub fn color_at_index(&self, index: usize, theme: &Theme) -> Hsla {
+        self.get(index)
+            .unwrap_or_else(|| crate::get_color_at_index(index, theme))
+    }
+
+    /// The terminal's background, falling back to the theme's terminal background.
+    pub fn background(&self, theme: &Theme) -> Hsla {
+        self.get(Self::BACKGROUND)
+            .unwrap_or(theme.colors().terminal_background)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for TerminalColors {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let colors = <HashMap<String, String> as serde::Deserialize>::deserialize(deserializer)?;
+        Ok(Self::parse(&colors))
+    }
+}
+
+/// Parses a color from the settings into its index in the palette and its value.
+pub(crate) fn parse_color(slot: &str, color: &str) -> anyhow::Result<(usize, Hsla)> {
+    const ANSI_NAMES: [&str; 8] = [
+        "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+    ];
+    let index = match slot {
+        "foreground" => TerminalColors::FOREGROUND,
+        "background" => TerminalColors::BACKGROUND,
+        _ => {
+            let (name, offset) = match slot.strip_prefix("bright_") {
+                Some(name) => (name, 8),
+                None => (slot, 0),
+            };
+            match ANSI_NAMES.iter().position(|ansi_name| *ansi_name == name) {
+                Some(index) => index + offset,
+                None => match slot.parse::<u8>() {
+                    Ok(index) => index as usize,
+                    Err(_) => anyhow::bail!("unknown color slot"),
+                },
+            }
+        }
+    };
+    let color = Rgba::try_from(color)?;
+    Ok((index, color.into()))
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum Shell {
@@ -267,6 +655,122 @@ pub enum AlternateScroll {
     Off,
 }
 
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ClearBehavior {
+    /// Clear the screen, keeping the scrollback.
+    Screen,
+    /// Clear the scrollback, keeping the screen.
+    Scrollback,
+    /// Clear both the screen and the scrollback.
+    #[default]
+    ScreenAndScrollback,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct BackgroundImage {
+    /// The absolute path of the image.
+    pub path: PathBuf,
+    /// How visible the image is, from 0 to 1. The terminal's background
+    /// color is blended over the rest of it, to keep text legible.
+    ///
+    /// Default: 0.2
+    #[serde(default = "BackgroundImage::default_opacity")]
+    pub opacity: f32,
+    /// How the image is fit to the terminal.
+    ///
+    /// Default: cover
+    #[serde(default)]
+    pub fit: BackgroundImageFit,
+}
+
+impl BackgroundImage {
+    fn default_opacity() -> f32 {
+        0.2
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BackgroundImageFit {
+    /// Stretch the image to fill the terminal.
+    Fill,
+    /// Scale the image to fit within the terminal.
+    Contain,
+    /// Scale the image to cover the whole terminal.
+    #[default]
+    Cover,
+    /// Show the image at its original size.
+    None,
+}
+
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CursorShape {
+    /// A block covering the whole cell.
+    #[default]
+    Block,
+    /// A line under the cell.
+    Underline,
+    /// A vertical bar before the cell.
+    Bar,
+}
+
+impl From<CursorShape> for AlacCursorShape {
+    fn from(shape: CursorShape) -> Self {
+        match shape {
+            CursorShape::Block => AlacCursorShape::Block,
+            CursorShape::Underline => AlacCursorShape::Underline,
+            CursorShape::Bar => AlacCursorShape::Beam,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OnExit {
+    /// Close the terminal.
+    #[default]
+    Close,
+    /// Keep the terminal open, showing the shell's last output.
+    KeepOpen,
+    /// Start a new shell in the terminal, in the directory the old one started in.
+    Restart,
+}
+
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum EnterAfterExit {
+    /// Close the terminal.
+    #[default]
+    Close,
+    /// Start a new shell in the terminal, in the directory the old one started in.
+    Restart,
+}
+
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchEnterBehavior {
+    /// Select the next match, keeping the search bar open.
+    #[default]
+    SelectNext,
+    /// Keep the current match selected, close the search bar and focus the terminal.
+    Accept,
+}
+
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TitleTruncation {
+    /// Show the name of the working directory, cut off at the end: `terminal_vi…`
+    #[default]
+    End,
+    /// Show the whole working directory, with its middle cut out: `~/projects…minal/src`
+    Middle,
+    /// Show the whole working directory, with every directory but the last
+    /// shortened to its first letter: `~/p/z/c/t/src`
+    Abbreviate,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum WorkingDirectory {
@@ -275,11 +779,15 @@ pub enum WorkingDirectory {
     CurrentProjectDirectory,
     /// Use the first project in this workspace's directory.
     FirstProjectDirectory,
+    /// Use the directory of the file open in the active editor. Will fallback
+    /// to the current project directory strategy if no file is open.
+    ActiveFileDirectory,
     /// Always use this platform's home directory (if it can be found).
     AlwaysHome,
-    /// Always use a specific directory. This value will be shell expanded.
+    /// Always use a specific directory. This value will be shell expanded,
+    /// and relative paths are resolved against the first project directory.
     /// If this path is not a valid directory the terminal will default to
-    /// this platform's home directory  (if it can be found).
+    /// this platform's home directory  (if it can be found), and say so.
     Always { directory: String },
 }
 
@@ -34,6 +34,9 @@ define_connection! {
             DROP TABLE terminals;
 
             ALTER TABLE terminals2 RENAME TO terminals;
+        ),
+        sql!(
+            ALTER TABLE terminals ADD COLUMN title TEXT;
         )];
 }
 
@@ -56,8 +59,31 @@ impl TerminalDb {
             workspace_id: WorkspaceId,
             working_directory: PathBuf
         ) -> Result<()> {
-            INSERT OR REPLACE INTO terminals(item_id, workspace_id, working_directory)
-            VALUES (?, ?, ?)
+            INSERT INTO terminals(item_id, workspace_id, working_directory)
+            VALUES (?1, ?2, ?3)
+            ON CONFLICT DO UPDATE SET
+                working_directory = ?3
+        }
+    }
+
+    query! {
+        pub async fn save_title(
+            item_id: ItemId,
+            workspace_id: WorkspaceId,
+            title: String
+        ) -> Result<()> {
+            INSERT INTO terminals(item_id, workspace_id, title)
+            VALUES (?1, ?2, ?3)
+            ON CONFLICT DO UPDATE SET
+                title = ?3
+        }
+    }
+
+    query! {
+        pub fn get_title(item_id: ItemId, workspace_id: WorkspaceId) -> Result<Option<String>> {
+            SELECT title
+            FROM terminals
+            WHERE item_id = ? AND workspace_id = ?
         }
     }
 
@@ -69,3 +95,60 @@ impl TerminalDb {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use db::open_test_db;
+
+    #[gpui::test]
+    async fn test_terminal_state_round_trip() {
+        let db = TerminalDb(open_test_db("test_terminal_state_round_trip").await);
+        let workspace_id = db
+            .write(|conn| {
+                conn.select_row::<WorkspaceId>(sql!(
+                    INSERT INTO workspaces DEFAULT VALUES RETURNING workspace_id
+                ))
+                .unwrap()()
+                .unwrap()
+                .unwrap()
+            })
+            .await;
+
+        assert_eq!(db.get_working_directory(1, workspace_id).unwrap(), None);
+        assert_eq!(db.get_title(1, workspace_id).unwrap(), None);
+
+        db.save_working_directory(1, workspace_id, PathBuf::from("/tmp/project"))
+            .await
+            .unwrap();
+        db.save_title(1, workspace_id, "server".to_string())
+            .await
+            .unwrap();
+        // Saving one doesn't clear the other
+        db.save_working_directory(1, workspace_id, PathBuf::from("/tmp/project/src"))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            db.get_working_directory(1, workspace_id).unwrap(),
+            Some(PathBuf::from("/tmp/project/src"))
+        );
+        assert_eq!(
+            db.get_title(1, workspace_id).unwrap(),
+            Some("server".to_string())
+        );
+        assert_eq!(db.get_title(2, workspace_id).unwrap(), None);
+
+        // Each restore gives the terminal a new item id, which its title is saved under again
+        for (old_item_id, new_item_id) in [(1, 2), (2, 3)] {
+            let title = db.get_title(old_item_id, workspace_id).unwrap().unwrap();
+            db.save_title(new_item_id, workspace_id, title)
+                .await
+                .unwrap();
+        }
+        assert_eq!(
+            db.get_title(3, workspace_id).unwrap(),
+            Some("server".to_string())
+        );
+    }
+}
@@ -0,0 +1,93 @@
+use editor::Editor;
+use gpui::{
+    AppContext, DismissEvent, EventEmitter, FocusHandle, FocusableView, Render, View,
+    VisualContext, WeakView,
+};
+use ui::prelude::*;
+use workspace::ModalView;
+
+use crate::TerminalView;
+
+/// Asks for a name for a terminal, which its tab shows instead of its title.
+pub struct RenameTerminalModal {
+    name_editor: View<Editor>,
+    terminal_view: WeakView<TerminalView>,
+}
+
+impl ModalView for RenameTerminalModal {}
+
+impl FocusableView for RenameTerminalModal {
+    fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
+        self.name_editor.focus_handle(cx)
+    }
+}
+
+impl EventEmitter<DismissEvent> for RenameTerminalModal {}
+
+impl RenameTerminalModal {
+    pub fn new(
+        terminal_view: WeakView<TerminalView>,
+        name: String,
+        cx: &mut ViewContext<Self>,
+    ) -> Self {
+        let name_editor = cx.new_view(|cx| {
+            let mut editor = Editor::single_line(cx);
+            editor.set_placeholder_text("Terminal name", cx);
+            editor.set_text(name, cx);
+            editor.select_all(&editor::actions::SelectAll, cx);
+            editor
+        });
+        cx.subscribe(&name_editor, |_, _, event, cx| {
+            if let editor::EditorEvent::Blurred = event {
+                cx.emit(DismissEvent);
+            }
+        })
+        .detach();
+        Self {
+            name_editor,
+            terminal_view,
+        }
+    }
+
+    fn cancel(&mut self, _: &menu::Cancel, cx: &mut ViewContext<Self>) {
+        cx.emit(DismissEvent);
+    }
+
+    fn confirm(&mut self, _: &menu::Confirm, cx: &mut ViewContext<Self>) {
+        let name = self.name_editor.read(cx).text(cx).trim().to_string();
+        self.terminal_view
+            .update(cx, |terminal_view, cx| {
+                terminal_view.set_custom_title((!name.is_empty()).then_some(name), cx);
+                terminal_view.focus_handle.focus(cx);
+            })
+            .ok();
+        cx.emit(DismissEvent);
+    }
+}
+
+impl Render for RenameTerminalModal {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        div()
+            .elevation_2(cx)
+            .key_context("RenameTerminal")
+            .on_action(cx.listener(Self::cancel))
+            .on_action(cx.listener(Self::confirm))
+            .w_96()
+            .child(
+                v_flex()
+                    .px_1()
+                    .pt_0p5()
+                    .gap_px()
+                    .child(div().px_2().py_1().child(self.name_editor.clone()))
+                    .child(
+                        div()
+                            .h_px()
+                            .w_full()
+                            .bg(cx.theme().colors().element_background),
+                    )
+                    .child(div().px_2().py_1().child(
+                        Label::new("Leave empty to use the terminal's title").color(Color::Muted),
+                    )),
+            )
+    }
+}
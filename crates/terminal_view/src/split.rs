@@ -0,0 +1,281 @@
+use std::mem;
+
+/// How a split lays out its children.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SplitAxis {
+    /// Side by side, from left to right.
+    Horizontal,
+    /// On top of each other, from top to bottom.
+    Vertical,
+}
+
+/// A direction to move focus in, from one pane to the one next to it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SplitDirection {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+/// A tree of panes sharing a single terminal tab. Each split divides its space evenly between
+/// its children.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SplitNode<T> {
+    Pane(T),
+    Split {
+        axis: SplitAxis,
+        children: Vec<SplitNode<T>>,
+    },
+}
+
+/// Where a pane is, as fractions of the space taken up by the whole tree.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct PaneBounds {
+    left: f32,
+    top: f32,
+    right: f32,
+    bottom: f32,
+}
+
+impl<T: PartialEq> SplitNode<T> {
+    /// Splits `pane` along `axis`, putting `new_pane` after it. Returns whether `pane` was found.
+    pub fn split(&mut self, pane: &T, new_pane: T, axis: SplitAxis) -> bool {
+        match self {
+            SplitNode::Pane(existing) => {
+                if existing != pane {
+                    return false;
+                }
+                let existing = mem::replace(
+                    self,
+                    SplitNode::Split {
+                        axis,
+                        children: Vec::new(),
+                    },
+                );
+                *self = SplitNode::Split {
+                    axis,
+                    children: vec![existing, SplitNode::Pane(new_pane)],
+                };
+                true
+            }
+            SplitNode::Split {
+                axis: split_axis,
+                children,
+            } => {
+                // Splitting a pane the same way as its parent adds a sibling instead of nesting
+                if *split_axis == axis {
+                    if let Some(ix) = children.iter().position(|child| child.is_pane(pane)) {
+                        children.insert(ix + 1, SplitNode::Pane(new_pane));
+                        return true;
+                    }
+                }
+                match children.iter_mut().find(|child| child.contains(pane)) {
+                    Some(child) => child.split(pane, new_pane, axis),
+                    None => false,
+                }
+            }
+        }
+    }
+
+    /// Removes `pane`, replacing splits left with a single child by that child. The last pane
+    /// can't be removed. Returns whether `pane` was removed.
+    pub fn remove(&mut self, pane: &T) -> bool {
+        let SplitNode::Split { children, .. } = self else {
+            return false;
+        };
+        if let Some(ix) = children.iter().position(|child| child.is_pane(pane)) {
+            children.remove(ix);
+        } else if !children.iter_mut().any(|child| child.remove(pane)) {
+            return false;
+        }
+        if children.len() == 1 {
+            *self = children.pop().unwrap();
+        }
+        true
+    }
+
+    /// Puts `new_pane` where `pane` is. Returns whether `pane` was found.
+    pub fn replace(&mut self, pane: &T, new_pane: T) -> bool {
+        match self {
+            SplitNode::Pane(existing) if existing == pane => {
+                *existing = new_pane;
+                true
+            }
+            SplitNode::Pane(_) => false,
+            SplitNode::Split { children, .. } => {
+                match children.iter_mut().find(|child| child.contains(pane)) {
+                    Some(child) => child.replace(pane, new_pane),
+                    None => false,
+                }
+            }
+        }
+    }
+
+    pub fn contains(&self, pane: &T) -> bool {
+        match self {
+            SplitNode::Pane(existing) => existing == pane,
+            SplitNode::Split { children, .. } => children.iter().any(|child| child.contains(pane)),
+        }
+    }
+
+    pub fn panes(&self) -> Vec<&T> {
+        match self {
+            SplitNode::Pane(pane) => vec![pane],
+            SplitNode::Split { children, .. } => {
+                children.iter().flat_map(|child| child.panes()).collect()
+            }
+        }
+    }
+
+    /// The pane next to `pane` in `direction`. When several are, it's the one sharing the
+    /// longest edge with `pane`.
+    pub fn neighbor(&self, pane: &T, direction: SplitDirection) -> Option<&T> {
+        const EPSILON: f32 = 1e-4;
+
+        let mut panes = Vec::new();
+        self.layout(
+            PaneBounds {
+                left: 0.,
+                top: 0.,
+                right: 1.,
+                bottom: 1.,
+            },
+            &mut panes,
+        );
+        let (_, current) = *panes.iter().find(|(candidate, _)| *candidate == pane)?;
+
+        let overlap = |start: f32, end: f32, other_start: f32, other_end: f32| {
+            end.min(other_end) - start.max(other_start)
+        };
+        panes
+            .iter()
+            .filter_map(|(candidate, bounds)| {
+                let (gap, shared_edge) = match direction {
+                    SplitDirection::Left => (
+                        current.left - bounds.right,
+                        overlap(current.top, current.bottom, bounds.top, bounds.bottom),
+                    ),
+                    SplitDirection::Right => (
+                        bounds.left - current.right,
+                        overlap(current.top, current.bottom, bounds.top, bounds.bottom),
+                    ),
+                    SplitDirection::Up => (
+                        current.top - bounds.bottom,
+                        overlap(current.left, current.right, bounds.left, bounds.right),
+                    ),
+                    SplitDirection::Down => (
+                        bounds.top - current.bottom,
+                        overlap(current.left, current.right, bounds.left, bounds.right),
+                    ),
+                };
+                (gap.abs() < EPSILON && shared_edge > EPSILON).then_some((*candidate, shared_edge))
+            })
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(candidate, _)| candidate)
+    }
+
+    fn is_pane(&self, pane: &T) -> bool {
+        matches!(self, SplitNode::Pane(existing) if existing == pane)
+    }
+
+    fn layout<'a>(&'a self, bounds: PaneBounds, panes: &mut Vec<(&'a T, PaneBounds)>) {
+        match self {
+            SplitNode::Pane(pane) => panes.push((pane, bounds)),
+            SplitNode::Split { axis, children } => {
+                let count = children.len() as f32;
+                for (ix, child) in children.iter().enumerate() {
+                    let (start, end) = (ix as f32 / count, (ix + 1) as f32 / count);
+                    let child_bounds = match axis {
+                        SplitAxis::Horizontal => {
+                            let width = bounds.right - bounds.left;
+                            PaneBounds {
+                                left: bounds.left + width * start,
+                                right: bounds.left + width * end,
+                                ..bounds
+                            }
+                        }
+                        SplitAxis::Vertical => {
+                            let height = bounds.bottom - bounds.top;
+                            PaneBounds {
+                                top: bounds.top + height * start,
+                                bottom: bounds.top + height * end,
+                                ..bounds
+                            }
+                        }
+                    };
+                    child.layout(child_bounds, panes);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_two_pane_focus_routing() {
+        let mut splits = SplitNode::Pane(0);
+        assert!(splits.split(&0, 1, SplitAxis::Horizontal));
+        assert_eq!(splits.panes(), vec![&0, &1]);
+
+        assert_eq!(splits.neighbor(&0, SplitDirection::Right), Some(&1));
+        assert_eq!(splits.neighbor(&1, SplitDirection::Left), Some(&0));
+        assert_eq!(splits.neighbor(&0, SplitDirection::Left), None);
+        assert_eq!(splits.neighbor(&1, SplitDirection::Right), None);
+        assert_eq!(splits.neighbor(&0, SplitDirection::Up), None);
+        assert_eq!(splits.neighbor(&1, SplitDirection::Down), None);
+        assert_eq!(splits.neighbor(&2, SplitDirection::Left), None);
+    }
+
+    #[test]
+    fn test_nested_focus_routing() {
+        // 0 | 1
+        //   | -
+        //   | 2
+        let mut splits = SplitNode::Pane(0);
+        splits.split(&0, 1, SplitAxis::Horizontal);
+        splits.split(&1, 2, SplitAxis::Vertical);
+        assert_eq!(splits.neighbor(&2, SplitDirection::Up), Some(&1));
+        assert_eq!(splits.neighbor(&1, SplitDirection::Down), Some(&2));
+        assert_eq!(splits.neighbor(&2, SplitDirection::Left), Some(&0));
+        assert_eq!(splits.neighbor(&1, SplitDirection::Left), Some(&0));
+
+        // Splitting along the same axis as the parent adds a sibling
+        splits.split(&0, 3, SplitAxis::Horizontal);
+        assert_eq!(
+            splits,
+            SplitNode::Split {
+                axis: SplitAxis::Horizontal,
+                children: vec![
+                    SplitNode::Pane(0),
+                    SplitNode::Pane(3),
+                    SplitNode::Split {
+                        axis: SplitAxis::Vertical,
+                        children: vec![SplitNode::Pane(1), SplitNode::Pane(2)],
+                    },
+                ],
+            }
+        );
+        assert_eq!(splits.neighbor(&1, SplitDirection::Left), Some(&3));
+        assert_eq!(splits.neighbor(&3, SplitDirection::Left), Some(&0));
+    }
+
+    #[test]
+    fn test_removing_panes() {
+        let mut splits = SplitNode::Pane(0);
+        splits.split(&0, 1, SplitAxis::Horizontal);
+        splits.split(&1, 2, SplitAxis::Vertical);
+
+        assert!(splits.remove(&1));
+        assert_eq!(splits.panes(), vec![&0, &2]);
+        assert_eq!(splits.neighbor(&0, SplitDirection::Right), Some(&2));
+
+        assert!(splits.replace(&2, 3));
+        assert!(splits.remove(&0));
+        assert_eq!(splits, SplitNode::Pane(3));
+        assert!(!splits.remove(&3));
+    }
+}
@@ -1,11 +1,13 @@
+use collections::HashMap;
 use editor::{CursorLayout, HighlightedRange, HighlightedRangeLine};
 use gpui::{
-    div, fill, point, px, relative, AnyElement, Bounds, DispatchPhase, Element, ElementContext,
-    FocusHandle, Font, FontStyle, FontWeight, HighlightStyle, Hitbox, Hsla, InputHandler,
-    InteractiveElement, Interactivity, IntoElement, LayoutId, Model, ModelContext,
-    ModifiersChangedEvent, MouseButton, MouseMoveEvent, Pixels, Point, ShapedLine,
-    StatefulInteractiveElement, StrikethroughStyle, Styled, TextRun, TextStyle, UnderlineStyle,
-    WeakView, WhiteSpace, WindowContext, WindowTextSystem,
+    div, fill, img, point, px, relative, size, AnyElement, AppContext, Bounds, ContentMask,
+    DispatchPhase, Edges, Element, ElementContext, FocusHandle, Font, FontStyle, FontWeight,
+    HighlightStyle, Hitbox, Hsla, InputHandler, InteractiveElement, Interactivity, IntoElement,
+    LayoutId, Model, ModelContext, ModifiersChangedEvent, MouseButton, MouseMoveEvent, ObjectFit,
+    ParentElement, Pixels, Point, ShapedLine, Size, StatefulInteractiveElement, StrikethroughStyle,
+    Styled, TextRun, TextStyle, UnderlineStyle, WeakView, WhiteSpace, WindowContext,
+    WindowTextSystem,
 };
 use itertools::Itertools;
 use language::CursorShape;
@@ -20,15 +22,26 @@ use terminal::{
             CursorShape as AlacCursorShape, NamedColor,
         },
     },
-    terminal_settings::TerminalSettings,
-    HoveredWord, IndexedCell, Terminal, TerminalContent, TerminalSize,
+    terminal_settings::{BackgroundImageFit, EnterAfterExit, TerminalColors, TerminalSettings},
+    HoveredWord, IndexedCell, LineAttribute, Terminal, TerminalContent, TerminalSize,
 };
 use theme::{ActiveTheme, Theme, ThemeSettings};
 use ui::Tooltip;
 use workspace::Workspace;
 
 use std::mem;
-use std::{fmt::Debug, ops::RangeInclusive};
+use std::{
+    fmt::Debug,
+    ops::{Range, RangeInclusive},
+};
+
+const SCROLLBAR_WIDTH: Pixels = px(6.);
+const MIN_THUMB_HEIGHT: Pixels = px(12.);
+const BELL_FLASH_OPACITY: f32 = 0.15;
+/// How much of the terminal is covered up while it's dimmed for not being focused.
+const DIM_OPACITY: f32 = 0.4;
+const MIN_FONT_SIZE: Pixels = px(4.);
+const MAX_FONT_SIZE: Pixels = px(72.);
 
 /// The information generated during layout that is necessary for painting.
 pub struct LayoutState {
@@ -38,12 +51,31 @@ pub struct LayoutState {
     relative_highlighted_ranges: Vec<(RangeInclusive<AlacPoint>, Hsla)>,
     cursor: Option<CursorLayout>,
     background_color: Hsla,
+    /// The background image, and how visible it is.
+    background_image: Option<(AnyElement, f32)>,
+    /// The color the background is flashed with for a visual bell.
+    bell_flash: Option<Hsla>,
+    /// The color painted over the terminal while it's dimmed for not being focused.
+    dim_overlay: Option<Hsla>,
     dimensions: TerminalSize,
     mode: TermMode,
     display_offset: usize,
     hyperlink_tooltip: Option<AnyElement>,
-    gutter: Pixels,
+    /// Shows how the shell exited, while the terminal is kept open after it did.
+    exit_banner: Option<AnyElement>,
+    /// Scrolls back to the bottom, while the terminal is scrolled up.
+    follow_output_button: Option<AnyElement>,
+    /// Which search match is active out of how many, while there are matches.
+    match_count: Option<AnyElement>,
+    /// Where the scrollbar's thumb goes, and its color, while scrolled into the history.
+    scrollbar_thumb: Option<(Bounds<Pixels>, Hsla)>,
+    /// Where the grid starts, relative to the element: past the gutter and the padding.
+    grid_offset: Point<Pixels>,
+    /// How far down the grid is drawn while trackpad scrolling is part way through a line.
+    smooth_scroll_offset: Pixels,
     last_hovered_word: Option<HoveredWord>,
+    /// DEC line attributes, keyed by the line's position in the viewport.
+    line_attributes: HashMap<i32, LineAttribute>,
 }
 
 /// Helper struct for converting data between Alacritty's cursor points, and displayed cursor points.
@@ -87,19 +119,97 @@ impl LayoutCell {
         _visible_bounds: Bounds<Pixels>,
         cx: &mut ElementContext,
     ) {
+        let line_attribute = layout.line_attributes.get(&self.point.line).copied();
+        let cell_advance = cell_advance(line_attribute, layout.dimensions.cell_width);
+        let line_height = layout.dimensions.line_height;
         let pos = {
             let point = self.point;
 
             Point::new(
-                (origin.x + point.column as f32 * layout.dimensions.cell_width).floor(),
-                origin.y + point.line as f32 * layout.dimensions.line_height,
+                (origin.x + point.column as f32 * cell_advance).floor(),
+                origin.y + point.line as f32 * line_height,
             )
         };
 
-        self.text.paint(pos, layout.dimensions.line_height, cx).ok();
+        match line_attribute {
+            None => {
+                self.text.paint(pos, line_height, cx).ok();
+            }
+            // Glyphs can't be stretched horizontally, so center them in their wider cells instead.
+            Some(LineAttribute::DoubleWidth) => {
+                let offset = (cell_advance - self.text.width) * 0.5;
+                self.text
+                    .paint(point(pos.x + offset, pos.y), line_height, cx)
+                    .ok();
+            }
+            // The text was shaped at twice the font size, and each line only shows its half of it.
+            Some(half @ (LineAttribute::DoubleHeightTop | LineAttribute::DoubleHeightBottom)) => {
+                let line_bounds = Bounds::new(
+                    point(origin.x, pos.y),
+                    size(
+                        rendered_line_width(line_attribute, &layout.dimensions),
+                        line_height,
+                    ),
+                );
+                let text_origin = if half == LineAttribute::DoubleHeightBottom {
+                    point(pos.x, pos.y - line_height)
+                } else {
+                    pos
+                };
+                cx.with_content_mask(
+                    Some(ContentMask {
+                        bounds: line_bounds,
+                    }),
+                    |cx| {
+                        self.text.paint(text_origin, line_height * 2., cx).ok();
+                    },
+                );
+            }
+        }
     }
 }
 
+/// The text of a cell, or of several adjacent ones shaped together so that the font's
+/// ligatures can form across them.
+#[derive(Clone, Debug, PartialEq)]
+struct CellRun {
+    column: usize,
+    /// How many of the grid's columns the text takes up.
+    columns: usize,
+    text: String,
+    style: TextRun,
+    /// Whether the text can be shaped along with its neighbors. Wide characters and the
+    /// symbols drawn for invisible ones are kept on their own.
+    joinable: bool,
+}
+
+/// Joins the runs of adjacent cells with the same style, so that the font's ligatures can form
+/// across them. Blank cells aren't drawn, so ligatures don't span them. Monospace fonts keep
+/// ligatures as wide as the characters they replace, so the text stays on the grid.
+fn join_ligature_runs(runs: Vec<CellRun>) -> Vec<CellRun> {
+    let mut joined: Vec<CellRun> = Vec::with_capacity(runs.len());
+    for run in runs {
+        if let Some(last) = joined.last_mut() {
+            let same_style = TextRun {
+                len: run.style.len,
+                ..last.style.clone()
+            } == run.style;
+            if last.joinable
+                && run.joinable
+                && same_style
+                && last.column + last.columns == run.column
+            {
+                last.text.push_str(&run.text);
+                last.style.len += run.style.len;
+                last.columns += run.columns;
+                continue;
+            }
+        }
+        joined.push(run);
+    }
+    joined
+}
+
 #[derive(Clone, Debug, Default)]
 struct LayoutRect {
     point: AlacPoint<i32, i32>,
@@ -125,15 +235,17 @@ impl LayoutRect {
     }
 
     fn paint(&self, origin: Point<Pixels>, layout: &LayoutState, cx: &mut ElementContext) {
+        let line_attribute = layout.line_attributes.get(&self.point.line).copied();
+        let cell_advance = cell_advance(line_attribute, layout.dimensions.cell_width);
         let position = {
             let alac_point = self.point;
             point(
-                (origin.x + alac_point.column as f32 * layout.dimensions.cell_width).floor(),
+                (origin.x + alac_point.column as f32 * cell_advance).floor(),
                 origin.y + alac_point.line as f32 * layout.dimensions.line_height,
             )
         };
         let size = point(
-            (layout.dimensions.cell_width * self.num_of_cells as f32).ceil(),
+            (cell_advance * self.num_of_cells as f32).ceil(),
             layout.dimensions.line_height,
         )
         .into();
@@ -151,6 +263,12 @@ pub struct TerminalElement {
     focused: bool,
     cursor_visible: bool,
     can_navigate_to_selected_word: bool,
+    /// Other terminals that text input should be mirrored to
+    broadcast_peers: Vec<Model<Terminal>>,
+    /// How much the terminal's view is zoomed in, on top of the font size in the settings
+    font_size_delta: Pixels,
+    /// Whether the background is flashing for a visual bell
+    bell_flash: bool,
     interactivity: Interactivity,
 }
 
@@ -163,6 +281,7 @@ impl InteractiveElement for TerminalElement {
 impl StatefulInteractiveElement for TerminalElement {}
 
 impl TerminalElement {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         terminal: Model<Terminal>,
         workspace: WeakView<Workspace>,
@@ -170,6 +289,9 @@ impl TerminalElement {
         focused: bool,
         cursor_visible: bool,
         can_navigate_to_selected_word: bool,
+        broadcast_peers: Vec<Model<Terminal>>,
+        font_size_delta: Pixels,
+        bell_flash: bool,
     ) -> TerminalElement {
         TerminalElement {
             terminal,
@@ -178,6 +300,9 @@ impl TerminalElement {
             focus: focus.clone(),
             cursor_visible,
             can_navigate_to_selected_word,
+            broadcast_peers,
+            font_size_delta,
+            bell_flash,
             interactivity: Default::default(),
         }
         .track_focus(&focus)
@@ -186,15 +311,23 @@ impl TerminalElement {
 
     //Vec<Range<AlacPoint>> -> Clip out the parts of the ranges
 
+    #[allow(clippy::too_many_arguments)]
     fn layout_grid(
         grid: &Vec<IndexedCell>,
         text_style: &TextStyle,
         // terminal_theme: &TerminalStyle,
         text_system: &WindowTextSystem,
         hyperlink: Option<(HighlightStyle, &RangeInclusive<AlacPoint>)>,
+        line_attributes: &HashMap<i32, LineAttribute>,
+        columns: usize,
+        show_invisibles: bool,
+        ligatures: bool,
+        cursor_point: AlacPoint,
         cx: &WindowContext<'_>,
     ) -> (Vec<LayoutCell>, Vec<LayoutRect>) {
-        let theme = cx.theme();
+        let theme = &terminal::terminal_theme(cx);
+        let terminal_colors = &TerminalSettings::get_global(cx).colors;
+        let bold_is_bright = TerminalSettings::get_global(cx).bold_is_bright;
         let mut cells = vec![];
         let mut rects = vec![];
 
@@ -203,8 +336,31 @@ impl TerminalElement {
 
         let linegroups = grid.into_iter().group_by(|i| i.point.line);
         for (line_index, (_, line)) in linegroups.into_iter().enumerate() {
+            let line_attribute = line_attributes.get(&(line_index as i32)).copied();
+            let visible_columns = match line_attribute {
+                Some(_) => LineAttribute::visible_columns(columns),
+                None => columns,
+            };
+            let font_size = match line_attribute {
+                Some(LineAttribute::DoubleHeightTop | LineAttribute::DoubleHeightBottom) => {
+                    text_style.font_size.to_pixels(cx.rem_size()) * 2.
+                }
+                _ => text_style.font_size.to_pixels(cx.rem_size()),
+            };
+
+            // The second half of a double width line is pushed off the screen
+            let line = line
+                .filter(|cell| cell.point.column.0 < visible_columns)
+                .collect::<Vec<_>>();
+            let trailing_whitespace = if show_invisibles {
+                trailing_whitespace(&line, cursor_point)
+            } else {
+                0..0
+            };
+            let mut cell_runs = Vec::new();
+
             for cell in line {
-                let mut fg = cell.fg;
+                let mut fg = bold_color(cell.fg, cell.flags.contains(Flags::BOLD), bold_is_bright);
                 let mut bg = cell.bg;
                 if cell.flags.contains(Flags::INVERSE) {
                     mem::swap(&mut fg, &mut bg);
@@ -234,7 +390,7 @@ impl TerminalElement {
                                                     cell.point.column.0 as i32,
                                                 ),
                                                 1,
-                                                convert_color(&bg, theme),
+                                                convert_color(&bg, theme, terminal_colors),
                                             ))
                                         },
                                         |rect| Some(rect.extend()),
@@ -250,7 +406,7 @@ impl TerminalElement {
                                             cell.point.column.0 as i32,
                                         ),
                                         1,
-                                        convert_color(&bg, theme),
+                                        convert_color(&bg, theme, terminal_colors),
                                     ));
                                 }
                             }
@@ -259,7 +415,7 @@ impl TerminalElement {
                                 cur_rect = Some(LayoutRect::new(
                                     AlacPoint::new(line_index as i32, cell.point.column.0 as i32),
                                     1,
-                                    convert_color(&bg, &theme),
+                                    convert_color(&bg, &theme, terminal_colors),
                                 ));
                             }
                         }
@@ -268,27 +424,56 @@ impl TerminalElement {
 
                 //Layout current cell text
                 {
-                    if !is_blank(&cell) {
-                        let cell_text = cell.c.to_string();
-                        let cell_style =
-                            TerminalElement::cell_style(&cell, fg, theme, text_style, hyperlink);
+                    let invisible_symbol = if show_invisibles {
+                        invisible_symbol(cell.c, trailing_whitespace.contains(&cell.point.column.0))
+                    } else {
+                        None
+                    };
 
-                        let layout_cell = text_system
-                            .shape_line(
-                                cell_text.into(),
-                                text_style.font_size.to_pixels(cx.rem_size()),
-                                &[cell_style],
-                            )
-                            .unwrap();
+                    if !is_blank(&cell) || invisible_symbol.is_some() {
+                        let cell_text = match invisible_symbol {
+                            Some(symbol) => symbol.to_string(),
+                            None => cell_text(&cell),
+                        };
+                        let mut cell_style = TerminalElement::cell_style(
+                            &cell,
+                            fg,
+                            theme,
+                            terminal_colors,
+                            text_style,
+                            hyperlink,
+                        );
+                        cell_style.len = cell_text.len();
+                        if invisible_symbol.is_some() {
+                            cell_style.color.a *= 0.5;
+                        }
 
-                        cells.push(LayoutCell::new(
-                            AlacPoint::new(line_index as i32, cell.point.column.0 as i32),
-                            layout_cell,
-                        ))
+                        let columns = cell_columns(&cell);
+                        cell_runs.push(CellRun {
+                            column: cell.point.column.0,
+                            columns,
+                            text: cell_text,
+                            style: cell_style,
+                            joinable: columns == 1 && invisible_symbol.is_none(),
+                        });
                     };
                 }
             }
 
+            // Double width lines center each glyph in its cell, so their text is never joined.
+            if ligatures && line_attribute.is_none() {
+                cell_runs = join_ligature_runs(cell_runs);
+            }
+            for run in cell_runs {
+                let layout_cell = text_system
+                    .shape_line(run.text.into(), font_size, &[run.style])
+                    .unwrap();
+                cells.push(LayoutCell::new(
+                    AlacPoint::new(line_index as i32, run.column as i32),
+                    layout_cell,
+                ));
+            }
+
             if cur_rect.is_some() {
                 rects.push(cur_rect.take().unwrap());
             }
@@ -302,9 +487,13 @@ impl TerminalElement {
         cursor_point: DisplayCursor,
         size: TerminalSize,
         text_fragment: &ShapedLine,
+        columns: usize,
     ) -> Option<(Point<Pixels>, Pixels)> {
         if cursor_point.line() < size.total_lines() as i32 {
-            let cursor_width = if text_fragment.width == Pixels::ZERO {
+            // Wide characters take up two cells, whatever the width of the glyph drawn for them
+            let cursor_width = if columns > 1 {
+                size.cell_width() * columns as f32
+            } else if text_fragment.width == Pixels::ZERO {
                 size.cell_width()
             } else {
                 text_fragment.width
@@ -330,11 +519,12 @@ impl TerminalElement {
         fg: terminal::alacritty_terminal::vte::ansi::Color,
         // bg: terminal::alacritty_terminal::ansi::Color,
         colors: &Theme,
+        terminal_colors: &TerminalColors,
         text_style: &TextStyle,
         hyperlink: Option<(HighlightStyle, &RangeInclusive<AlacPoint>)>,
     ) -> TextRun {
         let flags = indexed.cell.flags;
-        let mut fg = convert_color(&fg, &colors);
+        let mut fg = convert_color(&fg, &colors, terminal_colors);
 
         // Ghostty uses (175/255) as the multiplier (~0.69), Alacritty uses 0.66, Kitty
         // uses 0.75. We're using 0.7 because it's pretty well in the middle of that.
@@ -483,7 +673,7 @@ impl TerminalElement {
             let terminal = terminal.clone();
             move |e, cx| {
                 terminal.update(cx, |terminal, cx| {
-                    terminal.scroll_wheel(e, origin);
+                    terminal.scroll_wheel(e, origin, cx);
                     cx.notify();
                 })
             }
@@ -536,6 +726,17 @@ impl TerminalElement {
                     },
                 ),
             );
+        } else {
+            #[cfg(target_os = "linux")]
+            self.interactivity.on_mouse_down(
+                MouseButton::Middle,
+                TerminalElement::generic_button_handler(
+                    terminal,
+                    origin,
+                    focus,
+                    move |terminal, _, _, cx| terminal.paste_primary(cx),
+                ),
+            );
         }
     }
 }
@@ -567,8 +768,6 @@ impl Element for TerminalElement {
                 let hitbox = hitbox.unwrap();
                 let settings = ThemeSettings::get_global(cx).clone();
 
-                let buffer_font_size = settings.buffer_font_size(cx);
-
                 let terminal_settings = TerminalSettings::get_global(cx);
                 let font_family = terminal_settings
                     .font_family
@@ -581,10 +780,20 @@ impl Element for TerminalElement {
                     .unwrap_or(settings.buffer_font.features);
 
                 let line_height = terminal_settings.line_height.value();
-                let font_size = terminal_settings.font_size;
+                let cell_width_multiplier = terminal_settings.cell_width;
+                let padding = terminal_settings.padding.edges();
+                let double_size_lines = terminal_settings.double_size_lines;
+                let dim_inactive = terminal_settings.dim_inactive;
+                let show_invisibles = terminal_settings.show_invisibles;
+                let ligatures = terminal_settings.ligatures;
+                let snap_to_device_pixels = terminal_settings.snap_to_device_pixels;
+                let background_image = terminal_settings.background_image.clone();
+                let exit_status = {
+                    let terminal = self.terminal.read(cx);
+                    terminal.exit_status().filter(|_| terminal.task().is_none())
+                };
 
-                let font_size =
-                    font_size.map_or(buffer_font_size, |size| theme::adjusted_font_size(size, cx));
+                let font_size = zoomed_font_size(settings_font_size(cx), self.font_size_delta);
 
                 let theme = cx.theme().clone();
 
@@ -620,35 +829,61 @@ impl Element for TerminalElement {
                 let text_system = cx.text_system();
                 let player_color = theme.players().local();
                 let match_color = theme.colors().search_match_background;
-                let gutter;
+                let grid_offset;
                 let dimensions = {
                     let rem_size = cx.rem_size();
                     let font_pixels = text_style.font_size.to_pixels(rem_size);
-                    let line_height = font_pixels * line_height.to_pixels(rem_size);
+                    let scale_factor = cx.scale_factor();
                     let font_id = cx.text_system().resolve_font(&text_style.font());
-
-                    let cell_width = text_system
+                    let advance = text_system
                         .advance(font_id, font_pixels, 'm')
                         .unwrap()
                         .width;
-                    gutter = cell_width;
-
-                    let mut size = bounds.size;
-                    size.width -= gutter;
-
-                    // https://github.com/zed-industries/zed/issues/2750
-                    // if the terminal is one column wide, rendering 🦀
-                    // causes alacritty to misbehave.
-                    if size.width < cell_width * 2.0 {
-                        size.width = cell_width * 2.0;
+                    let (mut line_height, mut cell_width) = cell_size(
+                        font_pixels,
+                        advance,
+                        line_height.to_pixels(rem_size).0,
+                        cell_width_multiplier,
+                    );
+                    if snap_to_device_pixels {
+                        line_height = snap_to_device_pixel(line_height, scale_factor);
+                        cell_width = snap_to_device_pixel(cell_width, scale_factor);
                     }
-
-                    TerminalSize::new(line_height, cell_width, size)
+                    let gutter = cell_width;
+                    grid_offset = Point::new(gutter + padding.left, padding.top);
+                    // Keep the last columns clear of the scrollbar once there's scrollback
+                    let scrollbar_width = if self.terminal.read(cx).last_content.scrollbar_shown {
+                        SCROLLBAR_WIDTH
+                    } else {
+                        px(0.)
+                    };
+                    let size = grid_size(
+                        bounds.size,
+                        gutter,
+                        &padding,
+                        scrollbar_width,
+                        line_height,
+                        cell_width,
+                    );
+
+                    // Passing the scale factor along means moving the window to a display
+                    // with a different one resizes the PTY, even though the terminal's size
+                    // in logical pixels stays the same.
+                    TerminalSize::new(line_height, cell_width, size).with_scale_factor(scale_factor)
                 };
 
-                let search_matches = self.terminal.read(cx).matches.clone();
+                let (search_matches, active_match, match_count_label) = {
+                    let terminal = self.terminal.read(cx);
+                    (
+                        terminal.matches.clone(),
+                        terminal.active_match(),
+                        terminal.match_count_label(),
+                    )
+                };
 
-                let background_color = theme.colors().terminal_background;
+                let background_color = TerminalSettings::get_global(cx)
+                    .colors
+                    .background(&terminal::terminal_theme(cx));
 
                 let last_hovered_word = self.terminal.update(cx, |terminal, cx| {
                     terminal.set_size(dimensions);
@@ -663,7 +898,7 @@ impl Element for TerminalElement {
                 });
 
                 let hyperlink_tooltip = last_hovered_word.clone().map(|hovered_word| {
-                    let offset = bounds.origin + Point::new(gutter, px(0.));
+                    let offset = bounds.origin + grid_offset;
                     let mut element = div()
                         .size_full()
                         .id("terminal-element")
@@ -673,20 +908,137 @@ impl Element for TerminalElement {
                     element
                 });
 
+                let background_image = background_image.map(|image| {
+                    let object_fit = match image.fit {
+                        BackgroundImageFit::Fill => ObjectFit::Fill,
+                        BackgroundImageFit::Contain => ObjectFit::Contain,
+                        BackgroundImageFit::Cover => ObjectFit::Cover,
+                        BackgroundImageFit::None => ObjectFit::None,
+                    };
+                    let mut element = img(image.path)
+                        .size_full()
+                        .object_fit(object_fit)
+                        .into_any_element();
+                    element.layout(bounds.origin, bounds.size.into(), cx);
+                    (element, image.opacity.clamp(0., 1.))
+                });
+
+                let exit_banner = exit_status.map(|exit_status| {
+                    let mut element = div()
+                        .size_full()
+                        .flex()
+                        .flex_col()
+                        .justify_end()
+                        .child(
+                            div()
+                                .w_full()
+                                .px_2()
+                                .bg(theme.colors().status_bar_background)
+                                .text_color(theme.colors().text_muted)
+                                .child(exit_banner_text(
+                                    exit_status,
+                                    TerminalSettings::get_global(cx).enter_after_exit,
+                                )),
+                        )
+                        .into_any_element();
+                    element.layout(bounds.origin, bounds.size.into(), cx);
+                    element
+                });
+
+                let (scrolled_up, unseen_output) = {
+                    let terminal = self.terminal.read(cx);
+                    (
+                        terminal.last_content.display_offset > 0,
+                        terminal.has_unseen_output(),
+                    )
+                };
+                let follow_output_button = scrolled_up.then(|| {
+                    let terminal = self.terminal.clone();
+                    let (label, color) = if unseen_output {
+                        ("New output below", theme.colors().text_accent)
+                    } else {
+                        ("Scroll to bottom", theme.colors().text_muted)
+                    };
+                    let mut element = div()
+                        .size_full()
+                        .flex()
+                        .justify_end()
+                        .items_end()
+                        .p_2()
+                        .child(
+                            div()
+                                .id("follow-output")
+                                .px_2()
+                                .rounded_md()
+                                .bg(theme.colors().element_background)
+                                .hover(|style| style.bg(theme.colors().element_hover))
+                                .cursor_pointer()
+                                .text_color(color)
+                                .child(label)
+                                .on_click(move |_, cx| {
+                                    terminal.update(cx, |terminal, cx| {
+                                        terminal.scroll_to_bottom();
+                                        cx.notify();
+                                    })
+                                }),
+                        )
+                        .into_any_element();
+                    element.layout(bounds.origin, bounds.size.into(), cx);
+                    element
+                });
+
+                let match_count = match_count_label.map(|label| {
+                    let mut element = div()
+                        .size_full()
+                        .flex()
+                        .justify_end()
+                        .items_start()
+                        .p_2()
+                        .child(
+                            div()
+                                .px_2()
+                                .rounded_md()
+                                .bg(theme.colors().element_background)
+                                .text_color(theme.colors().text_muted)
+                                .child(label),
+                        )
+                        .into_any_element();
+                    element.layout(bounds.origin, bounds.size.into(), cx);
+                    element
+                });
+
                 let TerminalContent {
                     cells,
                     mode,
                     display_offset,
+                    history_size,
                     cursor_char,
                     selection,
                     cursor,
+                    line_attributes,
+                    predicted_echo,
                     ..
                 } = &self.terminal.read(cx).last_content;
+                let cursor_visible = self.terminal.read(cx).cursor_visible();
+
+                let line_attributes = if double_size_lines {
+                    line_attributes
+                        .iter()
+                        .map(|(line, attribute)| (line + *display_offset as i32, *attribute))
+                        .collect()
+                } else {
+                    HashMap::default()
+                };
 
                 // searches, highlights to a single range representations
                 let mut relative_highlighted_ranges = Vec::new();
-                for search_match in search_matches {
-                    relative_highlighted_ranges.push((search_match, match_color))
+                for (index, search_match) in search_matches.into_iter().enumerate() {
+                    let color = if Some(index) == active_match {
+                        active_match_color(match_color)
+                    } else {
+                        match_color
+                    };
+                    relative_highlighted_ranges.push((search_match, color))
                 }
                 if let Some(selection) = selection {
                     relative_highlighted_ranges
@@ -695,22 +1047,76 @@ impl Element for TerminalElement {
 
                 // then have that representation be converted to the appropriate highlight data structure
 
-                let (cells, rects) = TerminalElement::layout_grid(
+                let cursor_columns = cells
+                    .iter()
+                    .find(|cell| cell.point == cursor.point)
+                    .map_or(1, cell_columns);
+
+                let (mut cells, rects) = TerminalElement::layout_grid(
                     cells,
                     &text_style,
                     &cx.text_system(),
                     last_hovered_word
                         .as_ref()
                         .map(|last_hovered_word| (link_style, &last_hovered_word.word_match)),
+                    &line_attributes,
+                    dimensions.num_columns(),
+                    show_invisibles,
+                    ligatures,
+                    cursor.point,
                     cx,
                 );
 
+                // Predicted echo is underlined, to tell it apart from the real output
+                let mut predicted_cursor = None;
+                if let Some((anchor, predicted_text)) = predicted_echo {
+                    let line = anchor.line.0 + *display_offset as i32;
+                    for (i, c) in predicted_text.chars().enumerate() {
+                        let text = c.to_string();
+                        let run = TextRun {
+                            len: text.len(),
+                            font: text_style.font(),
+                            color: theme.colors().text.opacity(0.7),
+                            background_color: None,
+                            underline: Some(UnderlineStyle {
+                                color: Some(theme.colors().text.opacity(0.7)),
+                                thickness: px(1.0),
+                                wavy: false,
+                            }),
+                            strikethrough: None,
+                        };
+                        let shaped = cx
+                            .text_system()
+                            .shape_line(
+                                text.into(),
+                                text_style.font_size.to_pixels(cx.rem_size()),
+                                &[run],
+                            )
+                            .unwrap();
+                        cells.push(LayoutCell::new(
+                            AlacPoint::new(line, (anchor.column.0 + i) as i32),
+                            shaped,
+                        ));
+                    }
+                    predicted_cursor = Some(AlacPoint::new(
+                        anchor.line,
+                        anchor.column + predicted_text.len(),
+                    ));
+                }
+
                 // Layout cursor. Rectangle is used for IME, so we should lay it out even
-                // if we don't end up showing it.
-                let cursor = if let AlacCursorShape::Hidden = cursor.shape {
+                // if we don't end up showing it. A cursor the program hid stays hidden, even
+                // where it would otherwise be drawn hollow.
+                let cursor = if !cursor_visible || cursor.shape == AlacCursorShape::Hidden {
                     None
                 } else {
-                    let cursor_point = DisplayCursor::from(cursor.point, *display_offset);
+                    let mut cursor_point = DisplayCursor::from(
+                        predicted_cursor.unwrap_or(cursor.point),
+                        *display_offset,
+                    );
+                    if line_attributes.contains_key(&cursor_point.line) {
+                        cursor_point.col *= LineAttribute::WIDTH_MULTIPLIER as usize;
+                    }
                     let cursor_text = {
                         let str_trxt = cursor_char.to_string();
                         let len = str_trxt.len();
@@ -721,7 +1127,7 @@ impl Element for TerminalElement {
                                 &[TextRun {
                                     len,
                                     font: text_style.font(),
-                                    color: theme.colors().terminal_background,
+                                    color: background_color,
                                     background_color: None,
                                     underline: Default::default(),
                                     strikethrough: None,
@@ -731,43 +1137,74 @@ impl Element for TerminalElement {
                     };
 
                     let focused = self.focused;
-                    TerminalElement::shape_cursor(cursor_point, dimensions, &cursor_text).map(
-                        move |(cursor_position, block_width)| {
-                            let (shape, text) = match cursor.shape {
-                                AlacCursorShape::Block if !focused => (CursorShape::Hollow, None),
-                                AlacCursorShape::Block => (CursorShape::Block, Some(cursor_text)),
-                                AlacCursorShape::Underline => (CursorShape::Underscore, None),
-                                AlacCursorShape::Beam => (CursorShape::Bar, None),
-                                AlacCursorShape::HollowBlock => (CursorShape::Hollow, None),
-                                //This case is handled in the if wrapping the whole cursor layout
-                                AlacCursorShape::Hidden => unreachable!(),
-                            };
-
-                            CursorLayout::new(
-                                cursor_position,
-                                block_width,
-                                dimensions.line_height,
-                                theme.players().local().cursor,
-                                shape,
-                                text,
-                            )
-                        },
+                    let window_active = cx.is_window_active();
+                    // The copy mode cursor is always a block, to tell it apart from the shell's
+                    let alac_shape = if mode.contains(TermMode::VI) {
+                        AlacCursorShape::Block
+                    } else {
+                        cursor.shape
+                    };
+                    TerminalElement::shape_cursor(
+                        cursor_point,
+                        dimensions,
+                        &cursor_text,
+                        cursor_columns,
                     )
+                    .map(move |(cursor_position, block_width)| {
+                        let shape = cursor_shape(alac_shape, focused, window_active);
+                        let text = (shape == CursorShape::Block).then_some(cursor_text);
+
+                        CursorLayout::new(
+                            cursor_position,
+                            block_width,
+                            dimensions.line_height,
+                            theme.players().local().cursor,
+                            shape,
+                            text,
+                        )
+                    })
                 };
 
+                let bell_flash = self.bell_flash.then(|| {
+                    let mut flash = theme.colors().text;
+                    flash.a *= BELL_FLASH_OPACITY;
+                    flash
+                });
+
+                let dim_overlay = dim_overlay(dim_inactive, self.focused, background_color);
+
+                let scrollbar_thumb = scrollbar_thumb_bounds(
+                    bounds,
+                    *display_offset,
+                    *history_size,
+                    dimensions.num_lines(),
+                )
+                .map(|thumb| (thumb, theme.colors().scrollbar_thumb_background));
+
+                let smooth_scroll_offset = self.terminal.read(cx).smooth_scroll_offset();
+
                 LayoutState {
                     hitbox,
                     cells,
                     cursor,
                     background_color,
+                    background_image,
+                    bell_flash,
+                    dim_overlay,
                     dimensions,
                     rects,
                     relative_highlighted_ranges,
                     mode: *mode,
                     display_offset: *display_offset,
                     hyperlink_tooltip,
-                    gutter,
+                    exit_banner,
+                    follow_output_button,
+                    match_count,
+                    scrollbar_thumb,
+                    grid_offset,
+                    smooth_scroll_offset,
                     last_hovered_word,
+                    line_attributes,
                 }
             })
     }
@@ -780,7 +1217,16 @@ impl Element for TerminalElement {
         cx: &mut ElementContext<'_>,
     ) {
         cx.paint_quad(fill(bounds, layout.background_color));
-        let origin = bounds.origin + Point::new(layout.gutter, px(0.));
+        if let Some((mut image, opacity)) = layout.background_image.take() {
+            image.paint(cx);
+            let mut overlay = layout.background_color;
+            overlay.a *= 1. - opacity;
+            cx.paint_quad(fill(bounds, overlay));
+        }
+        if let Some(flash) = layout.bell_flash {
+            cx.paint_quad(fill(bounds, flash));
+        }
+        let origin = bounds.origin + layout.grid_offset;
 
         let terminal_input_handler = TerminalInputHandler {
             terminal: self.terminal.clone(),
@@ -789,6 +1235,7 @@ impl Element for TerminalElement {
                 .as_ref()
                 .map(|cursor| cursor.bounding_rect(origin)),
             workspace: self.workspace.clone(),
+            broadcast_peers: self.broadcast_peers.clone(),
         };
 
         self.register_mouse_listeners(origin, layout.mode, &layout.hitbox, cx);
@@ -800,6 +1247,9 @@ impl Element for TerminalElement {
 
         let cursor = layout.cursor.take();
         let hyperlink_tooltip = layout.hyperlink_tooltip.take();
+        let exit_banner = layout.exit_banner.take();
+        let follow_output_button = layout.follow_output_button.take();
+        let match_count = layout.match_count.take();
         self.interactivity
             .paint(bounds, Some(&layout.hitbox), cx, |_, cx| {
                 cx.handle_input(&self.focus, terminal_input_handler);
@@ -820,35 +1270,62 @@ impl Element for TerminalElement {
                     }
                 });
 
-                for rect in &layout.rects {
-                    rect.paint(origin, &layout, cx);
-                }
+                // The grid moves with smooth scrolling, but stays inside the element
+                let grid_origin = origin + point(px(0.), layout.smooth_scroll_offset);
+                cx.with_content_mask(Some(ContentMask { bounds }), |cx| {
+                    for rect in &layout.rects {
+                        rect.paint(grid_origin, &layout, cx);
+                    }
 
-                for (relative_highlighted_range, color) in layout.relative_highlighted_ranges.iter()
-                {
-                    if let Some((start_y, highlighted_range_lines)) =
-                        to_highlighted_range_lines(relative_highlighted_range, &layout, origin)
+                    for (relative_highlighted_range, color) in
+                        layout.relative_highlighted_ranges.iter()
                     {
-                        let hr = HighlightedRange {
-                            start_y, //Need to change this
-                            line_height: layout.dimensions.line_height,
-                            lines: highlighted_range_lines,
-                            color: *color,
-                            //Copied from editor. TODO: move to theme or something
-                            corner_radius: 0.15 * layout.dimensions.line_height,
-                        };
-                        hr.paint(bounds, cx);
+                        if let Some((start_y, highlighted_range_lines)) = to_highlighted_range_lines(
+                            relative_highlighted_range,
+                            &layout,
+                            grid_origin,
+                        ) {
+                            let hr = HighlightedRange {
+                                start_y, //Need to change this
+                                line_height: layout.dimensions.line_height,
+                                lines: highlighted_range_lines,
+                                color: *color,
+                                //Copied from editor. TODO: move to theme or something
+                                corner_radius: 0.15 * layout.dimensions.line_height,
+                            };
+                            hr.paint(bounds, cx);
+                        }
+                    }
+
+                    for cell in &layout.cells {
+                        cell.paint(grid_origin, &layout, bounds, cx);
                     }
+
+                    if self.cursor_visible {
+                        if let Some(mut cursor) = cursor {
+                            cursor.paint(grid_origin, cx);
+                        }
+                    }
+                });
+
+                if let Some((thumb, color)) = layout.scrollbar_thumb {
+                    cx.paint_quad(fill(thumb, color));
                 }
 
-                for cell in &layout.cells {
-                    cell.paint(origin, &layout, bounds, cx);
+                if let Some(overlay) = layout.dim_overlay {
+                    cx.paint_quad(fill(bounds, overlay));
                 }
 
-                if self.cursor_visible {
-                    if let Some(mut cursor) = cursor {
-                        cursor.paint(origin, cx);
-                    }
+                if let Some(mut element) = exit_banner {
+                    element.paint(cx);
+                }
+
+                if let Some(mut element) = match_count {
+                    element.paint(cx);
+                }
+
+                if let Some(mut element) = follow_output_button {
+                    element.paint(cx);
                 }
 
                 if let Some(mut element) = hyperlink_tooltip {
@@ -870,6 +1347,7 @@ struct TerminalInputHandler {
     terminal: Model<Terminal>,
     workspace: WeakView<Workspace>,
     cursor_bounds: Option<Bounds<Pixels>>,
+    broadcast_peers: Vec<Model<Terminal>>,
 }
 
 impl InputHandler for TerminalInputHandler {
@@ -905,7 +1383,16 @@ impl InputHandler for TerminalInputHandler {
         text: &str,
         cx: &mut WindowContext,
     ) {
-        self.terminal.update(cx, |terminal, _| {
+        for terminal in self.broadcast_peers.iter() {
+            terminal.update(cx, |terminal, _| {
+                terminal.input(text.into());
+            });
+        }
+        self.terminal.update(cx, |terminal, cx| {
+            if TerminalSettings::get_global(cx).local_echo {
+                terminal.predict_echo(text);
+                cx.notify();
+            }
             terminal.input(text.into());
         });
 
@@ -937,6 +1424,26 @@ impl InputHandler for TerminalInputHandler {
     }
 }
 
+/// The text drawn for a cell: its character, followed by the zero width characters combined
+/// with it, like accents and the joiners between emoji.
+fn cell_text(cell: &IndexedCell) -> String {
+    let mut text = cell.c.to_string();
+    if let Some(zerowidth) = cell.zerowidth() {
+        text.extend(zerowidth);
+    }
+    text
+}
+
+/// How many of the grid's columns a cell takes up. Alacritty puts a spacer cell after each
+/// wide character, like most CJK characters and emoji, which is drawn over it.
+fn cell_columns(cell: &IndexedCell) -> usize {
+    if cell.flags.contains(Flags::WIDE_CHAR) {
+        2
+    } else {
+        1
+    }
+}
+
 fn is_blank(cell: &IndexedCell) -> bool {
     if cell.c != ' ' {
         return false;
@@ -960,6 +1467,191 @@ fn is_blank(cell: &IndexedCell) -> bool {
     return true;
 }
 
+/// The columns of a line's trailing spaces.
+///
+/// Spaces written to the terminal can't be told apart from cells that were never written to,
+/// so only the spaces before a line wrap or the cursor are known to be part of the output.
+fn trailing_whitespace(line: &[&IndexedCell], cursor_point: AlacPoint) -> Range<usize> {
+    let end = match line.last() {
+        Some(last) if last.flags.contains(Flags::WRAPLINE) => last.point.column.0 + 1,
+        Some(last) if last.point.line == cursor_point.line => cursor_point.column.0,
+        _ => return 0..0,
+    };
+    let start = line
+        .iter()
+        .filter(|cell| cell.point.column.0 < end)
+        .rev()
+        .find(|cell| cell.c != ' ' || cell.flags.contains(Flags::WIDE_CHAR_SPACER))
+        .map_or(0, |cell| cell.point.column.0 + 1);
+    start..end
+}
+
+/// The symbol drawn in place of an invisible character when `show_invisibles` is enabled.
+fn invisible_symbol(c: char, trailing: bool) -> Option<char> {
+    match c {
+        ' ' if trailing => Some('·'),
+        '\u{a0}' | '\u{202f}' => Some('⍽'),
+        '\u{2000}'..='\u{200a}' | '\u{3000}' => Some('·'),
+        // Control characters have matching symbols in the Control Pictures block
+        '\u{0}'..='\u{1f}' => char::from_u32(0x2400 + c as u32),
+        '\u{7f}' => Some('␡'),
+        _ => None,
+    }
+}
+
+/// The terminal's font size from the settings, before it's zoomed.
+pub(crate) fn settings_font_size(cx: &mut AppContext) -> Pixels {
+    let buffer_font_size = ThemeSettings::get_global(cx).buffer_font_size(cx);
+    TerminalSettings::get_global(cx)
+        .font_size
+        .map_or(buffer_font_size, |size| theme::adjusted_font_size(size, cx))
+}
+
+/// The font size of a terminal zoomed by `delta`, kept readable. Unzoomed terminals use the
+/// font size from the settings as is.
+pub(crate) fn zoomed_font_size(font_size: Pixels, delta: Pixels) -> Pixels {
+    if delta == px(0.) {
+        font_size
+    } else {
+        (font_size + delta).clamp(MIN_FONT_SIZE, MAX_FONT_SIZE)
+    }
+}
+
+fn exit_banner_text(exit_status: i32, enter_after_exit: EnterAfterExit) -> String {
+    let action = match enter_after_exit {
+        EnterAfterExit::Close => "close",
+        EnterAfterExit::Restart => "restart",
+    };
+    format!("Process exited with code {exit_status} — press enter to {action}")
+}
+
+/// How to draw the cursor. It's hollow while the terminal is unfocused if it's a block, and
+/// whatever its shape while the window is in the background, like in native terminals.
+fn cursor_shape(shape: AlacCursorShape, focused: bool, window_active: bool) -> CursorShape {
+    if !window_active {
+        return CursorShape::Hollow;
+    }
+    match shape {
+        AlacCursorShape::Block if !focused => CursorShape::Hollow,
+        AlacCursorShape::Block => CursorShape::Block,
+        AlacCursorShape::Underline => CursorShape::Underscore,
+        AlacCursorShape::Beam => CursorShape::Bar,
+        AlacCursorShape::HollowBlock => CursorShape::Hollow,
+        //This case is handled in the if wrapping the whole cursor layout
+        AlacCursorShape::Hidden => unreachable!(),
+    }
+}
+
+/// The active search match is drawn more opaque than the others, so it stands out.
+fn active_match_color(match_color: Hsla) -> Hsla {
+    Hsla {
+        a: (match_color.a * 2.).min(1.),
+        ..match_color
+    }
+}
+
+/// The size the grid has to fit in, once the gutter, the padding and the scrollbar are taken
+/// out of the element's `bounds`. At least two columns and a line are kept.
+fn grid_size(
+    bounds: Size<Pixels>,
+    gutter: Pixels,
+    padding: &Edges<Pixels>,
+    scrollbar_width: Pixels,
+    line_height: Pixels,
+    cell_width: Pixels,
+) -> Size<Pixels> {
+    let mut size = Size {
+        width: bounds.width - gutter - padding.left - padding.right - scrollbar_width,
+        height: bounds.height - padding.top - padding.bottom,
+    };
+
+    // https://github.com/zed-industries/zed/issues/2750
+    // if the terminal is one column wide, rendering 🦀
+    // causes alacritty to misbehave.
+    if size.width < cell_width * 2.0 {
+        size.width = cell_width * 2.0;
+    }
+    if size.height < line_height {
+        size.height = line_height;
+    }
+    size
+}
+
+/// The bounds of the scrollbar's thumb along the right edge of `bounds`, sized and placed
+/// to show which part of the history and screen is visible. `None` when not scrolled.
+fn scrollbar_thumb_bounds(
+    bounds: Bounds<Pixels>,
+    display_offset: usize,
+    history_size: usize,
+    screen_lines: usize,
+) -> Option<Bounds<Pixels>> {
+    if display_offset == 0 || screen_lines == 0 {
+        return None;
+    }
+    let total_lines = (history_size + screen_lines) as f32;
+    let height = (bounds.size.height * (screen_lines as f32 / total_lines)).max(MIN_THUMB_HEIGHT);
+    let top = (bounds.size.height - height)
+        * ((history_size.saturating_sub(display_offset)) as f32 / history_size.max(1) as f32);
+    Some(Bounds::new(
+        point(
+            bounds.origin.x + bounds.size.width - SCROLLBAR_WIDTH,
+            bounds.origin.y + top,
+        ),
+        size(SCROLLBAR_WIDTH, height),
+    ))
+}
+
+/// The color to paint over a terminal that isn't focused, when `dim_inactive` is on. It's the
+/// terminal's background, so the text fades into it in both light and dark themes.
+fn dim_overlay(dim_inactive: bool, focused: bool, background_color: Hsla) -> Option<Hsla> {
+    (dim_inactive && !focused).then(|| {
+        let mut overlay = background_color;
+        overlay.a *= DIM_OPACITY;
+        overlay
+    })
+}
+
+/// The height of the terminal's lines and the width of its cells, for a font of `font_pixels`
+/// whose characters are `advance` wide. The cell width multiplier is kept between 0.5 and 2.
+fn cell_size(
+    font_pixels: Pixels,
+    advance: Pixels,
+    line_height_multiplier: f32,
+    cell_width_multiplier: f32,
+) -> (Pixels, Pixels) {
+    let cell_width_multiplier = if cell_width_multiplier.is_finite() {
+        cell_width_multiplier.clamp(0.5, 2.)
+    } else {
+        1.
+    };
+    (
+        font_pixels * line_height_multiplier,
+        advance * cell_width_multiplier,
+    )
+}
+
+/// Rounds a length to a whole number of device pixels, at least one.
+fn snap_to_device_pixel(length: Pixels, scale_factor: f32) -> Pixels {
+    px((f32::from(length) * scale_factor).round().max(1.) / scale_factor)
+}
+
+/// The horizontal distance between the cells of a line with the given attribute.
+fn cell_advance(line_attribute: Option<LineAttribute>, cell_width: Pixels) -> Pixels {
+    match line_attribute {
+        Some(_) => cell_width * LineAttribute::WIDTH_MULTIPLIER,
+        None => cell_width,
+    }
+}
+
+/// How much horizontal space the visible cells of a line with the given attribute take up.
+fn rendered_line_width(line_attribute: Option<LineAttribute>, dimensions: &TerminalSize) -> Pixels {
+    let visible_columns = match line_attribute {
+        Some(_) => LineAttribute::visible_columns(dimensions.num_columns()),
+        None => dimensions.num_columns(),
+    };
+    cell_advance(line_attribute, dimensions.cell_width) * visible_columns as f32
+}
+
 fn to_highlighted_range_lines(
     range: &RangeInclusive<AlacPoint>,
     layout: &LayoutState,
@@ -1026,49 +1718,522 @@ fn to_highlighted_range_lines(
     Some((start_y, highlighted_range_lines))
 }
 
-/// Converts a 2, 8, or 24 bit color ANSI color to the GPUI equivalent.
-fn convert_color(fg: &terminal::alacritty_terminal::vte::ansi::Color, theme: &Theme) -> Hsla {
+/// The color bold text is drawn in: the bright variant of one of the 8 standard colors with
+/// `bold_is_bright`, and its own color otherwise.
+fn bold_color(
+    fg: terminal::alacritty_terminal::vte::ansi::Color,
+    bold: bool,
+    bold_is_bright: bool,
+) -> terminal::alacritty_terminal::vte::ansi::Color {
+    use terminal::alacritty_terminal::vte::ansi::Color;
+
+    if !bold || !bold_is_bright {
+        return fg;
+    }
+    match fg {
+        Color::Named(named) => Color::Named(match named {
+            NamedColor::Black => NamedColor::BrightBlack,
+            NamedColor::Red => NamedColor::BrightRed,
+            NamedColor::Green => NamedColor::BrightGreen,
+            NamedColor::Yellow => NamedColor::BrightYellow,
+            NamedColor::Blue => NamedColor::BrightBlue,
+            NamedColor::Magenta => NamedColor::BrightMagenta,
+            NamedColor::Cyan => NamedColor::BrightCyan,
+            NamedColor::White => NamedColor::BrightWhite,
+            named => named,
+        }),
+        Color::Indexed(index @ 0..=7) => Color::Indexed(index + 8),
+        fg => fg,
+    }
+}
+
+/// Converts a 2, 8, or 24 bit color ANSI color to the GPUI equivalent, using the colors from
+/// the settings over the theme's.
+fn convert_color(
+    fg: &terminal::alacritty_terminal::vte::ansi::Color,
+    theme: &Theme,
+    terminal_colors: &TerminalColors,
+) -> Hsla {
     let colors = theme.colors();
     match fg {
         // Named and theme defined colors
-        terminal::alacritty_terminal::vte::ansi::Color::Named(n) => match n {
-            NamedColor::Black => colors.terminal_ansi_black,
-            NamedColor::Red => colors.terminal_ansi_red,
-            NamedColor::Green => colors.terminal_ansi_green,
-            NamedColor::Yellow => colors.terminal_ansi_yellow,
-            NamedColor::Blue => colors.terminal_ansi_blue,
-            NamedColor::Magenta => colors.terminal_ansi_magenta,
-            NamedColor::Cyan => colors.terminal_ansi_cyan,
-            NamedColor::White => colors.terminal_ansi_white,
-            NamedColor::BrightBlack => colors.terminal_ansi_bright_black,
-            NamedColor::BrightRed => colors.terminal_ansi_bright_red,
-            NamedColor::BrightGreen => colors.terminal_ansi_bright_green,
-            NamedColor::BrightYellow => colors.terminal_ansi_bright_yellow,
-            NamedColor::BrightBlue => colors.terminal_ansi_bright_blue,
-            NamedColor::BrightMagenta => colors.terminal_ansi_bright_magenta,
-            NamedColor::BrightCyan => colors.terminal_ansi_bright_cyan,
-            NamedColor::BrightWhite => colors.terminal_ansi_bright_white,
-            NamedColor::Foreground => colors.text,
-            NamedColor::Background => colors.background,
-            NamedColor::Cursor => theme.players().local().cursor,
-            NamedColor::DimBlack => colors.terminal_ansi_dim_black,
-            NamedColor::DimRed => colors.terminal_ansi_dim_red,
-            NamedColor::DimGreen => colors.terminal_ansi_dim_green,
-            NamedColor::DimYellow => colors.terminal_ansi_dim_yellow,
-            NamedColor::DimBlue => colors.terminal_ansi_dim_blue,
-            NamedColor::DimMagenta => colors.terminal_ansi_dim_magenta,
-            NamedColor::DimCyan => colors.terminal_ansi_dim_cyan,
-            NamedColor::DimWhite => colors.terminal_ansi_dim_white,
-            NamedColor::BrightForeground => colors.terminal_bright_foreground,
-            NamedColor::DimForeground => colors.terminal_dim_foreground,
-        },
+        terminal::alacritty_terminal::vte::ansi::Color::Named(n) => {
+            if let Some(color) = terminal_colors.get(*n as usize) {
+                return color;
+            }
+            match n {
+                NamedColor::Black => colors.terminal_ansi_black,
+                NamedColor::Red => colors.terminal_ansi_red,
+                NamedColor::Green => colors.terminal_ansi_green,
+                NamedColor::Yellow => colors.terminal_ansi_yellow,
+                NamedColor::Blue => colors.terminal_ansi_blue,
+                NamedColor::Magenta => colors.terminal_ansi_magenta,
+                NamedColor::Cyan => colors.terminal_ansi_cyan,
+                NamedColor::White => colors.terminal_ansi_white,
+                NamedColor::BrightBlack => colors.terminal_ansi_bright_black,
+                NamedColor::BrightRed => colors.terminal_ansi_bright_red,
+                NamedColor::BrightGreen => colors.terminal_ansi_bright_green,
+                NamedColor::BrightYellow => colors.terminal_ansi_bright_yellow,
+                NamedColor::BrightBlue => colors.terminal_ansi_bright_blue,
+                NamedColor::BrightMagenta => colors.terminal_ansi_bright_magenta,
+                NamedColor::BrightCyan => colors.terminal_ansi_bright_cyan,
+                NamedColor::BrightWhite => colors.terminal_ansi_bright_white,
+                NamedColor::Foreground => colors.text,
+                NamedColor::Background => colors.background,
+                NamedColor::Cursor => theme.players().local().cursor,
+                NamedColor::DimBlack => colors.terminal_ansi_dim_black,
+                NamedColor::DimRed => colors.terminal_ansi_dim_red,
+                NamedColor::DimGreen => colors.terminal_ansi_dim_green,
+                NamedColor::DimYellow => colors.terminal_ansi_dim_yellow,
+                NamedColor::DimBlue => colors.terminal_ansi_dim_blue,
+                NamedColor::DimMagenta => colors.terminal_ansi_dim_magenta,
+                NamedColor::DimCyan => colors.terminal_ansi_dim_cyan,
+                NamedColor::DimWhite => colors.terminal_ansi_dim_white,
+                NamedColor::BrightForeground => colors.terminal_bright_foreground,
+                NamedColor::DimForeground => colors.terminal_dim_foreground,
+            }
+        }
         // 'True' colors
         terminal::alacritty_terminal::vte::ansi::Color::Spec(rgb) => {
             terminal::rgba_color(rgb.r, rgb.g, rgb.b)
         }
         // 8 bit, indexed colors
         terminal::alacritty_terminal::vte::ansi::Color::Indexed(i) => {
-            terminal::get_color_at_index(*i as usize, theme)
+            terminal_colors.color_at_index(*i as usize, theme)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        tests::{add_terminal_view, init_test},
+        TerminalView,
+    };
+    use gpui::{font, hsla, FontFeatures, TestAppContext, View, VisualTestContext};
+    use settings::SettingsStore;
+    use terminal::alacritty_terminal::{
+        index::{Column, Line},
+        term::cell::Cell,
+    };
+    use terminal::{term_with, terminal_settings::TerminalPadding};
+
+    fn line(text: &str, line: i32) -> Vec<IndexedCell> {
+        text.chars()
+            .enumerate()
+            .map(|(column, c)| IndexedCell {
+                point: AlacPoint::new(Line(line), Column(column)),
+                cell: Cell {
+                    c,
+                    ..Cell::default()
+                },
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_zoomed_font_size() {
+        assert_eq!(zoomed_font_size(px(15.), px(0.)), px(15.));
+        assert_eq!(zoomed_font_size(px(15.), px(2.)), px(17.));
+        assert_eq!(zoomed_font_size(px(15.), px(-20.)), MIN_FONT_SIZE);
+        assert_eq!(zoomed_font_size(px(15.), px(100.)), MAX_FONT_SIZE);
+        // Resetting goes back to the settings exactly, even if they're out of range
+        assert_eq!(zoomed_font_size(px(100.), px(0.)), px(100.));
+    }
+
+    #[test]
+    fn test_grid_size_leaves_out_padding() {
+        let bounds = size(px(200.), px(100.));
+        let (line_height, cell_width) = (px(10.), px(5.));
+        let unpadded = grid_size(
+            bounds,
+            cell_width,
+            &Edges::default(),
+            px(0.),
+            line_height,
+            cell_width,
+        );
+        let unpadded = TerminalSize::new(line_height, cell_width, unpadded);
+        assert_eq!((unpadded.num_columns(), unpadded.num_lines()), (39, 10));
+
+        let padding = TerminalPadding::Sides {
+            top: 10.,
+            right: 5.,
+            bottom: 20.,
+            left: 15.,
+        };
+        let padded = grid_size(
+            bounds,
+            cell_width,
+            &padding.edges(),
+            px(0.),
+            line_height,
+            cell_width,
+        );
+        assert_eq!(padded, size(px(175.), px(70.)));
+        let padded = TerminalSize::new(line_height, cell_width, padded);
+        assert_eq!((padded.num_columns(), padded.num_lines()), (35, 7));
+
+        // Padding bigger than the terminal still leaves room for some text
+        let padded = grid_size(
+            bounds,
+            cell_width,
+            &TerminalPadding::Uniform(100.).edges(),
+            px(0.),
+            line_height,
+            cell_width,
+        );
+        assert_eq!(padded, size(px(10.), px(10.)));
+    }
+
+    #[test]
+    fn test_grid_size_leaves_out_scrollbar() {
+        let bounds = size(px(200.), px(100.));
+        let (line_height, cell_width) = (px(10.), px(5.));
+        let columns = |scrollbar_width| {
+            let grid = grid_size(
+                bounds,
+                cell_width,
+                &Edges::default(),
+                scrollbar_width,
+                line_height,
+                cell_width,
+            );
+            TerminalSize::new(line_height, cell_width, grid).num_columns()
+        };
+        assert_eq!(columns(px(0.)), 39);
+        assert_eq!(columns(SCROLLBAR_WIDTH), 37);
+    }
+
+    #[test]
+    fn test_exit_banner_text() {
+        assert_eq!(
+            exit_banner_text(1, EnterAfterExit::Close),
+            "Process exited with code 1 — press enter to close"
+        );
+        assert_eq!(
+            exit_banner_text(2, EnterAfterExit::Restart),
+            "Process exited with code 2 — press enter to restart"
+        );
+    }
+
+    #[test]
+    fn test_scrollbar_thumb_bounds() {
+        let bounds = Bounds::new(point(px(0.), px(0.)), size(px(100.), px(100.)));
+        assert_eq!(scrollbar_thumb_bounds(bounds, 0, 30, 10), None);
+
+        let top = scrollbar_thumb_bounds(bounds, 30, 30, 10).unwrap();
+        assert_eq!(top.origin, point(px(94.), px(0.)));
+        assert_eq!(top.size, size(px(6.), px(25.)));
+
+        let middle = scrollbar_thumb_bounds(bounds, 15, 30, 10).unwrap();
+        assert_eq!(middle.origin.y, px(37.5));
+
+        // Long histories still get a thumb that can be grabbed
+        let long = scrollbar_thumb_bounds(bounds, 1, 10_000, 10).unwrap();
+        assert_eq!(long.size.height, MIN_THUMB_HEIGHT);
+    }
+
+    #[test]
+    fn test_trailing_whitespace() {
+        let prompt = line("$ echo   ", 0);
+        let prompt = prompt.iter().collect::<Vec<_>>();
+        let cursor = AlacPoint::new(Line(0), Column(9));
+        assert_eq!(trailing_whitespace(&prompt, cursor), 6..9);
+        // Blank cells after the cursor were never written to
+        let cursor = AlacPoint::new(Line(0), Column(6));
+        assert_eq!(trailing_whitespace(&prompt, cursor), 6..6);
+
+        let mut wrapped = line("output  ", 1);
+        wrapped
+            .last_mut()
+            .unwrap()
+            .cell
+            .flags
+            .insert(Flags::WRAPLINE);
+        let wrapped = wrapped.iter().collect::<Vec<_>>();
+        assert_eq!(trailing_whitespace(&wrapped, cursor), 6..8);
+
+        let output = line("output  ", 2);
+        let output = output.iter().collect::<Vec<_>>();
+        assert_eq!(trailing_whitespace(&output, cursor), 0..0);
+    }
+
+    #[test]
+    fn test_invisible_symbols() {
+        assert_eq!(invisible_symbol(' ', false), None);
+        assert_eq!(invisible_symbol(' ', true), Some('·'));
+        assert_eq!(invisible_symbol('a', true), None);
+        assert_eq!(invisible_symbol('\u{a0}', false), Some('⍽'));
+        assert_eq!(invisible_symbol('\t', false), Some('␉'));
+        assert_eq!(invisible_symbol('\u{1b}', false), Some('␛'));
+        assert_eq!(invisible_symbol('\u{7f}', false), Some('␡'));
+    }
+
+    #[test]
+    fn test_double_size_line_width() {
+        let dimensions = TerminalSize::new(px(10.), px(5.), size(px(405.), px(100.)));
+        assert_eq!(dimensions.num_columns(), 81);
+
+        assert_eq!(rendered_line_width(None, &dimensions), px(405.));
+        for attribute in [
+            LineAttribute::DoubleWidth,
+            LineAttribute::DoubleHeightTop,
+            LineAttribute::DoubleHeightBottom,
+        ] {
+            assert_eq!(
+                cell_advance(Some(attribute), dimensions.cell_width),
+                px(10.)
+            );
+            assert_eq!(rendered_line_width(Some(attribute), &dimensions), px(400.));
         }
     }
+
+    fn layout_cells(output: &str) -> Vec<IndexedCell> {
+        let term = term_with(output.as_bytes(), 20, 5);
+        term.grid()
+            .display_iter()
+            .filter(|cell| cell.point.line.0 == 0)
+            .map(|cell| IndexedCell {
+                point: cell.point,
+                cell: cell.cell.clone(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_wide_and_zero_width_characters() {
+        let cell_width = px(5.);
+
+        // Each CJK character takes up two cells, the second being a spacer
+        let cells = layout_cells("漢字x");
+        let drawn = cells
+            .iter()
+            .filter(|cell| !cell.flags.contains(Flags::WIDE_CHAR_SPACER))
+            .take(3)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            drawn
+                .iter()
+                .map(|cell| (cell.point.column.0, cell_text(cell), cell_columns(cell)))
+                .collect::<Vec<_>>(),
+            [
+                (0, "漢".to_string(), 2),
+                (2, "字".to_string(), 2),
+                (4, "x".to_string(), 1)
+            ]
+        );
+        let width = drawn
+            .iter()
+            .map(|cell| cell_width * cell_columns(cell) as f32)
+            .fold(px(0.), |total, width| total + width);
+        assert_eq!(width, px(25.));
+
+        // Combining characters share the cell of the character before them
+        let cells = layout_cells("e\u{301}!");
+        assert_eq!(cell_text(&cells[0]), "e\u{301}");
+        assert_eq!(cell_columns(&cells[0]), 1);
+        assert_eq!(cells[1].c, '!');
+    }
+
+    fn cell_run(column: usize, text: &str, color: Hsla) -> CellRun {
+        CellRun {
+            column,
+            columns: text.chars().count(),
+            text: text.to_string(),
+            style: TextRun {
+                len: text.len(),
+                font: font("Zed Mono"),
+                color,
+                background_color: None,
+                underline: None,
+                strikethrough: None,
+            },
+            joinable: true,
+        }
+    }
+
+    #[test]
+    fn test_join_ligature_runs() {
+        let white = hsla(0., 0., 1., 1.);
+        let red = hsla(0., 1., 0.5, 1.);
+        let wide = CellRun {
+            columns: 2,
+            joinable: false,
+            ..cell_run(7, "漢", white)
+        };
+        let runs = vec![
+            cell_run(0, "=", white),
+            cell_run(1, ">", white),
+            // A blank cell in between
+            cell_run(3, "!", white),
+            cell_run(4, "=", white),
+            cell_run(5, "=", red),
+            cell_run(6, "-", white),
+            wide,
+            cell_run(9, ">", white),
+        ];
+        let joined = join_ligature_runs(runs);
+        assert_eq!(
+            joined
+                .iter()
+                .map(|run| (run.column, run.columns, run.text.as_str(), run.style.len))
+                .collect::<Vec<_>>(),
+            [
+                (0, 2, "=>", 2),
+                (3, 2, "!=", 2),
+                (5, 1, "=", 1),
+                (6, 1, "-", 1),
+                (7, 2, "漢", 3),
+                (9, 1, ">", 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cell_style_keeps_font_features() {
+        let features: FontFeatures =
+            serde_json::from_value(serde_json::json!({ "calt": true })).unwrap();
+        let text_style = TextStyle {
+            font_features: features.clone(),
+            ..TextStyle::default()
+        };
+        let cell = &layout_cells("=>")[0];
+        let style = TerminalElement::cell_style(
+            cell,
+            cell.fg,
+            &Theme::default(),
+            &TerminalColors::default(),
+            &text_style,
+            None,
+        );
+        assert_eq!(style.font.features, features);
+    }
+
+    #[test]
+    fn test_bold_is_bright() {
+        use terminal::alacritty_terminal::vte::ansi::{Color, Rgb};
+
+        let red = Color::Named(NamedColor::Red);
+        assert_eq!(
+            bold_color(red, true, true),
+            Color::Named(NamedColor::BrightRed)
+        );
+        assert_eq!(bold_color(red, true, false), red);
+        assert_eq!(bold_color(red, false, true), red);
+        assert_eq!(
+            bold_color(Color::Indexed(4), true, true),
+            Color::Indexed(12)
+        );
+        assert_eq!(
+            bold_color(Color::Indexed(4), true, false),
+            Color::Indexed(4)
+        );
+
+        // Only the 8 standard colors brighten
+        for color in [
+            Color::Named(NamedColor::BrightRed),
+            Color::Named(NamedColor::Foreground),
+            Color::Indexed(12),
+            Color::Indexed(100),
+            Color::Spec(Rgb { r: 1, g: 2, b: 3 }),
+        ] {
+            assert_eq!(bold_color(color, true, true), color);
+        }
+
+        let theme = Theme::default();
+        let terminal_colors = TerminalColors::default();
+        assert_eq!(
+            convert_color(&bold_color(red, true, true), &theme, &terminal_colors),
+            theme.colors().terminal_ansi_bright_red
+        );
+        assert_eq!(
+            convert_color(&bold_color(red, true, false), &theme, &terminal_colors),
+            theme.colors().terminal_ansi_red
+        );
+    }
+
+    #[gpui::test]
+    async fn test_cursor_hollow_while_window_inactive(cx: &mut TestAppContext) {
+        let (project, workspace) = init_test(cx).await;
+        let view = add_terminal_view(&project, &workspace, cx);
+        let cx = &mut VisualTestContext::from_window(cx.windows()[0], cx);
+        let cursor_shape =
+            |cx: &mut VisualTestContext| lay_out(&view, cx).cursor.map(|cursor| cursor.shape());
+        cx.focus_view(&view);
+        let shape = cursor_shape(cx);
+        assert!(shape.is_some_and(|shape| shape != CursorShape::Hollow));
+
+        // Blurring the window and focusing it again
+        cx.deactivate_window();
+        assert_eq!(cursor_shape(cx), Some(CursorShape::Hollow));
+        cx.update(|cx| cx.activate_window());
+        cx.run_until_parked();
+        assert_eq!(cursor_shape(cx), shape);
+    }
+
+    /// Lays out the element that draws `view`'s terminal, like drawing its window does.
+    fn lay_out(view: &View<TerminalView>, cx: &mut VisualTestContext) -> LayoutState {
+        view.update(cx, |view, cx| {
+            let mut element = view.terminal_element(cx);
+            cx.with_element_context(|cx| {
+                let (_, mut before_layout) = element.before_layout(cx);
+                let bounds = Bounds::new(point(px(0.), px(0.)), size(px(400.), px(200.)));
+                element.after_layout(bounds, &mut before_layout, cx)
+            })
+        })
+    }
+
+    #[gpui::test]
+    async fn test_dim_follows_focus(cx: &mut TestAppContext) {
+        let (project, workspace) = init_test(cx).await;
+        let view = add_terminal_view(&project, &workspace, cx);
+        let cx = &mut VisualTestContext::from_window(cx.windows()[0], cx);
+        let dimmed = |cx: &mut VisualTestContext| lay_out(&view, cx).dim_overlay.is_some();
+
+        // Never dimmed with the setting off
+        cx.update(|cx| cx.blur());
+        assert!(!dimmed(cx));
+
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<TerminalSettings>(cx, |settings| {
+                settings.dim_inactive = Some(true);
+            });
+        });
+        assert!(dimmed(cx));
+        cx.focus_view(&view);
+        assert!(!dimmed(cx));
+        cx.update(|cx| cx.blur());
+        assert!(dimmed(cx));
+    }
+
+    #[test]
+    fn test_cell_size_multipliers() {
+        let bounds = size(px(400.), px(200.));
+        let (line_height, cell_width) = cell_size(px(10.), px(6.), 1., 1.);
+        assert_eq!((line_height, cell_width), (px(10.), px(6.)));
+        assert_eq!(
+            TerminalSize::new(line_height, cell_width, bounds).num_lines(),
+            20
+        );
+
+        let (line_height, cell_width) = cell_size(px(10.), px(6.), 2., 1.);
+        assert_eq!(
+            TerminalSize::new(line_height, cell_width, bounds).num_lines(),
+            10
+        );
+
+        assert_eq!(cell_size(px(10.), px(6.), 1., 1.5).1, px(9.));
+        assert_eq!(cell_size(px(10.), px(6.), 1., 0.).1, px(3.));
+        assert_eq!(cell_size(px(10.), px(6.), 1., 10.).1, px(12.));
+        assert_eq!(cell_size(px(10.), px(6.), 1., f32::NAN).1, px(6.));
+    }
+
+    #[test]
+    fn test_snap_to_device_pixel() {
+        assert_eq!(snap_to_device_pixel(px(8.4), 1.), px(8.));
+        assert_eq!(snap_to_device_pixel(px(8.4), 2.), px(8.5));
+        assert_eq!(snap_to_device_pixel(px(0.1), 1.), px(1.));
+    }
 }
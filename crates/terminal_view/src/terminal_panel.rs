@@ -1,6 +1,6 @@
 use std::{ops::ControlFlow, path::PathBuf, sync::Arc};
 
-use crate::TerminalView;
+use crate::{OpenTerminalSettings, TerminalView};
 use collections::{HashMap, HashSet};
 use db::kvp::KEY_VALUE_STORE;
 use futures::future::join_all;
@@ -11,12 +11,15 @@ use gpui::{
 };
 use itertools::Itertools;
 use project::{Fs, ProjectEntryId};
-use search::{buffer_search::DivRegistrar, BufferSearchBar};
+use search::{
+    buffer_search::{Dismiss, DivRegistrar},
+    BufferSearchBar, SelectNextMatch,
+};
 use serde::{Deserialize, Serialize};
 use settings::Settings;
 use task::{RevealStrategy, SpawnInTerminal, TaskId};
 use terminal::{
-    terminal_settings::{Shell, TerminalDockPosition, TerminalSettings},
+    terminal_settings::{SearchEnterBehavior, Shell, TerminalDockPosition, TerminalSettings},
     SpawnTask,
 };
 use ui::{h_flex, ButtonCommon, Clickable, IconButton, IconSize, Selectable, Tooltip};
@@ -33,7 +36,7 @@ use anyhow::Result;
 
 const TERMINAL_PANEL_KEY: &str = "TerminalPanel";
 
-actions!(terminal_panel, [ToggleFocus]);
+actions!(terminal_panel, [ToggleFocus, TogglePinnedHeight]);
 
 pub fn init(cx: &mut AppContext) {
     cx.observe_new_views(
@@ -43,6 +46,11 @@ pub fn init(cx: &mut AppContext) {
             workspace.register_action(|workspace, _: &ToggleFocus, cx| {
                 workspace.toggle_panel_focus::<TerminalPanel>(cx);
             });
+            workspace.register_action(|workspace, _: &TogglePinnedHeight, cx| {
+                if let Some(panel) = workspace.panel::<TerminalPanel>(cx) {
+                    panel.update(cx, |panel, cx| panel.toggle_pinned_height(cx));
+                }
+            });
         },
     )
     .detach();
@@ -54,6 +62,8 @@ pub struct TerminalPanel {
     workspace: WeakView<Workspace>,
     width: Option<Pixels>,
     height: Option<Pixels>,
+    /// Whether the panel's height only changes when its edge is dragged
+    height_pinned: bool,
     pending_serialization: Task<Option<()>>,
     pending_terminals_to_add: usize,
     _subscriptions: Vec<Subscription>,
@@ -89,6 +99,16 @@ impl TerminalPanel {
                             })
                             .tooltip(|cx| Tooltip::text("New Terminal", cx)),
                     )
+                    .child(
+                        IconButton::new("terminal_settings", IconName::Settings)
+                            .icon_size(IconSize::Small)
+                            .on_click(|_, cx| {
+                                cx.dispatch_action(OpenTerminalSettings.boxed_clone())
+                            })
+                            .tooltip(|cx| {
+                                Tooltip::for_action("Terminal Settings", &OpenTerminalSettings, cx)
+                            }),
+                    )
                     .child({
                         let zoomed = pane.is_zoomed();
                         IconButton::new("toggle_zoom", IconName::Maximize)
@@ -166,6 +186,7 @@ impl TerminalPanel {
             pending_serialization: Task::ready(None),
             width: None,
             height: None,
+            height_pinned: false,
             pending_terminals_to_add: 0,
             deferred_tasks: HashMap::default(),
             _subscriptions: subscriptions,
@@ -195,6 +216,7 @@ impl TerminalPanel {
                     cx.notify();
                     panel.height = serialized_panel.height.map(|h| h.round());
                     panel.width = serialized_panel.width.map(|w| w.round());
+                    panel.height_pinned = serialized_panel.height_pinned;
                     panel.pane.update(cx, |_, cx| {
                         serialized_panel
                             .items
@@ -477,7 +499,7 @@ impl TerminalPanel {
                 } else {
                     let working_directory_strategy =
                         TerminalSettings::get_global(cx).working_directory.clone();
-                    crate::get_working_directory(workspace, cx, working_directory_strategy)
+                    crate::working_directory_or_notify(workspace, cx, working_directory_strategy)
                 };
 
                 let window = cx.window_handle();
@@ -509,6 +531,39 @@ impl TerminalPanel {
         .detach_and_log_err(cx);
     }
 
+    /// Closes the search bar instead of selecting the next match when Enter is pressed in it,
+    /// if `search_enter_behavior` says so. The current match stays selected in the terminal.
+    fn accept_search_match(&mut self, _: &SelectNextMatch, cx: &mut ViewContext<Self>) {
+        if TerminalSettings::get_global(cx).search_enter_behavior != SearchEnterBehavior::Accept {
+            return;
+        }
+        let Some(search_bar) = self
+            .pane
+            .read(cx)
+            .toolbar()
+            .read(cx)
+            .item_of_type::<BufferSearchBar>()
+        else {
+            return;
+        };
+        // With the terminal itself focused, this came from a "next match" binding, not Enter.
+        if !search_bar.focus_handle(cx).contains_focused(cx) {
+            return;
+        }
+        search_bar.update(cx, |search_bar, cx| search_bar.dismiss(&Dismiss, cx));
+        cx.stop_propagation();
+    }
+
+    /// Pins the panel's height, so it's no longer reset to `default_height`, or unpins it.
+    fn toggle_pinned_height(&mut self, cx: &mut ViewContext<Self>) {
+        self.height_pinned = !self.height_pinned;
+        if self.height_pinned && self.height.is_none() {
+            self.height = Some(TerminalSettings::get_global(cx).default_height);
+        }
+        self.serialize(cx);
+        cx.notify();
+    }
+
     fn serialize(&mut self, cx: &mut ViewContext<Self>) {
         let mut items_to_serialize = HashSet::default();
         let items = self
@@ -534,6 +589,7 @@ impl TerminalPanel {
             .filter(|active_id| items_to_serialize.contains(active_id));
         let height = self.height;
         let width = self.width;
+        let height_pinned = self.height_pinned;
         self.pending_serialization = cx.background_executor().spawn(
             async move {
                 KEY_VALUE_STORE
@@ -544,6 +600,7 @@ impl TerminalPanel {
                             active_item_id,
                             height,
                             width,
+                            height_pinned,
                         })?,
                     )
                     .await?;
@@ -622,8 +679,8 @@ fn add_paths_to_terminal(pane: &mut Pane, paths: &[PathBuf], cx: &mut ViewContex
         let mut new_text = paths.iter().map(|path| format!(" {path:?}")).join("");
         new_text.push(' ');
         terminal_view.update(cx, |terminal_view, cx| {
-            terminal_view.terminal().update(cx, |terminal, _| {
-                terminal.paste(&new_text);
+            terminal_view.terminal().update(cx, |terminal, cx| {
+                terminal.paste(&new_text, cx);
             });
         });
     }
@@ -645,7 +702,11 @@ impl Render for TerminalPanel {
             cx,
         );
         BufferSearchBar::register(&mut registrar);
-        registrar.into_div().size_full().child(self.pane.clone())
+        registrar
+            .into_div()
+            .size_full()
+            .capture_action(cx.listener(Self::accept_search_match))
+            .child(self.pane.clone())
     }
 }
 
@@ -692,6 +753,8 @@ impl Panel for TerminalPanel {
     fn set_size(&mut self, size: Option<Pixels>, cx: &mut ViewContext<Self>) {
         match self.position(cx) {
             DockPosition::Left | DockPosition::Right => self.width = size,
+            // A pinned height can be dragged to a new one, but isn't reset.
+            DockPosition::Bottom if size.is_none() && self.height_pinned => return,
             DockPosition::Bottom => self.height = size,
         }
         self.serialize(cx);
@@ -744,4 +807,6 @@ struct SerializedTerminalPanel {
     active_item_id: Option<u64>,
     width: Option<Pixels>,
     height: Option<Pixels>,
+    #[serde(default)]
+    height_pinned: bool,
 }
@@ -1,37 +1,51 @@
 mod persistence;
+mod rename_terminal;
+mod split;
 pub mod terminal_element;
 pub mod terminal_panel;
 
 use collections::HashSet;
 use editor::{scroll::Autoscroll, Editor};
-use futures::{stream::FuturesUnordered, StreamExt};
+use futures::{channel::oneshot, stream::FuturesUnordered, StreamExt};
 use gpui::{
-    anchored, deferred, div, impl_actions, AnyElement, AppContext, DismissEvent, EventEmitter,
-    FocusHandle, FocusableView, KeyContext, KeyDownEvent, Keystroke, Model, MouseButton,
-    MouseDownEvent, Pixels, Render, Styled, Subscription, Task, View, VisualContext, WeakView,
+    actions, anchored, deferred, div, impl_actions, px, Action, AnyElement, AppContext,
+    ClipboardItem, DismissEvent, EventEmitter, FocusHandle, FocusableView, KeyContext,
+    KeyDownEvent, Keystroke, Model, MouseButton, MouseDownEvent, Pixels, PromptLevel, Render,
+    Styled, Subscription, Task, View, VisualContext, WeakView,
 };
 use language::Bias;
 use persistence::TERMINAL_DB;
-use project::{search::SearchQuery, Fs, LocalWorktree, Metadata, Project};
+use project::{search::SearchQuery, Fs, LocalWorktree, Metadata, Project, ProjectEntryId};
+use rename_terminal::RenameTerminalModal;
 use settings::SettingsStore;
+use split::{SplitAxis, SplitDirection, SplitNode};
 use terminal::{
     alacritty_terminal::{
-        index::Point,
+        index::{Direction as AlacDirection, Point},
         term::{search::RegexSearch, TermMode},
     },
+    existing_directory_or_home,
+    mappings::keys::unescape_keys,
     terminal_settings::{TerminalBlink, TerminalSettings, WorkingDirectory},
-    Clear, Copy, Event, MaybeNavigationTarget, Paste, ShowCharacterPalette, TaskStatus, Terminal,
+    Clear, ClearScrollback, Copy, CopyAsAnsi, CopyAsHtml, CopyFormat, CopyLastCommandOutput, Event,
+    JumpToNextPrompt, JumpToPreviousPrompt, MaybeNavigationTarget, Paste, PathLikeTarget,
+    ScrollLineDown, ScrollLineUp, ScrollPageDown, ScrollPageUp, ScrollToBottom, ScrollToTop,
+    ShowCharacterPalette, Signal, TaskStatus, Terminal, ToggleCopyMode,
+};
+use terminal_element::{settings_font_size, zoomed_font_size, TerminalElement};
+use terminal_panel::TerminalPanel;
+use ui::{h_flex, prelude::*, ContextMenu, Icon, IconName, Indicator, Label};
+use util::{
+    paths::{self, PathLikeWithPosition},
+    ResultExt,
 };
-use terminal_element::TerminalElement;
-use ui::{h_flex, prelude::*, ContextMenu, Icon, IconName, Label};
-use util::{paths::PathLikeWithPosition, ResultExt};
 use workspace::{
     item::{BreadcrumbText, Item, ItemEvent, TabContentParams},
-    notifications::NotifyResultExt,
+    notifications::{NotificationId, NotifyResultExt},
     register_deserializable_item,
     searchable::{SearchEvent, SearchOptions, SearchableItem, SearchableItemHandle},
-    CloseActiveItem, NewCenterTerminal, OpenVisible, Pane, ToolbarItemLocation, Workspace,
-    WorkspaceId,
+    ClipboardRing, CloseActiveItem, NewCenterTerminal, OpenVisible, Pane, Toast,
+    ToolbarItemLocation, Workspace, WorkspaceId,
 };
 
 use anyhow::Context;
@@ -41,17 +55,32 @@ use settings::Settings;
 use smol::Timer;
 
 use std::{
+    env, iter,
     ops::RangeInclusive,
     path::{Path, PathBuf},
     sync::Arc,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 const REGEX_SPECIAL_CHARS: &[char] = &[
     '\\', '.', '*', '+', '?', '|', '(', ')', '[', ']', '{', '}', '^', '$',
 ];
 
-const CURSOR_BLINK_INTERVAL: Duration = Duration::from_millis(500);
+/// The shortest `cursor_blink_interval_ms` is taken to be, so the cursor doesn't flicker.
+const MIN_CURSOR_BLINK_INTERVAL: Duration = Duration::from_millis(50);
+const BELL_FLASH_DURATION: Duration = Duration::from_millis(150);
+/// How long the pointer has to stay over a terminal for `focus_follows_mouse` to focus it.
+const FOCUS_FOLLOWS_MOUSE_DELAY: Duration = Duration::from_millis(250);
+
+struct TerminalNotification;
+
+/// A pane of a terminal tab that's been split.
+#[derive(Clone, PartialEq)]
+enum SplitPane {
+    /// The terminal of the view that is the tab.
+    This,
+    Other(View<TerminalView>),
+}
 
 ///Event to transmit the scroll from the element to the view
 #[derive(Clone, Debug, PartialEq)]
@@ -63,7 +92,81 @@ pub struct SendText(String);
 #[derive(Clone, Debug, Default, Deserialize, PartialEq)]
 pub struct SendKeystroke(String);
 
-impl_actions!(terminal, [SendText, SendKeystroke]);
+/// Writes bytes to the terminal, given as a string with escapes like `\x1b` and `\r`, for
+/// binding keys to sequences the terminal doesn't send for any key.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+pub struct SendKeys(String);
+
+/// Saves the scrollback and the screen to a file, as plain text or with their colors as ANSI
+/// escapes.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+pub struct SaveOutput {
+    #[serde(default)]
+    pub preserve_ansi: bool,
+}
+
+/// Opens a new terminal and runs a command in it, so that commands like a build can be bound to
+/// keys.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct SpawnTerminalCommand {
+    pub command: String,
+    /// Where to run the command, which is shell expanded. The `working_directory` setting
+    /// decides where when it's left out.
+    #[serde(default)]
+    pub working_directory: Option<String>,
+    /// Whether to focus the new terminal, rather than open it in the background.
+    #[serde(default = "default_reveal")]
+    pub reveal: bool,
+}
+
+fn default_reveal() -> bool {
+    true
+}
+
+impl_actions!(
+    terminal,
+    [
+        SendText,
+        SendKeystroke,
+        SendKeys,
+        SaveOutput,
+        SpawnTerminalCommand
+    ]
+);
+
+actions!(
+    terminal,
+    [
+        OpenTerminalSettings,
+        ToggleBroadcastInput,
+        GoToFirstErrorInOutput,
+        GoToLastErrorInOutput,
+        CopyCommandInvocation,
+        FindNext,
+        FindPrev,
+        Restart,
+        IncreaseFontSize,
+        DecreaseFontSize,
+        ResetFontSize,
+        SendSigInt,
+        SendSigTerm,
+        SendSigKill,
+        SendSigHup,
+        RenameTerminal,
+        SplitRight,
+        SplitDown,
+        FocusPaneLeft,
+        FocusPaneRight,
+        FocusPaneUp,
+        FocusPaneDown,
+        DeployInCurrentDir,
+        RevealInProjectPanel,
+        FocusTerminal,
+        ClearLine,
+        ClearToEnd,
+        PastePrevious
+    ]
+);
 
 pub fn init(cx: &mut AppContext) {
     terminal_panel::init(cx);
@@ -73,10 +176,241 @@ pub fn init(cx: &mut AppContext) {
 
     cx.observe_new_views(|workspace: &mut Workspace, _| {
         workspace.register_action(TerminalView::deploy);
+        workspace.register_action(focus_terminal);
+        workspace.register_action(open_terminal_settings);
+        workspace.register_action(spawn_terminal_command);
     })
     .detach();
 }
 
+/// What [`FocusTerminal`] does.
+#[derive(Debug, PartialEq, Eq)]
+enum FocusTerminalTarget {
+    /// A terminal is focused already, so focus goes back to where it was before.
+    ReturnFocus,
+    /// Focus the terminal at this index, the one that was focused most recently.
+    Terminal(usize),
+    /// There are no terminals to focus, so a new one is opened.
+    Deploy,
+}
+
+/// Decides what [`FocusTerminal`] does, given when each of the workspace's terminals was
+/// last focused.
+fn focus_terminal_target(
+    terminal_focused: bool,
+    last_focused: &[Option<Instant>],
+) -> FocusTerminalTarget {
+    if terminal_focused {
+        return FocusTerminalTarget::ReturnFocus;
+    }
+    last_focused
+        .iter()
+        .enumerate()
+        .max_by_key(|(_, last_focused)| **last_focused)
+        .map_or(FocusTerminalTarget::Deploy, |(ix, _)| {
+            FocusTerminalTarget::Terminal(ix)
+        })
+}
+
+fn cursor_blink_interval(interval_ms: u64) -> Duration {
+    Duration::from_millis(interval_ms).max(MIN_CURSOR_BLINK_INTERVAL)
+}
+
+/// What a terminal's tab shows next to its title to draw attention to it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum TabIndicator {
+    /// The shell exited, and the terminal is being kept open.
+    Exited,
+    /// A program rang the bell.
+    Bell,
+    /// Output arrived while the terminal wasn't focused.
+    Activity,
+}
+
+/// The indicator a tab shows, if any, with the shell exiting taking precedence over a bell, and a
+/// bell over plain activity.
+fn tab_indicator(exited: bool, has_bell: bool, has_activity: bool) -> Option<TabIndicator> {
+    if exited {
+        Some(TabIndicator::Exited)
+    } else if has_bell {
+        Some(TabIndicator::Bell)
+    } else if has_activity {
+        Some(TabIndicator::Activity)
+    } else {
+        None
+    }
+}
+
+/// Whether a terminal has activity to show after new output, which it only does while it isn't
+/// focused.
+fn activity_after_output(has_activity: bool, focused: bool) -> bool {
+    has_activity || !focused
+}
+
+/// The name of the terminal's directory, shown next to its title once `detail` is above 0,
+/// which is when other tabs have the same title.
+fn tab_directory_name(directory: Option<&Path>, detail: usize) -> Option<String> {
+    if detail == 0 {
+        return None;
+    }
+    let directory = directory?;
+    let name = directory.file_name().unwrap_or(directory.as_os_str());
+    Some(name.to_string_lossy().into_owned())
+}
+
+/// What the tab shows at the given level of `detail`, used to find tabs that look the same.
+fn tab_description(title: &str, directory: Option<&Path>, detail: usize) -> String {
+    match tab_directory_name(directory, detail) {
+        Some(name) => format!("{title} {name}"),
+        None => title.to_string(),
+    }
+}
+
+/// How long to wait before focusing a terminal the pointer moved onto, or `None` if it
+/// shouldn't be focused. Focus is never taken from a modal.
+fn hover_focus_delay(
+    focus_follows_mouse: bool,
+    focused: bool,
+    has_active_modal: bool,
+) -> Option<Duration> {
+    if focus_follows_mouse && !focused && !has_active_modal {
+        Some(FOCUS_FOLLOWS_MOUSE_DELAY)
+    } else {
+        None
+    }
+}
+
+/// Focuses the terminal that was focused most recently, in the center or in the terminal
+/// panel, opening a new one when there aren't any. From a terminal, focus goes back to where
+/// it was before.
+fn focus_terminal(workspace: &mut Workspace, _: &FocusTerminal, cx: &mut ViewContext<Workspace>) {
+    let panel = workspace.panel::<TerminalPanel>(cx);
+    let mut terminals = workspace
+        .items_of_type::<TerminalView>(cx)
+        .collect::<Vec<_>>();
+    if let Some(panel) = &panel {
+        terminals.extend(
+            panel
+                .read(cx)
+                .pane()
+                .read(cx)
+                .items_of_type::<TerminalView>(),
+        );
+    }
+    let focused_ix = terminals
+        .iter()
+        .position(|terminal| terminal.focus_handle(cx).contains_focused(cx));
+    let last_focused = terminals
+        .iter()
+        .map(|terminal| terminal.read(cx).last_focused)
+        .collect::<Vec<_>>();
+
+    match focus_terminal_target(focused_ix.is_some(), &last_focused) {
+        FocusTerminalTarget::ReturnFocus => {
+            let Some(terminal) = focused_ix.map(|ix| terminals[ix].clone()) else {
+                return;
+            };
+            match terminal.update(cx, |terminal, _| terminal.return_focus.take()) {
+                Some(return_focus) => cx.focus(&return_focus),
+                None => cx.focus_view(workspace.active_pane()),
+            }
+        }
+        FocusTerminalTarget::Terminal(ix) => {
+            let terminal = terminals[ix].clone();
+            let return_focus = cx.focused();
+            terminal.update(cx, |terminal, _| terminal.return_focus = return_focus);
+            let panel_pane = panel
+                .filter(|panel| {
+                    panel
+                        .read(cx)
+                        .pane()
+                        .read(cx)
+                        .index_for_item(&terminal)
+                        .is_some()
+                })
+                .map(|panel| panel.read(cx).pane().clone());
+            match panel_pane {
+                Some(pane) => {
+                    workspace.focus_panel::<TerminalPanel>(cx);
+                    pane.update(cx, |pane, cx| {
+                        if let Some(ix) = pane.index_for_item(&terminal) {
+                            pane.activate_item(ix, true, true, cx);
+                        }
+                    });
+                }
+                None => {
+                    workspace.activate_item(&terminal, cx);
+                }
+            }
+            cx.focus_view(&terminal);
+        }
+        FocusTerminalTarget::Deploy => {
+            let strategy = TerminalSettings::get_global(cx).working_directory.clone();
+            let working_directory = working_directory_or_notify(workspace, cx, strategy);
+            TerminalView::deploy_in(workspace, working_directory, true, cx);
+        }
+    }
+}
+
+fn spawn_terminal_command(
+    workspace: &mut Workspace,
+    action: &SpawnTerminalCommand,
+    cx: &mut ViewContext<Workspace>,
+) {
+    let strategy = match &action.working_directory {
+        Some(directory) => WorkingDirectory::Always {
+            directory: directory.clone(),
+        },
+        None => TerminalSettings::get_global(cx).working_directory.clone(),
+    };
+    let working_directory = working_directory_or_notify(workspace, cx, strategy);
+    if let Some(view) = TerminalView::deploy_in(workspace, working_directory, action.reveal, cx) {
+        let command = command_line(&action.command);
+        view.update(cx, |view, cx| view.send_text(&command, cx));
+    }
+}
+
+/// The command typed into a terminal to run it, ending with enter.
+fn command_line(command: &str) -> String {
+    format!("{}\r", command.trim_end_matches(['\r', '\n']))
+}
+
+/// Opens the user's settings file, with the cursor on its `terminal` section.
+fn open_terminal_settings(
+    workspace: &mut Workspace,
+    _: &OpenTerminalSettings,
+    cx: &mut ViewContext<Workspace>,
+) {
+    let open_settings = workspace.open_abs_path(paths::SETTINGS.clone(), true, cx);
+    cx.spawn(|_, mut cx| async move {
+        let item = open_settings.await?;
+        if let Some(editor) = item.downcast::<Editor>() {
+            editor.update(&mut cx, |editor, cx| {
+                let settings = editor.buffer().read(cx).snapshot(cx).text();
+                if let Some(offset) = terminal_settings_offset(&settings) {
+                    editor.change_selections(Some(Autoscroll::center()), cx, |s| {
+                        s.select_ranges([offset..offset])
+                    });
+                }
+            })?;
+        }
+        anyhow::Ok(())
+    })
+    .detach_and_log_err(cx);
+}
+
+/// Finds the `"terminal"` key in a settings file, skipping any string values that happen to match.
+fn terminal_settings_offset(settings: &str) -> Option<usize> {
+    settings
+        .match_indices("\"terminal\"")
+        .find_map(|(ix, key)| {
+            settings[ix + key.len()..]
+                .trim_start()
+                .starts_with(':')
+                .then_some(ix)
+        })
+}
+
 ///A terminal view, maintains the PTY's file handles and communicates with the terminal
 pub struct TerminalView {
     terminal: Model<Terminal>,
@@ -84,14 +418,41 @@ pub struct TerminalView {
     focus_handle: FocusHandle,
     //Currently using iTerm bell, show bell emoji in tab until input is received
     has_bell: bool,
+    /// Whether output arrived while this terminal wasn't focused, shown in its tab until it is
+    has_activity: bool,
+    /// Ends the flash of the background for a visual bell, while it's showing
+    bell_flash: Option<Task<()>>,
     context_menu: Option<(View<ContextMenu>, gpui::Point<Pixels>, Subscription)>,
     blink_state: bool,
     blinking_on: bool,
     blinking_paused: bool,
     blink_epoch: usize,
+    /// How long the cursor stays on, and then off, from `cursor_blink_interval_ms`
+    blink_interval: Duration,
     can_navigate_to_selected_word: bool,
     workspace_id: WorkspaceId,
     show_title: bool,
+    /// Whether input to this terminal is also sent to the other terminals in its pane
+    broadcast_input: bool,
+    /// How much this terminal is zoomed in, on top of the font size in the settings
+    font_size_delta: Pixels,
+    /// The name the user gave this terminal, shown instead of its title
+    custom_title: Option<String>,
+    /// When this terminal was last focused, to find the most recently used one
+    last_focused: Option<Instant>,
+    /// What was focused before [`FocusTerminal`] focused this terminal, to go back to
+    return_focus: Option<FocusHandle>,
+    /// Focuses this terminal once the pointer has stayed over it, with `focus_follows_mouse`
+    hover_focus: Option<Task<()>>,
+    /// The terminals this tab is split into, when it's been split
+    splits: Option<SplitNode<SplitPane>>,
+    /// Whether this is one of the panes of another terminal's tab, which handles splitting
+    /// and moving between panes for it
+    is_split_pane: bool,
+    /// The text last pasted from the clipboard, while `PastePrevious` can still replace it
+    last_paste: Option<String>,
+    /// The missing theme last named by the `theme` setting, so it's only warned about once
+    warned_theme_name: Option<String>,
     _subscriptions: Vec<Subscription>,
     _terminal_subscriptions: Vec<Subscription>,
 }
@@ -113,29 +474,43 @@ impl TerminalView {
         _: &NewCenterTerminal,
         cx: &mut ViewContext<Workspace>,
     ) {
-        let strategy = TerminalSettings::get_global(cx);
-        let working_directory =
-            get_working_directory(workspace, cx, strategy.working_directory.clone());
+        let strategy = TerminalSettings::get_global(cx).working_directory.clone();
+        let working_directory = working_directory_or_notify(workspace, cx, strategy);
+        Self::deploy_in(workspace, working_directory, true, cx);
+    }
 
+    /// Opens a new terminal in the active pane, started in `working_directory`. Without `focus`,
+    /// it's opened in the background.
+    fn deploy_in(
+        workspace: &mut Workspace,
+        working_directory: Option<PathBuf>,
+        focus: bool,
+        cx: &mut ViewContext<Workspace>,
+    ) -> Option<View<TerminalView>> {
         let window = cx.window_handle();
         let terminal = workspace
             .project()
             .update(cx, |project, cx| {
                 project.create_terminal(working_directory, None, window, cx)
             })
-            .notify_err(workspace, cx);
-
-        if let Some(terminal) = terminal {
-            let view = cx.new_view(|cx| {
-                TerminalView::new(
-                    terminal,
-                    workspace.weak_handle(),
-                    workspace.database_id(),
-                    cx,
-                )
+            .notify_err(workspace, cx)?;
+
+        let view = cx.new_view(|cx| {
+            TerminalView::new(
+                terminal,
+                workspace.weak_handle(),
+                workspace.database_id(),
+                cx,
+            )
+        });
+        if focus {
+            workspace.add_item_to_active_pane(Box::new(view.clone()), cx);
+        } else {
+            workspace.active_pane().update(cx, |pane, cx| {
+                pane.add_item(Box::new(view.clone()), false, false, None, cx)
             });
-            workspace.add_item_to_active_pane(Box::new(view), cx)
         }
+        Some(view)
     }
 
     pub fn new(
@@ -154,23 +529,41 @@ impl TerminalView {
         let focus_out = cx.on_focus_out(&focus_handle, |terminal_view, cx| {
             terminal_view.focus_out(cx);
         });
+        let mut warned_theme_name = None;
+        terminal::warn_about_missing_theme(&mut warned_theme_name, cx);
 
         Self {
             terminal,
             workspace: workspace_handle,
             has_bell: false,
+            has_activity: false,
+            bell_flash: None,
             focus_handle,
             context_menu: None,
             blink_state: true,
             blinking_on: false,
             blinking_paused: false,
             blink_epoch: 0,
+            blink_interval: cursor_blink_interval(
+                TerminalSettings::get_global(cx).cursor_blink_interval_ms,
+            ),
             can_navigate_to_selected_word: false,
             workspace_id,
             show_title: TerminalSettings::get_global(cx).toolbar.title,
+            broadcast_input: false,
+            font_size_delta: px(0.),
+            custom_title: None,
+            last_focused: None,
+            return_focus: None,
+            hover_focus: None,
+            splits: None,
+            is_split_pane: false,
+            last_paste: None,
+            warned_theme_name,
             _subscriptions: vec![
                 focus_in,
                 focus_out,
+                cx.observe_window_activation(Self::window_activation_changed),
                 cx.observe_global::<SettingsStore>(Self::settings_changed),
             ],
             _terminal_subscriptions: terminal_subscriptions,
@@ -195,9 +588,15 @@ impl TerminalView {
         position: gpui::Point<Pixels>,
         cx: &mut ViewContext<Self>,
     ) {
+        let has_selection = self.terminal.read(cx).selection_text().is_some();
+        let has_errors = !self.error_locations(cx).is_empty();
         let context_menu = ContextMenu::build(cx, |menu, _| {
-            menu.action("Clear", Box::new(Clear))
-                .action("Close", Box::new(CloseActiveItem { save_intent: None }))
+            context_menu_entries(has_selection, has_errors)
+                .into_iter()
+                .fold(menu, |menu, entry| match entry {
+                    Some((label, action)) => menu.action(label, action),
+                    None => menu.separator(),
+                })
         });
 
         cx.focus_view(&context_menu);
@@ -216,8 +615,15 @@ impl TerminalView {
     }
 
     fn settings_changed(&mut self, cx: &mut ViewContext<Self>) {
+        terminal::warn_about_missing_theme(&mut self.warned_theme_name, cx);
         let settings = TerminalSettings::get_global(cx);
         self.show_title = settings.toolbar.title;
+        let blink_interval = cursor_blink_interval(settings.cursor_blink_interval_ms);
+        if blink_interval != self.blink_interval {
+            self.blink_interval = blink_interval;
+            // Starts blinking again at the new interval, dropping the timer for the old one
+            self.pause_cursor_blinking(cx);
+        }
         cx.notify();
     }
 
@@ -245,16 +651,73 @@ impl TerminalView {
         cx.notify();
     }
 
+    fn scroll_line_up(&mut self, _: &ScrollLineUp, cx: &mut ViewContext<Self>) {
+        self.terminal.update(cx, |term, _| term.scroll_line_up());
+        cx.notify();
+    }
+
+    fn scroll_line_down(&mut self, _: &ScrollLineDown, cx: &mut ViewContext<Self>) {
+        self.terminal.update(cx, |term, _| term.scroll_line_down());
+        cx.notify();
+    }
+
+    fn scroll_page_up(&mut self, _: &ScrollPageUp, cx: &mut ViewContext<Self>) {
+        self.terminal.update(cx, |term, _| term.scroll_page_up());
+        cx.notify();
+    }
+
+    fn scroll_page_down(&mut self, _: &ScrollPageDown, cx: &mut ViewContext<Self>) {
+        self.terminal.update(cx, |term, _| term.scroll_page_down());
+        cx.notify();
+    }
+
+    fn scroll_to_top(&mut self, _: &ScrollToTop, cx: &mut ViewContext<Self>) {
+        self.terminal.update(cx, |term, _| term.scroll_to_top());
+        cx.notify();
+    }
+
+    fn scroll_to_bottom(&mut self, _: &ScrollToBottom, cx: &mut ViewContext<Self>) {
+        self.terminal.update(cx, |term, _| term.scroll_to_bottom());
+        cx.notify();
+    }
+
+    fn jump_to_previous_prompt(&mut self, _: &JumpToPreviousPrompt, cx: &mut ViewContext<Self>) {
+        self.terminal
+            .update(cx, |term, _| term.jump_to_prompt(AlacDirection::Left));
+        cx.notify();
+    }
+
+    fn jump_to_next_prompt(&mut self, _: &JumpToNextPrompt, cx: &mut ViewContext<Self>) {
+        self.terminal
+            .update(cx, |term, _| term.jump_to_prompt(AlacDirection::Right));
+        cx.notify();
+    }
+
     fn clear(&mut self, _: &Clear, cx: &mut ViewContext<Self>) {
-        self.terminal.update(cx, |term, _| term.clear());
+        let settings = TerminalSettings::get_global(cx);
+        let (behavior, redraw_prompt) = (settings.clear_behavior, settings.clear_redraws_prompt);
+        self.terminal
+            .update(cx, |term, _| term.clear(behavior, redraw_prompt));
+        cx.notify();
+    }
+
+    fn clear_scrollback(&mut self, _: &ClearScrollback, cx: &mut ViewContext<Self>) {
+        self.terminal.update(cx, |term, _| term.clear_scrollback());
+        cx.notify();
+    }
+
+    fn toggle_copy_mode(&mut self, _: &ToggleCopyMode, cx: &mut ViewContext<Self>) {
+        self.terminal.update(cx, |term, _| term.toggle_copy_mode());
         cx.notify();
     }
 
     pub fn should_show_cursor(&self, focused: bool, cx: &mut gpui::ViewContext<Self>) -> bool {
         //Don't blink the cursor when not focused, blinking is disabled, or paused
         if !focused
+            || !cx.is_window_active()
             || !self.blinking_on
             || self.blinking_paused
+            || self.terminal.read(cx).copy_mode()
             || self
                 .terminal
                 .read(cx)
@@ -273,14 +736,49 @@ impl TerminalView {
         }
     }
 
+    fn ring_bell(&mut self, cx: &mut ViewContext<Self>) {
+        let bell = TerminalSettings::get_global(cx).bell;
+        self.has_bell = true;
+        if bell.is_audible() {
+            cx.beep();
+        }
+        if bell.is_visual() {
+            // Restarting the flash for a bell rung during one drops the task ending it
+            let flash = cx.background_executor().timer(BELL_FLASH_DURATION);
+            self.bell_flash = Some(cx.spawn(|this, mut cx| async move {
+                flash.await;
+                this.update(&mut cx, |this, cx| {
+                    this.bell_flash = None;
+                    cx.notify();
+                })
+                .log_err();
+            }));
+            cx.notify();
+        }
+    }
+
+    /// Shows a toast for this terminal, replacing the last one it showed.
+    fn show_notification(&self, message: String, cx: &mut ViewContext<Self>) {
+        if !TerminalSettings::get_global(cx).notify_on_bell {
+            return;
+        }
+        let id = NotificationId::identified::<TerminalNotification>(("terminal", cx.entity_id()));
+        self.workspace
+            .update(cx, |workspace, cx| {
+                workspace.show_toast(Toast::new(id, message), cx)
+            })
+            .ok();
+    }
+
     fn blink_cursors(&mut self, epoch: usize, cx: &mut ViewContext<Self>) {
         if epoch == self.blink_epoch && !self.blinking_paused {
             self.blink_state = !self.blink_state;
             cx.notify();
 
             let epoch = self.next_blink_epoch();
+            let interval = self.blink_interval;
             cx.spawn(|this, mut cx| async move {
-                Timer::after(CURSOR_BLINK_INTERVAL).await;
+                Timer::after(interval).await;
                 this.update(&mut cx, |this, cx| this.blink_cursors(epoch, cx))
                     .log_err();
             })
@@ -293,14 +791,34 @@ impl TerminalView {
         cx.notify();
 
         let epoch = self.next_blink_epoch();
+        let interval = self.blink_interval;
         cx.spawn(|this, mut cx| async move {
-            Timer::after(CURSOR_BLINK_INTERVAL).await;
+            Timer::after(interval).await;
             this.update(&mut cx, |this, cx| this.resume_cursor_blinking(epoch, cx))
                 .ok();
         })
         .detach();
     }
 
+    /// The cursor stops blinking while the window is in the background, showing solid, and
+    /// starts again from solid when it comes back.
+    fn window_activation_changed(&mut self, cx: &mut ViewContext<Self>) {
+        if cx.is_window_active() {
+            self.pause_cursor_blinking(cx);
+        } else {
+            self.blink_state = true;
+            cx.notify();
+        }
+    }
+
+    /// The directory shown in the tab to tell it apart from others with the same title.
+    fn tab_directory(&self, cx: &AppContext) -> Option<PathBuf> {
+        let terminal = self.terminal.read(cx);
+        terminal
+            .current_working_directory()
+            .or_else(|| terminal.associated_directory())
+    }
+
     pub fn terminal(&self) -> &Model<Terminal> {
         &self.terminal
     }
@@ -317,33 +835,614 @@ impl TerminalView {
         }
     }
 
+    /// Asks the user where to save something exported from this terminal, starting in the
+    /// terminal's associated directory.
+    pub fn prompt_for_save_path(&self, cx: &AppContext) -> oneshot::Receiver<Option<PathBuf>> {
+        let directory = self
+            .terminal
+            .read(cx)
+            .associated_directory()
+            .or_else(home_dir)
+            .unwrap_or_default();
+        cx.prompt_for_new_path(&directory)
+    }
+
+    pub fn broadcast_input(&self) -> bool {
+        self.broadcast_input
+    }
+
+    fn send_sig_int(&mut self, _: &SendSigInt, cx: &mut ViewContext<Self>) {
+        self.send_signal(Signal::Interrupt, cx);
+    }
+
+    fn send_sig_term(&mut self, _: &SendSigTerm, cx: &mut ViewContext<Self>) {
+        self.send_signal(Signal::Terminate, cx);
+    }
+
+    fn send_sig_kill(&mut self, _: &SendSigKill, cx: &mut ViewContext<Self>) {
+        self.send_signal(Signal::Kill, cx);
+    }
+
+    fn send_sig_hup(&mut self, _: &SendSigHup, cx: &mut ViewContext<Self>) {
+        self.send_signal(Signal::Hangup, cx);
+    }
+
+    /// Signals the program running in the terminal. Signals can't be sent on Windows, and
+    /// nothing happens when there's no process left to signal.
+    fn send_signal(&mut self, signal: Signal, cx: &mut ViewContext<Self>) {
+        #[cfg(unix)]
+        self.terminal.read(cx).signal_foreground(signal);
+        #[cfg(not(unix))]
+        let _ = (signal, cx);
+    }
+
+    fn toggle_broadcast_input(&mut self, _: &ToggleBroadcastInput, cx: &mut ViewContext<Self>) {
+        self.broadcast_input = !self.broadcast_input;
+        cx.emit(ItemEvent::UpdateTab);
+        cx.notify();
+    }
+
+    fn increase_font_size(&mut self, _: &IncreaseFontSize, cx: &mut ViewContext<Self>) {
+        self.change_font_size(px(1.), cx);
+    }
+
+    fn decrease_font_size(&mut self, _: &DecreaseFontSize, cx: &mut ViewContext<Self>) {
+        self.change_font_size(px(-1.), cx);
+    }
+
+    fn reset_font_size(&mut self, _: &ResetFontSize, cx: &mut ViewContext<Self>) {
+        self.font_size_delta = px(0.);
+        cx.notify();
+    }
+
+    fn change_font_size(&mut self, change: Pixels, cx: &mut ViewContext<Self>) {
+        let font_size = settings_font_size(cx);
+        self.font_size_delta =
+            zoomed_font_size(font_size, self.font_size_delta + change) - font_size;
+        cx.notify();
+    }
+
+    /// Replaces the terminal with a new shell, started in the same directory as the old one,
+    /// or in the home directory if that one was deleted.
+    fn restart(&mut self, _: &Restart, cx: &mut ViewContext<Self>) {
+        let Some(workspace) = self.workspace.upgrade() else {
+            return;
+        };
+        let terminal = self.terminal.read(cx);
+        if terminal.task().is_some() {
+            return;
+        }
+        let working_directory = existing_directory_or_home(terminal.working_directory());
+        let project = workspace.read(cx).project().clone();
+        let window = cx.window_handle();
+        let terminal = project.update(cx, |project, cx| {
+            project.create_terminal(working_directory, None, window, cx)
+        });
+        match terminal {
+            Ok(terminal) => {
+                self.set_terminal(terminal, cx);
+                cx.emit(ItemEvent::UpdateTab);
+                cx.notify();
+            }
+            Err(error) => {
+                let message = format!("Couldn't restart the terminal: {error}");
+                let id =
+                    NotificationId::identified::<TerminalNotification>(("restart", cx.entity_id()));
+                workspace.update(cx, |workspace, cx| {
+                    workspace.show_toast(Toast::new(id, message), cx)
+                });
+            }
+        }
+    }
+
+    fn rename(&mut self, _: &RenameTerminal, cx: &mut ViewContext<Self>) {
+        let Some(workspace) = self.workspace.upgrade() else {
+            return;
+        };
+        let terminal_view = cx.view().downgrade();
+        let name = self
+            .custom_title
+            .clone()
+            .unwrap_or_else(|| self.terminal.read(cx).title(None, None));
+        workspace.update(cx, |workspace, cx| {
+            workspace.toggle_modal(cx, |cx| RenameTerminalModal::new(terminal_view, name, cx));
+        });
+    }
+
+    /// Sets the name shown in this terminal's tab, or goes back to its title if it's `None`.
+    pub fn set_custom_title(&mut self, custom_title: Option<String>, cx: &mut ViewContext<Self>) {
+        let item_id = cx.entity_id();
+        let workspace_id = self.workspace_id;
+        let title = custom_title.clone().unwrap_or_default();
+        cx.background_executor()
+            .spawn(async move {
+                TERMINAL_DB
+                    .save_title(item_id.as_u64(), workspace_id, title)
+                    .await
+                    .log_err();
+            })
+            .detach();
+        self.custom_title = custom_title;
+        cx.emit(ItemEvent::UpdateTab);
+        cx.notify();
+    }
+
+    /// Opens a new terminal tab in the directory the shell in this one is in.
+    fn deploy_in_current_dir(&mut self, _: &DeployInCurrentDir, cx: &mut ViewContext<Self>) {
+        let Some(workspace) = self.workspace.upgrade() else {
+            return;
+        };
+        let current_dir = self.terminal.read(cx).current_working_directory();
+        workspace.update(cx, |workspace, cx| {
+            let strategy = TerminalSettings::get_global(cx).working_directory.clone();
+            let working_directory =
+                directory_for_new_terminal(current_dir, workspace, cx, strategy);
+            TerminalView::deploy_in(workspace, working_directory, true, cx);
+        });
+    }
+
+    fn split_right(&mut self, _: &SplitRight, cx: &mut ViewContext<Self>) {
+        self.split(SplitAxis::Horizontal, cx);
+    }
+
+    fn split_down(&mut self, _: &SplitDown, cx: &mut ViewContext<Self>) {
+        self.split(SplitAxis::Vertical, cx);
+    }
+
+    /// Splits the focused pane of this tab in two, starting a new terminal in the same
+    /// directory in the new pane.
+    fn split(&mut self, axis: SplitAxis, cx: &mut ViewContext<Self>) {
+        if self.is_split_pane {
+            cx.propagate();
+            return;
+        }
+        let Some(workspace) = self.workspace.upgrade() else {
+            return;
+        };
+        let focused = self.focused_split_pane(cx);
+        let working_directory = match &focused {
+            SplitPane::This => self.terminal.read(cx).working_directory(),
+            SplitPane::Other(view) => view.read(cx).terminal.read(cx).working_directory(),
+        };
+        let project = workspace.read(cx).project().clone();
+        let window = cx.window_handle();
+        let Some(terminal) = project
+            .update(cx, |project, cx| {
+                project.create_terminal(working_directory, None, window, cx)
+            })
+            .log_err()
+        else {
+            return;
+        };
+
+        let workspace = self.workspace.clone();
+        let workspace_id = self.workspace_id;
+        let view = cx.new_view(|cx| {
+            let mut view = TerminalView::new(terminal, workspace, workspace_id, cx);
+            view.is_split_pane = true;
+            view
+        });
+        cx.subscribe(&view, |this, view, event: &ItemEvent, cx| {
+            if let ItemEvent::CloseItem = event {
+                this.close_split_pane(SplitPane::Other(view), cx);
+            }
+        })
+        .detach();
+        self.splits
+            .get_or_insert(SplitNode::Pane(SplitPane::This))
+            .split(&focused, SplitPane::Other(view.clone()), axis);
+        view.focus_handle(cx).focus(cx);
+        cx.notify();
+    }
+
+    /// Removes a pane whose terminal closed, closing the tab when it was its last pane.
+    fn close_split_pane(&mut self, pane: SplitPane, cx: &mut ViewContext<Self>) {
+        let Some(splits) = self.splits.as_mut() else {
+            cx.emit(ItemEvent::CloseItem);
+            return;
+        };
+        let mut was_focused = match &pane {
+            SplitPane::This => self.focus_handle.is_focused(cx),
+            SplitPane::Other(view) => view.focus_handle(cx).contains_focused(cx),
+        };
+        match pane {
+            SplitPane::This => {
+                // This view is the tab, so it takes over the terminal of another pane instead
+                let Some(other) = splits.panes().into_iter().find_map(|pane| match pane {
+                    SplitPane::Other(view) => Some(view.clone()),
+                    SplitPane::This => None,
+                }) else {
+                    return;
+                };
+                was_focused |= other.focus_handle(cx).contains_focused(cx);
+                splits.remove(&SplitPane::This);
+                splits.replace(&SplitPane::Other(other.clone()), SplitPane::This);
+                let terminal = other.read(cx).terminal.clone();
+                self.set_terminal(terminal, cx);
+                cx.emit(ItemEvent::UpdateTab);
+            }
+            pane => {
+                splits.remove(&pane);
+            }
+        }
+        if let Some(SplitNode::Pane(_)) = self.splits {
+            self.splits = None;
+        }
+        if was_focused {
+            self.focus_handle.focus(cx);
+        }
+        cx.notify();
+    }
+
+    /// The pane of this tab that has focus, or this terminal if none of the others do.
+    fn focused_split_pane(&self, cx: &AppContext) -> SplitPane {
+        self.splits
+            .iter()
+            .flat_map(|splits| splits.panes())
+            .find(|pane| match pane {
+                SplitPane::Other(view) => view.focus_handle(cx).contains_focused(cx),
+                SplitPane::This => false,
+            })
+            .cloned()
+            .unwrap_or(SplitPane::This)
+    }
+
+    fn focus_pane_left(&mut self, _: &FocusPaneLeft, cx: &mut ViewContext<Self>) {
+        self.focus_pane(SplitDirection::Left, cx);
+    }
+
+    fn focus_pane_right(&mut self, _: &FocusPaneRight, cx: &mut ViewContext<Self>) {
+        self.focus_pane(SplitDirection::Right, cx);
+    }
+
+    fn focus_pane_up(&mut self, _: &FocusPaneUp, cx: &mut ViewContext<Self>) {
+        self.focus_pane(SplitDirection::Up, cx);
+    }
+
+    fn focus_pane_down(&mut self, _: &FocusPaneDown, cx: &mut ViewContext<Self>) {
+        self.focus_pane(SplitDirection::Down, cx);
+    }
+
+    fn focus_pane(&mut self, direction: SplitDirection, cx: &mut ViewContext<Self>) {
+        if self.is_split_pane {
+            cx.propagate();
+            return;
+        }
+        let Some(splits) = &self.splits else {
+            return;
+        };
+        let focused = self.focused_split_pane(cx);
+        match splits.neighbor(&focused, direction) {
+            Some(SplitPane::This) => self.focus_handle.focus(cx),
+            Some(SplitPane::Other(view)) => view.focus_handle(cx).focus(cx),
+            None => {}
+        }
+    }
+
+    fn find_next(&mut self, _: &FindNext, cx: &mut ViewContext<Self>) {
+        self.terminal.update(cx, |terminal, _| {
+            terminal.activate_adjacent_match(AlacDirection::Right)
+        });
+        cx.notify();
+    }
+
+    fn find_prev(&mut self, _: &FindPrev, cx: &mut ViewContext<Self>) {
+        self.terminal.update(cx, |terminal, _| {
+            terminal.activate_adjacent_match(AlacDirection::Left)
+        });
+        cx.notify();
+    }
+
+    fn go_to_first_error(&mut self, _: &GoToFirstErrorInOutput, cx: &mut ViewContext<Self>) {
+        if let Some(location) = self.error_locations(cx).into_iter().next() {
+            open_path_like_target(&self.workspace, &location, cx);
+        }
+    }
+
+    fn go_to_last_error(&mut self, _: &GoToLastErrorInOutput, cx: &mut ViewContext<Self>) {
+        if let Some(location) = self.error_locations(cx).pop() {
+            open_path_like_target(&self.workspace, &location, cx);
+        }
+    }
+
+    fn reveal_in_project_panel(&mut self, _: &RevealInProjectPanel, cx: &mut ViewContext<Self>) {
+        let Some(cwd) = self.terminal.read(cx).get_cwd() else {
+            return;
+        };
+        let Some(workspace) = self.workspace.upgrade() else {
+            return;
+        };
+        if let Some(entry_id) = worktree_entry_for_path(workspace.read(cx), &cwd, cx) {
+            workspace.update(cx, |workspace, cx| {
+                workspace.project().update(cx, |_, cx| {
+                    cx.emit(project::Event::RevealInProjectPanel(entry_id));
+                })
+            });
+            return;
+        }
+
+        let answer = cx.prompt(
+            PromptLevel::Info,
+            &format!("{} is outside the project.", cwd.display()),
+            Some("Add it to the project to see its files in the project panel."),
+            &["Add Folder", "Cancel"],
+        );
+        cx.spawn(|_, mut cx| async move {
+            if answer.await != Ok(0) {
+                return anyhow::Ok(());
+            }
+            let (worktree, _) = workspace
+                .update(&mut cx, |workspace, cx| {
+                    workspace.project().update(cx, |project, cx| {
+                        project.find_or_create_local_worktree(&cwd, true, cx)
+                    })
+                })?
+                .await?;
+            workspace.update(&mut cx, |workspace, cx| {
+                let root_entry_id = worktree.read(cx).root_entry().map(|entry| entry.id);
+                workspace.project().update(cx, |_, cx| match root_entry_id {
+                    Some(entry_id) => cx.emit(project::Event::RevealInProjectPanel(entry_id)),
+                    None => cx.emit(project::Event::ActivateProjectPanel),
+                })
+            })?;
+            anyhow::Ok(())
+        })
+        .detach_and_log_err(cx);
+    }
+
+    fn error_locations(&self, cx: &ViewContext<Self>) -> Vec<PathLikeTarget> {
+        let error_pattern = &TerminalSettings::get_global(cx).error_pattern;
+        self.terminal
+            .read(cx)
+            .error_locations(error_pattern)
+            .log_err()
+            .unwrap_or_default()
+    }
+
+    /// The terminals that input should be mirrored to: every other terminal in
+    /// this terminal's pane, while broadcasting is on.
+    fn broadcast_peers(&self, cx: &ViewContext<Self>) -> Vec<Model<Terminal>> {
+        // Keys in copy mode only move this terminal's cursor
+        if !self.broadcast_input || self.terminal.read(cx).copy_mode() {
+            return Vec::new();
+        }
+        let Some(workspace) = self.workspace.upgrade() else {
+            return Vec::new();
+        };
+        let workspace = workspace.read(cx);
+        let terminal_panel_pane = workspace
+            .panel::<TerminalPanel>(cx)
+            .map(|panel| panel.read(cx).pane().clone());
+        let this_id = cx.view().entity_id();
+        workspace
+            .panes()
+            .iter()
+            .cloned()
+            .chain(terminal_panel_pane)
+            .map(|pane| {
+                pane.read(cx)
+                    .items_of_type::<TerminalView>()
+                    .collect::<Vec<_>>()
+            })
+            .find(|views| views.iter().any(|view| view.entity_id() == this_id))
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|view| view.entity_id() != this_id)
+            .map(|view| view.read(cx).terminal.clone())
+            .collect()
+    }
+
+    /// Copies the command on the cursor's line as a snippet that can be run from anywhere in
+    /// the project, by prefixing it with a `cd` to the terminal's working directory.
+    fn copy_command_invocation(&mut self, _: &CopyCommandInvocation, cx: &mut ViewContext<Self>) {
+        let terminal = self.terminal.read(cx);
+        let command = terminal.cursor_line();
+        if command.is_empty() {
+            return;
+        }
+        let cwd = terminal.get_cwd();
+        let project_roots = self
+            .workspace
+            .upgrade()
+            .map(|workspace| {
+                workspace
+                    .read(cx)
+                    .visible_worktrees(cx)
+                    .map(|worktree| worktree.read(cx).abs_path().to_path_buf())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        let invocation = command_invocation(&command, cwd.as_deref(), &project_roots);
+        cx.write_to_clipboard(ClipboardItem::new(invocation));
+    }
+
     ///Attempt to paste the clipboard into the terminal
     fn copy(&mut self, _: &Copy, cx: &mut ViewContext<Self>) {
+        if let Some(text) = self.terminal.read(cx).selection_text().map(str::to_string) {
+            cx.default_global::<ClipboardRing>().record(text);
+        }
         self.terminal.update(cx, |term, _| term.copy());
         cx.notify();
     }
 
+    fn save_output(&mut self, action: &SaveOutput, cx: &mut ViewContext<Self>) {
+        let Some(fs) = self
+            .workspace
+            .update(cx, |workspace, cx| {
+                workspace.project().read(cx).fs().clone()
+            })
+            .ok()
+        else {
+            return;
+        };
+        let contents = self
+            .terminal
+            .read(cx)
+            .buffer_contents(action.preserve_ansi, cx);
+        let path = self.prompt_for_save_path(cx);
+        let workspace = self.workspace.clone();
+        let id =
+            NotificationId::identified::<TerminalNotification>(("save-output", cx.entity_id()));
+        cx.spawn(|_, mut cx| async move {
+            let Some(path) = path.await.ok().flatten() else {
+                return;
+            };
+            let Err(error) = fs.atomic_write(path.clone(), contents).await else {
+                return;
+            };
+            let message = format!("Couldn't save the terminal output to {path:?}: {error}");
+            workspace
+                .update(&mut cx, |workspace, cx| {
+                    workspace.show_toast(Toast::new(id, message), cx)
+                })
+                .ok();
+        })
+        .detach();
+    }
+
+    fn copy_last_command_output(&mut self, _: &CopyLastCommandOutput, cx: &mut ViewContext<Self>) {
+        self.terminal
+            .update(cx, |term, _| term.copy_last_command_output());
+        cx.notify();
+    }
+
+    fn copy_as_html(&mut self, _: &CopyAsHtml, cx: &mut ViewContext<Self>) {
+        self.terminal
+            .update(cx, |term, _| term.copy_styled(CopyFormat::Html));
+        cx.notify();
+    }
+
+    fn copy_as_ansi(&mut self, _: &CopyAsAnsi, cx: &mut ViewContext<Self>) {
+        self.terminal
+            .update(cx, |term, _| term.copy_styled(CopyFormat::Ansi));
+        cx.notify();
+    }
+
     ///Attempt to paste the clipboard into the terminal
     fn paste(&mut self, _: &Paste, cx: &mut ViewContext<Self>) {
-        if let Some(item) = cx.read_from_clipboard() {
-            self.terminal
-                .update(cx, |terminal, _cx| terminal.paste(item.text()));
+        let Some(item) = cx.read_from_clipboard() else {
+            return;
+        };
+        let text = item.text().clone();
+        cx.default_global::<ClipboardRing>().paste(&text);
+        self.paste_from_ring(text, cx);
+    }
+
+    /// Replaces the text just pasted with the next older text in the clipboard ring.
+    fn paste_previous(&mut self, _: &PastePrevious, cx: &mut ViewContext<Self>) {
+        let Some(last_paste) = self.last_paste.take() else {
+            return;
+        };
+        let Some(text) = cx
+            .default_global::<ClipboardRing>()
+            .paste_previous()
+            .map(str::to_string)
+        else {
+            return;
+        };
+        let erase = erase_text(&last_paste);
+        for terminal in self.broadcast_peers(cx).iter().chain([&self.terminal]) {
+            terminal.update(cx, |term, _| term.input(erase.clone()));
+        }
+        self.paste_from_ring(text, cx);
+    }
+
+    fn paste_from_ring(&mut self, text: String, cx: &mut ViewContext<Self>) {
+        if !TerminalSettings::get_global(cx).confirm_multiline_paste
+            || !self.terminal.read(cx).paste_runs_commands(&text)
+        {
+            self.paste_text(&text, cx);
+            // Pastes that ran commands can't be taken back
+            self.last_paste = Some(text).filter(|text| !text.contains(['\r', '\n']));
+            return;
+        }
+
+        let answer = cx.prompt(
+            PromptLevel::Warning,
+            "Paste multiple lines into the terminal?",
+            Some("Each line will run as a separate command."),
+            &["Paste", "Cancel"],
+        );
+        cx.spawn(|this, mut cx| async move {
+            if answer.await == Ok(0) {
+                this.update(&mut cx, |this, cx| this.paste_text(&text, cx))?;
+            }
+            anyhow::Ok(())
+        })
+        .detach_and_log_err(cx);
+    }
+
+    fn paste_text(&mut self, text: &str, cx: &mut ViewContext<Self>) {
+        for terminal in self.broadcast_peers(cx) {
+            terminal.update(cx, |terminal, cx| terminal.paste(text, cx));
         }
+        self.terminal
+            .update(cx, |terminal, cx| terminal.paste(text, cx));
     }
 
-    fn send_text(&mut self, text: &SendText, cx: &mut ViewContext<Self>) {
+    fn send_text_action(&mut self, text: &SendText, cx: &mut ViewContext<Self>) {
+        if self.terminal.read(cx).copy_mode() {
+            return;
+        }
         self.clear_bel(cx);
-        self.terminal.update(cx, |term, _| {
-            term.input(text.0.to_string());
-        });
+        for terminal in self.broadcast_peers(cx).iter().chain([&self.terminal]) {
+            terminal.update(cx, |term, _| {
+                term.input(text.0.to_string());
+            });
+        }
+    }
+
+    fn send_keys(&mut self, keys: &SendKeys, cx: &mut ViewContext<Self>) {
+        let Some(bytes) = unescape_keys(&keys.0).log_err() else {
+            return;
+        };
+        self.clear_bel(cx);
+        for terminal in self.broadcast_peers(cx).iter().chain([&self.terminal]) {
+            terminal.update(cx, |term, _| term.input_bytes(bytes.clone()));
+        }
+    }
+
+    /// Types text into the terminal, for other crates that run commands in it.
+    /// See [`Terminal::send_text`].
+    pub fn send_text(&mut self, text: &str, cx: &mut ViewContext<Self>) {
+        self.clear_bel(cx);
+        self.terminal
+            .update(cx, |terminal, _| terminal.send_text(text));
+    }
+
+    /// Clears the shell's input before the cursor, by typing ctrl-u.
+    fn clear_line(&mut self, _: &ClearLine, cx: &mut ViewContext<Self>) {
+        self.send_line_edit("ctrl-u", cx);
+    }
+
+    /// Clears the shell's input after the cursor, by typing ctrl-k.
+    fn clear_to_end(&mut self, _: &ClearToEnd, cx: &mut ViewContext<Self>) {
+        self.send_line_edit("ctrl-k", cx);
+    }
+
+    fn send_line_edit(&mut self, keystroke: &str, cx: &mut ViewContext<Self>) {
+        let mode = self.terminal.read(cx).last_content.mode;
+        if let Some(keystroke) = line_edit_keystroke(keystroke, mode) {
+            self.send_keystroke(&SendKeystroke(keystroke), cx);
+        }
     }
 
     fn send_keystroke(&mut self, text: &SendKeystroke, cx: &mut ViewContext<Self>) {
         if let Some(keystroke) = Keystroke::parse(&text.0).log_err() {
+            if keystroke.key == "enter"
+                && self
+                    .terminal
+                    .update(cx, |terminal, cx| terminal.enter_after_exit(cx))
+            {
+                return;
+            }
+            self.end_paste_cycle(cx);
             self.clear_bel(cx);
-            self.terminal.update(cx, |term, cx| {
-                term.try_keystroke(&keystroke, TerminalSettings::get_global(cx).option_as_meta);
-            });
+            for terminal in self.broadcast_peers(cx).iter().chain([&self.terminal]) {
+                terminal.update(cx, |term, cx| {
+                    term.try_keystroke(&keystroke, TerminalSettings::get_global(cx).option_as_meta);
+                });
+            }
         }
     }
 
@@ -361,6 +1460,13 @@ impl TerminalView {
             },
         );
 
+        if !self.terminal.read(cx).matches.is_empty() {
+            dispatch_context.add("search_matches");
+        }
+        if mode.contains(TermMode::VI) {
+            dispatch_context.add("copy_mode");
+        }
+
         if mode.contains(TermMode::APP_CURSOR) {
             dispatch_context.add("DECCKM");
         }
@@ -434,10 +1540,28 @@ fn subscribe_for_terminal_events(
     workspace: WeakView<Workspace>,
     cx: &mut ViewContext<'_, TerminalView>,
 ) -> Vec<Subscription> {
+    if let Some(shell) = terminal.read(cx).fallback_shell() {
+        let message = format!("The configured shell wasn't found, so {shell} was started instead.");
+        let workspace = workspace.clone();
+        // The workspace may be the one creating this view
+        cx.defer(move |_, cx| {
+            let id = NotificationId::identified::<TerminalNotification>((
+                "shell-fallback",
+                cx.entity_id(),
+            ));
+            workspace
+                .update(cx, |workspace, cx| {
+                    workspace.show_toast(Toast::new(id, message), cx)
+                })
+                .ok();
+        });
+    }
     let terminal_subscription = cx.observe(terminal, |_, _, cx| cx.notify());
     let terminal_events_subscription =
         cx.subscribe(terminal, move |this, _, event, cx| match event {
             Event::Wakeup => {
+                this.has_activity =
+                    activity_after_output(this.has_activity, this.focus_handle.is_focused(cx));
                 cx.notify();
                 cx.emit(Event::Wakeup);
                 cx.emit(ItemEvent::UpdateTab);
@@ -445,10 +1569,39 @@ fn subscribe_for_terminal_events(
             }
 
             Event::Bell => {
-                this.has_bell = true;
+                this.ring_bell(cx);
+                if !cx.is_window_active() {
+                    this.show_notification("Terminal activity".to_string(), cx);
+                }
                 cx.emit(Event::Wakeup);
             }
 
+            Event::Notification(message) => {
+                if !this.focus_handle.is_focused(cx) {
+                    this.show_notification(message.clone(), cx);
+                }
+            }
+
+            Event::ProcessExited { command, exit_code } => {
+                if *exit_code != 0
+                    && TerminalSettings::get_global(cx).notify_on_failure
+                    && !this.focus_handle.is_focused(cx)
+                {
+                    let message = format!("`{command}` exited with code {exit_code}");
+                    let id = NotificationId::identified::<TerminalNotification>((
+                        "process-exited",
+                        cx.entity_id(),
+                    ));
+                    workspace
+                        .update(cx, |workspace, cx| {
+                            workspace.show_toast(Toast::new(id, message), cx)
+                        })
+                        .ok();
+                }
+            }
+
+            Event::ClipboardWrite(text) => cx.write_to_clipboard(ClipboardItem::new(text.clone())),
+
             Event::BlinkChanged => this.blinking_on = !this.blinking_on,
 
             Event::TitleChanged => {
@@ -500,99 +1653,116 @@ fn subscribe_for_terminal_events(
                     if !this.can_navigate_to_selected_word {
                         return;
                     }
-                    let task_workspace = workspace.clone();
-                    let Some(fs) = workspace
-                        .update(cx, |workspace, cx| {
-                            workspace.project().read(cx).fs().clone()
-                        })
-                        .ok()
-                    else {
-                        return;
-                    };
-
-                    let path_like_target = path_like_target.clone();
-                    cx.spawn(|terminal_view, mut cx| async move {
-                        let valid_files_to_open = terminal_view
-                            .update(&mut cx, |_, cx| {
-                                possible_open_targets(
-                                    fs,
-                                    &task_workspace,
-                                    &path_like_target.terminal_dir,
-                                    &path_like_target.maybe_path,
-                                    cx,
-                                )
-                            })?
-                            .await;
-                        let paths_to_open = valid_files_to_open
-                            .iter()
-                            .map(|(p, _)| p.path_like.clone())
-                            .collect();
-                        let opened_items = task_workspace
-                            .update(&mut cx, |workspace, cx| {
-                                workspace.open_paths(
-                                    paths_to_open,
-                                    OpenVisible::OnlyDirectories,
-                                    None,
-                                    cx,
-                                )
-                            })
-                            .context("workspace update")?
-                            .await;
-
-                        let mut has_dirs = false;
-                        for ((path, metadata), opened_item) in valid_files_to_open
-                            .into_iter()
-                            .zip(opened_items.into_iter())
-                        {
-                            if metadata.is_dir {
-                                has_dirs = true;
-                            } else if let Some(Ok(opened_item)) = opened_item {
-                                if let Some(row) = path.row {
-                                    let col = path.column.unwrap_or(0);
-                                    if let Some(active_editor) = opened_item.downcast::<Editor>() {
-                                        active_editor
-                                            .downgrade()
-                                            .update(&mut cx, |editor, cx| {
-                                                let snapshot = editor.snapshot(cx).display_snapshot;
-                                                let point = snapshot.buffer_snapshot.clip_point(
-                                                    language::Point::new(
-                                                        row.saturating_sub(1),
-                                                        col.saturating_sub(1),
-                                                    ),
-                                                    Bias::Left,
-                                                );
-                                                editor.change_selections(
-                                                    Some(Autoscroll::center()),
-                                                    cx,
-                                                    |s| s.select_ranges([point..point]),
-                                                );
-                                            })
-                                            .log_err();
-                                    }
-                                }
-                            }
-                        }
-
-                        if has_dirs {
-                            task_workspace.update(&mut cx, |workspace, cx| {
-                                workspace.project().update(cx, |_, cx| {
-                                    cx.emit(project::Event::ActivateProjectPanel);
-                                })
-                            })?;
-                        }
-
-                        anyhow::Ok(())
-                    })
-                    .detach_and_log_err(cx)
+                    open_path_like_target(&workspace, path_like_target, cx);
                 }
             },
+            Event::TitleChangedTo(_) | Event::ContentChanged(_) => {}
             Event::BreadcrumbsChanged => cx.emit(ItemEvent::UpdateBreadcrumbs),
-            Event::CloseTerminal => cx.emit(ItemEvent::CloseItem),
+            Event::CloseTerminal => this.close_split_pane(SplitPane::This, cx),
+            Event::RestartTerminal => this.restart(&Restart, cx),
             Event::SelectionsChanged => cx.emit(SearchEvent::ActiveMatchChanged),
         });
     vec![terminal_subscription, terminal_events_subscription]
 }
 
+/// Opens the file a path in the terminal's output points to, at its line and column if it has
+/// them. Directories are revealed in the project panel instead.
+fn open_path_like_target(
+    workspace: &WeakView<Workspace>,
+    path_like_target: &PathLikeTarget,
+    cx: &mut ViewContext<TerminalView>,
+) {
+    let task_workspace = workspace.clone();
+    let Some(fs) = workspace
+        .update(cx, |workspace, cx| {
+            workspace.project().read(cx).fs().clone()
+        })
+        .ok()
+    else {
+        return;
+    };
+
+    let path_like_target = path_like_target.clone();
+    let range_end = split_range_end(&path_like_target.maybe_path).1;
+    cx.spawn(|terminal_view, mut cx| async move {
+        let valid_files_to_open = terminal_view
+            .update(&mut cx, |_, cx| {
+                possible_open_targets(
+                    fs,
+                    &task_workspace,
+                    &path_like_target.terminal_dir,
+                    &path_like_target.maybe_path,
+                    cx,
+                )
+            })?
+            .await;
+        let paths_to_open = valid_files_to_open
+            .iter()
+            .map(|(p, _)| p.path_like.clone())
+            .collect();
+        let opened_items = task_workspace
+            .update(&mut cx, |workspace, cx| {
+                workspace.open_paths(paths_to_open, OpenVisible::OnlyDirectories, None, cx)
+            })
+            .context("workspace update")?
+            .await;
+
+        let mut has_dirs = false;
+        for ((path, metadata), opened_item) in valid_files_to_open
+            .into_iter()
+            .zip(opened_items.into_iter())
+        {
+            if metadata.is_dir {
+                has_dirs = true;
+            } else if let Some(Ok(opened_item)) = opened_item {
+                if let Some(row) = path.row {
+                    let col = path.column.unwrap_or(0);
+                    if let Some(active_editor) = opened_item.downcast::<Editor>() {
+                        active_editor
+                            .downgrade()
+                            .update(&mut cx, |editor, cx| {
+                                let snapshot = editor.snapshot(cx).display_snapshot;
+                                let point = snapshot.buffer_snapshot.clip_point(
+                                    language::Point::new(
+                                        row.saturating_sub(1),
+                                        col.saturating_sub(1),
+                                    ),
+                                    Bias::Left,
+                                );
+                                // The end column is the range's last, so the selection ends
+                                // after it
+                                let end = range_end.map_or(point, |(end_row, end_col)| {
+                                    snapshot.buffer_snapshot.clip_point(
+                                        language::Point::new(
+                                            end_row.unwrap_or(row).saturating_sub(1),
+                                            end_col,
+                                        ),
+                                        Bias::Left,
+                                    )
+                                });
+                                editor.change_selections(Some(Autoscroll::center()), cx, |s| {
+                                    s.select_ranges([point..end])
+                                });
+                            })
+                            .log_err();
+                    }
+                }
+            }
+        }
+
+        if has_dirs {
+            task_workspace.update(&mut cx, |workspace, cx| {
+                workspace.project().update(cx, |_, cx| {
+                    cx.emit(project::Event::ActivateProjectPanel);
+                })
+            })?;
+        }
+
+        anyhow::Ok(())
+    })
+    .detach_and_log_err(cx);
+}
+
 fn possible_open_paths_metadata(
     fs: Arc<dyn Fs>,
     row: Option<u32>,
@@ -635,7 +1805,8 @@ fn possible_open_targets(
     maybe_path: &String,
     cx: &mut ViewContext<TerminalView>,
 ) -> Task<Vec<(PathLikeWithPosition<PathBuf>, Metadata)>> {
-    let path_like = PathLikeWithPosition::parse_str(maybe_path.as_str(), |path_str| {
+    let (maybe_path, _) = split_range_end(maybe_path);
+    let path_like = PathLikeWithPosition::parse_str(maybe_path, |path_str| {
         Ok::<_, std::convert::Infallible>(Path::new(path_str).to_path_buf())
     })
     .expect("infallible");
@@ -676,6 +1847,50 @@ fn possible_open_targets(
     possible_open_paths_metadata(fs, row, column, potential_abs_paths, cx)
 }
 
+/// What to type to erase pasted text from the shell's input: a backspace for each character.
+fn erase_text(text: &str) -> String {
+    "\x7f".repeat(text.chars().count())
+}
+
+/// The keystroke typed by `ClearLine` or `ClearToEnd`, unless a full screen program is running.
+/// Those give ctrl-u and ctrl-k other meanings, like scrolling up in vim or less.
+fn line_edit_keystroke(keystroke: &str, mode: TermMode) -> Option<String> {
+    (!mode.contains(TermMode::ALT_SCREEN)).then(|| keystroke.to_string())
+}
+
+/// Splits the end off a range like `file.rs:10:5-10:20`, as compilers print them, returning the
+/// path with the start's row and column, and the end's row and column. An end without a row,
+/// like the one in `file.rs:10:5-20`, is on the start's row.
+fn split_range_end(maybe_path: &str) -> (&str, Option<(Option<u32>, u32)>) {
+    let is_number = |text: &str| !text.is_empty() && text.bytes().all(|b| b.is_ascii_digit());
+    let Some((start, end)) = maybe_path.rsplit_once('-') else {
+        return (maybe_path, None);
+    };
+    // The start needs a row and a column, or it could be part of the file's name
+    let mut start_position = start.rsplitn(3, ':');
+    let (Some(column), Some(row), Some(_)) = (
+        start_position.next(),
+        start_position.next(),
+        start_position.next(),
+    ) else {
+        return (maybe_path, None);
+    };
+    if !is_number(row) || !is_number(column) {
+        return (maybe_path, None);
+    }
+    let end = match end.split_once(':') {
+        Some((row, column)) if is_number(row) && is_number(column) => {
+            (row.parse().ok(), column.parse().ok())
+        }
+        None if is_number(end) => (None, end.parse().ok()),
+        _ => return (maybe_path, None),
+    };
+    match end {
+        (row, Some(column)) => (start, Some((row, column))),
+        _ => (maybe_path, None),
+    }
+}
+
 fn regex_to_literal(regex: &str) -> String {
     regex
         .chars()
@@ -700,18 +1915,66 @@ pub fn regex_search_for_query(query: &project::search::SearchQuery) -> Option<Re
 
 impl TerminalView {
     fn key_down(&mut self, event: &KeyDownEvent, cx: &mut ViewContext<Self>) {
+        self.end_paste_cycle(cx);
         self.clear_bel(cx);
         self.pause_cursor_blinking(cx);
 
-        self.terminal.update(cx, |term, cx| {
-            term.try_keystroke(
-                &event.keystroke,
-                TerminalSettings::get_global(cx).option_as_meta,
-            )
-        });
+        for terminal in self.broadcast_peers(cx).iter().chain([&self.terminal]) {
+            terminal.update(cx, |term, cx| {
+                term.try_keystroke(
+                    &event.keystroke,
+                    TerminalSettings::get_global(cx).option_as_meta,
+                )
+            });
+        }
+    }
+
+    /// Typing between pastes stops `PastePrevious` from replacing the last one.
+    fn end_paste_cycle(&mut self, cx: &mut ViewContext<Self>) {
+        if self.last_paste.take().is_some() {
+            cx.default_global::<ClipboardRing>().reset();
+        }
+    }
+
+    fn has_active_modal(&self, cx: &WindowContext) -> bool {
+        self.workspace
+            .upgrade()
+            .map_or(false, |workspace| workspace.read(cx).has_active_modal(cx))
+    }
+
+    /// Focuses this terminal once the pointer has stayed over it for a moment, so that
+    /// passing over it on the way somewhere else doesn't.
+    fn hover_changed(&mut self, hovered: bool, cx: &mut ViewContext<Self>) {
+        self.hover_focus = None;
+        if !hovered {
+            return;
+        }
+        let Some(delay) = hover_focus_delay(
+            TerminalSettings::get_global(cx).focus_follows_mouse,
+            self.focus_handle.is_focused(cx),
+            self.has_active_modal(cx),
+        ) else {
+            return;
+        };
+        self.hover_focus = Some(cx.spawn(|this, mut cx| async move {
+            Timer::after(delay).await;
+            this.update(&mut cx, |this, cx| {
+                this.hover_focus = None;
+                // A modal may have opened in the meantime
+                if !this.has_active_modal(cx) {
+                    cx.focus(&this.focus_handle);
+                }
+            })
+            .log_err();
+        }));
     }
 
     fn focus_in(&mut self, cx: &mut ViewContext<Self>) {
+        self.last_focused = Some(Instant::now());
+        if self.has_activity {
+            self.has_activity = false;
+            cx.emit(ItemEvent::UpdateTab);
+        }
         self.terminal.read(cx).focus_in();
         self.blink_cursors(self.blink_epoch, cx);
         cx.notify();
@@ -727,22 +1990,151 @@ impl TerminalView {
 
 impl Render for TerminalView {
     fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
-        let terminal_handle = self.terminal.clone();
+        let pane = self.render_pane(cx).into_any_element();
+        if self.is_split_pane {
+            return pane;
+        }
 
-        let focused = self.focus_handle.is_focused(cx);
+        let content = match &self.splits {
+            Some(splits) => render_splits(splits, &mut Some(pane), cx),
+            None => pane,
+        };
+        div()
+            .size_full()
+            .on_action(cx.listener(TerminalView::split_right))
+            .on_action(cx.listener(TerminalView::split_down))
+            .on_action(cx.listener(TerminalView::focus_pane_left))
+            .on_action(cx.listener(TerminalView::focus_pane_right))
+            .on_action(cx.listener(TerminalView::focus_pane_up))
+            .on_action(cx.listener(TerminalView::focus_pane_down))
+            .child(content)
+            .into_any_element()
+    }
+}
+
+/// The entries of the terminal's context menu, with `None` standing for a separator. Copy is only
+/// offered when there's a selection, as it does nothing otherwise.
+fn context_menu_entries(
+    has_selection: bool,
+    has_errors: bool,
+) -> Vec<Option<(&'static str, Box<dyn Action>)>> {
+    let mut entries = Vec::new();
+    if has_selection {
+        entries.push(Some(("Copy", Box::new(Copy) as Box<dyn Action>)));
+    }
+    entries.extend([
+        Some(("Paste", Box::new(Paste) as Box<dyn Action>)),
+        Some(("Select All", Box::new(editor::actions::SelectAll))),
+        None,
+        Some(("Clear", Box::new(Clear))),
+    ]);
+    if has_errors {
+        entries.extend([
+            None,
+            Some((
+                "Go to First Error",
+                Box::new(GoToFirstErrorInOutput) as Box<dyn Action>,
+            )),
+            Some(("Go to Last Error", Box::new(GoToLastErrorInOutput))),
+        ]);
+    }
+    entries.extend([
+        None,
+        Some((
+            "Close",
+            Box::new(CloseActiveItem { save_intent: None }) as Box<dyn Action>,
+        )),
+    ]);
+    entries
+}
+
+/// Lays out the panes of a split terminal tab, with `this` as the pane of the view that is the tab.
+fn render_splits(
+    node: &SplitNode<SplitPane>,
+    this: &mut Option<AnyElement>,
+    cx: &ViewContext<TerminalView>,
+) -> AnyElement {
+    match node {
+        SplitNode::Pane(SplitPane::This) => this.take().unwrap_or_else(|| div().into_any_element()),
+        SplitNode::Pane(SplitPane::Other(view)) => view.clone().into_any_element(),
+        SplitNode::Split { axis, children } => {
+            let mut container = match axis {
+                SplitAxis::Horizontal => h_flex(),
+                SplitAxis::Vertical => v_flex(),
+            }
+            .size_full();
+            for (ix, child) in children.iter().enumerate() {
+                let child = render_splits(child, this, cx);
+                container = container.child(
+                    div()
+                        .flex_1()
+                        .overflow_hidden()
+                        .map(|pane| match axis {
+                            SplitAxis::Horizontal => {
+                                pane.h_full().when(ix > 0, |pane| pane.border_l_1())
+                            }
+                            SplitAxis::Vertical => {
+                                pane.w_full().when(ix > 0, |pane| pane.border_t_1())
+                            }
+                        })
+                        .border_color(cx.theme().colors().border)
+                        .child(child),
+                );
+            }
+            container.into_any_element()
+        }
+    }
+}
 
+impl TerminalView {
+    fn render_pane(&mut self, cx: &mut ViewContext<Self>) -> Div {
         div()
             .size_full()
             .relative()
             .track_focus(&self.focus_handle)
             .key_context(self.dispatch_context(cx))
-            .on_action(cx.listener(TerminalView::send_text))
+            .on_action(cx.listener(TerminalView::send_text_action))
             .on_action(cx.listener(TerminalView::send_keystroke))
+            .on_action(cx.listener(TerminalView::send_keys))
+            .on_action(cx.listener(TerminalView::clear_line))
+            .on_action(cx.listener(TerminalView::clear_to_end))
+            .on_action(cx.listener(TerminalView::paste_previous))
             .on_action(cx.listener(TerminalView::copy))
+            .on_action(cx.listener(TerminalView::copy_as_html))
+            .on_action(cx.listener(TerminalView::copy_as_ansi))
+            .on_action(cx.listener(TerminalView::copy_last_command_output))
+            .on_action(cx.listener(TerminalView::save_output))
             .on_action(cx.listener(TerminalView::paste))
             .on_action(cx.listener(TerminalView::clear))
+            .on_action(cx.listener(TerminalView::clear_scrollback))
+            .on_action(cx.listener(TerminalView::toggle_copy_mode))
+            .on_action(cx.listener(TerminalView::scroll_line_up))
+            .on_action(cx.listener(TerminalView::scroll_line_down))
+            .on_action(cx.listener(TerminalView::scroll_page_up))
+            .on_action(cx.listener(TerminalView::scroll_page_down))
+            .on_action(cx.listener(TerminalView::scroll_to_top))
+            .on_action(cx.listener(TerminalView::scroll_to_bottom))
+            .on_action(cx.listener(TerminalView::jump_to_previous_prompt))
+            .on_action(cx.listener(TerminalView::jump_to_next_prompt))
             .on_action(cx.listener(TerminalView::show_character_palette))
             .on_action(cx.listener(TerminalView::select_all))
+            .on_action(cx.listener(TerminalView::toggle_broadcast_input))
+            .on_action(cx.listener(TerminalView::go_to_first_error))
+            .on_action(cx.listener(TerminalView::go_to_last_error))
+            .on_action(cx.listener(TerminalView::copy_command_invocation))
+            .on_action(cx.listener(TerminalView::find_next))
+            .on_action(cx.listener(TerminalView::find_prev))
+            .on_action(cx.listener(TerminalView::restart))
+            .on_action(cx.listener(TerminalView::increase_font_size))
+            .on_action(cx.listener(TerminalView::decrease_font_size))
+            .on_action(cx.listener(TerminalView::reset_font_size))
+            .on_action(cx.listener(TerminalView::send_sig_int))
+            .on_action(cx.listener(TerminalView::send_sig_term))
+            .on_action(cx.listener(TerminalView::send_sig_kill))
+            .on_action(cx.listener(TerminalView::send_sig_hup))
+            .on_action(cx.listener(TerminalView::rename))
+            .on_action(cx.listener(TerminalView::deploy_in_current_dir))
+            .on_action(cx.listener(TerminalView::reveal_in_project_panel))
             .on_key_down(cx.listener(Self::key_down))
             .on_mouse_down(
                 MouseButton::Right,
@@ -755,15 +2147,23 @@ impl Render for TerminalView {
             )
             .child(
                 // TODO: Oddly this wrapper div is needed for TerminalElement to not steal events from the context menu
-                div().size_full().child(TerminalElement::new(
-                    terminal_handle,
-                    self.workspace.clone(),
-                    self.focus_handle.clone(),
-                    focused,
-                    self.should_show_cursor(focused, cx),
-                    self.can_navigate_to_selected_word,
-                )),
+                div().size_full().child(
+                    self.terminal_element(cx).id("terminal-element").on_hover(
+                        cx.listener(|this, hovered, cx| this.hover_changed(*hovered, cx)),
+                    ),
+                ),
             )
+            .when(self.broadcast_input, |div| {
+                div.child(
+                    h_flex()
+                        .absolute()
+                        .top_1()
+                        .right_2()
+                        .gap_1()
+                        .child(Icon::new(IconName::Link).color(Color::Warning))
+                        .child(Label::new("Broadcasting Input").color(Color::Warning)),
+                )
+            })
             .children(self.context_menu.as_ref().map(|(menu, position, _)| {
                 deferred(
                     anchored()
@@ -774,18 +2174,76 @@ impl Render for TerminalView {
                 .with_priority(1)
             }))
     }
+
+    /// The element that paints the terminal, as it looks with the view's focus and settings.
+    fn terminal_element(&self, cx: &mut ViewContext<Self>) -> TerminalElement {
+        let focused = self.focus_handle.is_focused(cx);
+        TerminalElement::new(
+            self.terminal.clone(),
+            self.workspace.clone(),
+            self.focus_handle.clone(),
+            focused,
+            self.should_show_cursor(focused, cx),
+            self.can_navigate_to_selected_word,
+            self.broadcast_peers(cx),
+            self.font_size_delta,
+            self.bell_flash.is_some(),
+        )
+    }
 }
 
 impl Item for TerminalView {
     type Event = ItemEvent;
 
     fn tab_tooltip_text(&self, cx: &AppContext) -> Option<SharedString> {
-        Some(self.terminal().read(cx).title(false).into())
+        Some(
+            self.terminal()
+                .read(cx)
+                .title(self.custom_title.as_deref(), None)
+                .into(),
+        )
+    }
+
+    fn close_confirmation(&self, cx: &AppContext) -> Option<String> {
+        if !TerminalSettings::get_global(cx).confirm_close_with_running_process {
+            return None;
+        }
+        let split_terminals = self
+            .splits
+            .iter()
+            .flat_map(|splits| splits.panes())
+            .filter_map(|pane| match pane {
+                SplitPane::This => None,
+                SplitPane::Other(view) => Some(view.read(cx).terminal.clone()),
+            });
+        let process_name = iter::once(self.terminal.clone())
+            .chain(split_terminals)
+            .find_map(|terminal| terminal.read(cx).running_process_name())?;
+        Some(format!(
+            "\"{process_name}\" is still running in this terminal. Close it anyway?"
+        ))
+    }
+
+    fn tab_description(&self, detail: usize, cx: &AppContext) -> Option<SharedString> {
+        let terminal = self.terminal().read(cx);
+        let title = terminal.title(
+            self.custom_title.as_deref(),
+            Some(TerminalSettings::get_global(cx).title_truncation),
+        );
+        let directory = self.tab_directory(cx);
+        Some(tab_description(&title, directory.as_deref(), detail).into())
     }
 
     fn tab_content(&self, params: TabContentParams, cx: &WindowContext) -> AnyElement {
         let terminal = self.terminal().read(cx);
-        let title = terminal.title(true);
+        let title = terminal.title(
+            self.custom_title.as_deref(),
+            Some(TerminalSettings::get_global(cx).title_truncation),
+        );
+        let description = params.detail.and_then(|detail| {
+            let directory = self.tab_directory(cx);
+            tab_directory_name(directory.as_deref(), detail)
+        });
         let icon = match terminal.task() {
             Some(terminal_task) => match &terminal_task.status {
                 TaskStatus::Unknown => IconName::ExclamationTriangle,
@@ -800,14 +2258,34 @@ impl Item for TerminalView {
             },
             None => IconName::Terminal,
         };
+        let indicator = tab_indicator(
+            terminal.task().is_none() && terminal.exit_status().is_some(),
+            self.has_bell,
+            self.has_activity,
+        );
         h_flex()
             .gap_2()
             .child(Icon::new(icon))
+            .when(self.broadcast_input, |tab| {
+                tab.child(Icon::new(IconName::Link).color(Color::Warning))
+            })
             .child(Label::new(title).color(if params.selected {
                 Color::Default
             } else {
                 Color::Muted
             }))
+            .when_some(description, |tab, description| {
+                tab.child(
+                    Label::new(description)
+                        .size(LabelSize::XSmall)
+                        .color(Color::Muted),
+                )
+            })
+            .when_some(indicator, |tab, indicator| match indicator {
+                TabIndicator::Exited => tab.child(Icon::new(IconName::Exit).color(Color::Muted)),
+                TabIndicator::Bell => tab.child(Icon::new(IconName::Bell).color(Color::Warning)),
+                TabIndicator::Activity => tab.child(Indicator::dot().color(Color::Accent)),
+            })
             .into_any()
     }
 
@@ -817,27 +2295,29 @@ impl Item for TerminalView {
 
     fn clone_on_split(
         &self,
-        _workspace_id: WorkspaceId,
-        _cx: &mut ViewContext<Self>,
+        workspace_id: WorkspaceId,
+        cx: &mut ViewContext<Self>,
     ) -> Option<View<Self>> {
-        //From what I can tell, there's no  way to tell the current working
-        //Directory of the terminal from outside the shell. There might be
-        //solutions to this, but they are non-trivial and require more IPC
-
-        // Some(TerminalContainer::new(
-        //     Err(anyhow::anyhow!("failed to instantiate terminal")),
-        //     workspace_id,
-        //     cx,
-        // ))
-
-        // TODO
-        None
+        let terminal = self.terminal.read(cx);
+        let working_directory = terminal
+            .current_working_directory()
+            .or_else(|| terminal.associated_directory());
+        let project = self.workspace.upgrade()?.read(cx).project().clone();
+        let window = cx.window_handle();
+        let terminal = project
+            .update(cx, |project, cx| {
+                project.create_terminal(working_directory, None, window, cx)
+            })
+            .log_err()?;
+        Some(
+            cx.new_view(|cx| TerminalView::new(terminal, self.workspace.clone(), workspace_id, cx)),
+        )
     }
 
     fn is_dirty(&self, cx: &gpui::AppContext) -> bool {
         match self.terminal.read(cx).task() {
             Some(task) => task.status == TaskStatus::Running,
-            None => self.has_bell(),
+            None => false,
         }
     }
 
@@ -892,12 +2372,24 @@ impl Item for TerminalView {
                     .flatten()
                 })
                 .filter(|cwd| !cwd.as_os_str().is_empty());
+            let custom_title = TERMINAL_DB
+                .get_title(item_id, workspace_id)
+                .log_err()
+                .flatten()
+                .filter(|title| !title.is_empty());
 
             let terminal = project.update(&mut cx, |project, cx| {
                 project.create_terminal(cwd, None, window, cx)
             })??;
             pane.update(&mut cx, |_, cx| {
-                cx.new_view(|cx| TerminalView::new(terminal, workspace, workspace_id, cx))
+                cx.new_view(|cx| {
+                    let mut view = TerminalView::new(terminal, workspace, workspace_id, cx);
+                    // Saved again, as the restored view has a new item id
+                    if custom_title.is_some() {
+                        view.set_custom_title(custom_title, cx);
+                    }
+                    view
+                })
             })
         })
     }
@@ -934,23 +2426,22 @@ impl SearchableItem for TerminalView {
 
     /// Clear stored matches
     fn clear_matches(&mut self, cx: &mut ViewContext<Self>) {
-        self.terminal().update(cx, |term, _| term.matches.clear())
+        self.terminal().update(cx, |term, _| term.clear_matches())
     }
 
     /// Store matches returned from find_matches somewhere for rendering
     fn update_matches(&mut self, matches: &[Self::Match], cx: &mut ViewContext<Self>) {
         self.terminal()
-            .update(cx, |term, _| term.matches = matches.to_vec())
+            .update(cx, |term, _| term.set_matches(matches.to_vec()))
     }
 
     /// Returns the selection content to pre-load into this search
     fn query_suggestion(&mut self, cx: &mut ViewContext<Self>) -> String {
         self.terminal()
             .read(cx)
-            .last_content
-            .selection_text
-            .clone()
+            .selection_text()
             .unwrap_or_default()
+            .to_string()
     }
 
     /// Focus match at given index into the Vec of matches
@@ -1043,19 +2534,90 @@ pub fn get_working_directory(
     cx: &AppContext,
     strategy: WorkingDirectory,
 ) -> Option<PathBuf> {
-    let res = match strategy {
+    resolve_working_directory(workspace, cx, strategy)
+        .log_err()
+        .flatten()
+        .or_else(home_dir)
+}
+
+/// Like `get_working_directory`, but tells the user with a toast when the configured directory
+/// can't be used and the home directory is used instead.
+pub fn working_directory_or_notify(
+    workspace: &mut Workspace,
+    cx: &mut ViewContext<Workspace>,
+    strategy: WorkingDirectory,
+) -> Option<PathBuf> {
+    match resolve_working_directory(workspace, cx, strategy) {
+        Ok(directory) => directory.or_else(home_dir),
+        Err(error) => {
+            let id = NotificationId::identified::<TerminalNotification>((
+                "working-directory",
+                cx.entity_id(),
+            ));
+            let message = format!("{error:#}, so the terminal was opened in the home directory.");
+            workspace.show_toast(Toast::new(id, message), cx);
+            home_dir()
+        }
+    }
+}
+
+fn resolve_working_directory(
+    workspace: &Workspace,
+    cx: &AppContext,
+    strategy: WorkingDirectory,
+) -> anyhow::Result<Option<PathBuf>> {
+    Ok(match strategy {
         WorkingDirectory::CurrentProjectDirectory => current_project_directory(workspace, cx)
             .or_else(|| first_project_directory(workspace, cx)),
         WorkingDirectory::FirstProjectDirectory => first_project_directory(workspace, cx),
+        WorkingDirectory::ActiveFileDirectory => active_file_directory(workspace, cx)
+            .or_else(|| current_project_directory(workspace, cx))
+            .or_else(|| first_project_directory(workspace, cx)),
         WorkingDirectory::AlwaysHome => None,
-        WorkingDirectory::Always { directory } => {
-            shellexpand::full(&directory) //TODO handle this better
-                .ok()
-                .map(|dir| Path::new(&dir.to_string()).to_path_buf())
-                .filter(|dir| dir.is_dir())
-        }
+        WorkingDirectory::Always { directory } => Some(expand_directory(
+            &directory,
+            first_project_directory(workspace, cx).as_deref(),
+            |name| env::var(name),
+        )?),
+    })
+}
+
+/// Expands `~` and the environment variables `env_var` looks up in a configured directory,
+/// resolving relative paths against `project_root`, or the home directory without one. Fails if
+/// the directory doesn't exist.
+fn expand_directory(
+    directory: &str,
+    project_root: Option<&Path>,
+    env_var: impl Fn(&str) -> Result<String, env::VarError>,
+) -> anyhow::Result<PathBuf> {
+    let home = || home_dir().and_then(|home| home.into_os_string().into_string().ok());
+    let expanded = shellexpand::full_with_context(directory, home, |name| env_var(name).map(Some))
+        .with_context(|| format!("Couldn't expand the working directory {directory}"))?;
+    let path = Path::new(expanded.as_ref());
+    let path = match project_root.map(Path::to_path_buf).or_else(home_dir) {
+        Some(root) if path.is_relative() => root.join(path),
+        _ => path.to_path_buf(),
     };
-    res.or_else(home_dir)
+    let path = path
+        .canonicalize()
+        .with_context(|| format!("The working directory {} doesn't exist", path.display()))?;
+    anyhow::ensure!(
+        path.is_dir(),
+        "The working directory {} isn't a directory",
+        path.display()
+    );
+    Ok(path)
+}
+
+/// The directory to start a terminal opened from another one in: the directory the shell in that
+/// one last reported with OSC 7, or the one `strategy` picks if it hasn't.
+fn directory_for_new_terminal(
+    current_dir: Option<PathBuf>,
+    workspace: &Workspace,
+    cx: &AppContext,
+    strategy: WorkingDirectory,
+) -> Option<PathBuf> {
+    current_dir.or_else(|| get_working_directory(workspace, cx, strategy))
 }
 
 ///Gets the first project's home directory, or the home directory
@@ -1083,6 +2645,59 @@ fn current_project_directory(workspace: &Workspace, cx: &AppContext) -> Option<P
         .and_then(get_path_from_wt)
 }
 
+/// The project panel entry for `path`, or for its closest ancestor that has one, when it's inside
+/// one of the workspace's worktrees.
+fn worktree_entry_for_path(
+    workspace: &Workspace,
+    path: &Path,
+    cx: &AppContext,
+) -> Option<ProjectEntryId> {
+    workspace.worktrees(cx).find_map(|worktree| {
+        let worktree = worktree.read(cx);
+        let relative_path = path.strip_prefix(worktree.abs_path()).ok()?;
+        relative_path
+            .ancestors()
+            .find_map(|ancestor| worktree.entry_for_path(ancestor))
+            .map(|entry| entry.id)
+    })
+}
+
+///Gets the directory of the file open in the active item, if it's a file in a local worktree
+fn active_file_directory(workspace: &Workspace, cx: &AppContext) -> Option<PathBuf> {
+    let project_path = workspace.active_item(cx)?.project_path(cx)?;
+    let worktree = workspace
+        .project()
+        .read(cx)
+        .worktree_for_id(project_path.worktree_id, cx)?;
+    let abs_path = worktree
+        .read(cx)
+        .as_local()?
+        .absolutize(&project_path.path)
+        .ok()?;
+    abs_path.parent().map(Path::to_path_buf)
+}
+
+/// Formats a command as `cd <cwd> && <command>`, leaving out the `cd` when the command was run
+/// from the root of the project.
+fn command_invocation(command: &str, cwd: Option<&Path>, project_roots: &[PathBuf]) -> String {
+    match cwd {
+        Some(cwd) if !project_roots.iter().any(|root| root == cwd) => {
+            format!("cd {} && {command}", shell_quote(&cwd.to_string_lossy()))
+        }
+        _ => command.to_string(),
+    }
+}
+
+/// Quotes a string for POSIX shells, if it needs quoting.
+fn shell_quote(text: &str) -> String {
+    let is_safe = |c: char| c.is_alphanumeric() || "/._-~+=:@%,".contains(c);
+    if !text.is_empty() && text.chars().all(is_safe) && !text.starts_with('~') {
+        text.to_string()
+    } else {
+        format!("'{}'", text.replace('\'', r"'\''"))
+    }
+}
+
 fn get_path_from_wt(wt: &LocalWorktree) -> Option<PathBuf> {
     wt.root_entry()
         .filter(|re| re.is_dir())
@@ -1092,10 +2707,14 @@ fn get_path_from_wt(wt: &LocalWorktree) -> Option<PathBuf> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use gpui::TestAppContext;
+    use gpui::{TestAppContext, VisualTestContext};
     use project::{Entry, Project, ProjectPath, Worktree};
     use std::path::Path;
-    use workspace::AppState;
+    use terminal::{mappings::keys::to_esc_str, terminal_settings::TerminalBell};
+    use workspace::{
+        item::test::{TestItem, TestProjectItem},
+        AppState,
+    };
 
     // Working directory calculation tests
 
@@ -1203,6 +2822,114 @@ mod tests {
         });
     }
 
+    // Active item is a file -> the file's directory
+    #[gpui::test]
+    async fn active_item_is_file(cx: &mut TestAppContext) {
+        let (project, workspace) = init_test(cx).await;
+
+        let (wt, _entry) = create_folder_wt(project.clone(), "/root/", cx).await;
+        let project_path = cx.update(|cx| ProjectPath {
+            worktree_id: wt.read(cx).id(),
+            path: Path::new("src/main.rs").into(),
+        });
+        add_active_item(&workspace, Some(project_path), cx);
+
+        cx.update(|cx| {
+            let workspace = workspace.read(cx);
+
+            let res = active_file_directory(workspace, cx);
+            assert_eq!(res, Some(PathBuf::from("/root/src")));
+            let res = get_working_directory(workspace, cx, WorkingDirectory::ActiveFileDirectory);
+            assert_eq!(res, Some(PathBuf::from("/root/src")));
+        });
+    }
+
+    // Active item isn't a file -> the current project directory
+    #[gpui::test]
+    async fn active_item_is_not_file(cx: &mut TestAppContext) {
+        let (project, workspace) = init_test(cx).await;
+
+        create_folder_wt(project.clone(), "/root/", cx).await;
+        add_active_item(&workspace, None, cx);
+
+        cx.update(|cx| {
+            let workspace = workspace.read(cx);
+
+            assert!(workspace.active_item(cx).is_some());
+            let res = active_file_directory(workspace, cx);
+            assert_eq!(res, None);
+            let res = get_working_directory(workspace, cx, WorkingDirectory::ActiveFileDirectory);
+            assert_eq!(res, Some(PathBuf::from("/root/")));
+        });
+    }
+
+    // No active item -> the current project directory
+    #[gpui::test]
+    async fn no_active_item(cx: &mut TestAppContext) {
+        let (project, workspace) = init_test(cx).await;
+
+        create_folder_wt(project.clone(), "/root/", cx).await;
+
+        cx.update(|cx| {
+            let workspace = workspace.read(cx);
+
+            assert!(workspace.active_item(cx).is_none());
+            let res = active_file_directory(workspace, cx);
+            assert_eq!(res, None);
+            let res = get_working_directory(workspace, cx, WorkingDirectory::ActiveFileDirectory);
+            assert_eq!(res, Some(PathBuf::from("/root/")));
+        });
+    }
+
+    // The terminal's directory resolves to its entry in the worktree containing it
+    #[gpui::test]
+    async fn worktree_entry_for_terminal_directory(cx: &mut TestAppContext) {
+        let (project, workspace) = init_test(cx).await;
+
+        let (wt, root_entry) = create_folder_wt(project.clone(), "/root/", cx).await;
+        let src_entry = cx
+            .update(|cx| {
+                wt.update(cx, |wt, cx| {
+                    wt.as_local()
+                        .unwrap()
+                        .create_entry(Path::new("src"), true, cx)
+                })
+            })
+            .await
+            .unwrap()
+            .unwrap();
+
+        cx.update(|cx| {
+            let workspace = workspace.read(cx);
+
+            let res = worktree_entry_for_path(workspace, Path::new("/root/src"), cx);
+            assert_eq!(res, Some(src_entry.id));
+            let res = worktree_entry_for_path(workspace, Path::new("/root/src/unscanned"), cx);
+            assert_eq!(res, Some(src_entry.id));
+            let res = worktree_entry_for_path(workspace, Path::new("/root"), cx);
+            assert_eq!(res, Some(root_entry.id));
+            let res = worktree_entry_for_path(workspace, Path::new("/elsewhere"), cx);
+            assert_eq!(res, None);
+        });
+    }
+
+    /// Adds an item to the workspace's active pane, a file at `project_path` if there is one
+    fn add_active_item(
+        workspace: &View<Workspace>,
+        project_path: Option<ProjectPath>,
+        cx: &mut TestAppContext,
+    ) {
+        let cx = &mut VisualTestContext::from_window(cx.windows()[0], cx);
+        workspace.update(cx, |workspace, cx| {
+            let project_item = cx.new_model(|_| TestProjectItem {
+                entry_id: None,
+                project_path,
+            });
+            let item = cx.new_view(|cx| TestItem::new(cx).with_project_items(&[project_item]));
+            workspace.add_item_to_active_pane(Box::new(item), cx);
+        });
+    }
+
     /// Creates a worktree with 1 file: /root.txt
     pub async fn init_test(cx: &mut TestAppContext) -> (Model<Project>, View<Workspace>) {
         let params = cx.update(AppState::test);
@@ -1221,6 +2948,56 @@ mod tests {
         (project, workspace)
     }
 
+    /// Opens a terminal running the default shell in the workspace's active pane
+    pub fn add_terminal_view(
+        project: &Model<Project>,
+        workspace: &View<Workspace>,
+        cx: &mut TestAppContext,
+    ) -> View<TerminalView> {
+        cx.update(terminal::init);
+        let window = cx.windows()[0];
+        let terminal = project
+            .update(cx, |project, cx| {
+                project.create_terminal(None, None, window, cx)
+            })
+            .unwrap();
+        let cx = &mut VisualTestContext::from_window(window, cx);
+        workspace.update(cx, |workspace, cx| {
+            let workspace_id = workspace.database_id();
+            let weak_workspace = cx.view().downgrade();
+            let view =
+                cx.new_view(|cx| TerminalView::new(terminal, weak_workspace, workspace_id, cx));
+            workspace.add_item_to_active_pane(Box::new(view.clone()), cx);
+            view
+        })
+    }
+
+    // A terminal opened from another one starts in the directory that one's shell reported
+    #[gpui::test]
+    async fn new_terminal_in_current_dir(cx: &mut TestAppContext) {
+        let (project, workspace) = init_test(cx).await;
+        create_folder_wt(project.clone(), "/root/", cx).await;
+
+        cx.update(|cx| {
+            let workspace = workspace.read(cx);
+
+            let res = directory_for_new_terminal(
+                Some(PathBuf::from("/root/src")),
+                workspace,
+                cx,
+                WorkingDirectory::FirstProjectDirectory,
+            );
+            assert_eq!(res, Some(PathBuf::from("/root/src")));
+            let res = directory_for_new_terminal(
+                None,
+                workspace,
+                cx,
+                WorkingDirectory::FirstProjectDirectory,
+            );
+            assert_eq!(res, Some(PathBuf::from("/root/")));
+        });
+    }
+
     /// Creates a worktree with 1 folder: /root{suffix}/
     async fn create_folder_wt(
         project: Model<Project>,
@@ -1282,6 +3059,263 @@ mod tests {
         });
     }
 
+    #[test]
+    fn always_directory_expansion() {
+        let temp_dir = std::env::temp_dir().canonicalize().unwrap();
+        let env_var = |name: &str| match name {
+            "TEST_DIR" => Ok(temp_dir.to_string_lossy().into_owned()),
+            _ => Err(env::VarError::NotPresent),
+        };
+
+        let home = home_dir().unwrap().canonicalize().unwrap();
+        assert_eq!(expand_directory("~", None, env_var).unwrap(), home);
+        assert_eq!(
+            expand_directory("$TEST_DIR", None, env_var).unwrap(),
+            temp_dir
+        );
+
+        // Relative directories are in the project
+        let (root, name) = (temp_dir.parent().unwrap(), temp_dir.file_name().unwrap());
+        assert_eq!(
+            expand_directory(&name.to_string_lossy(), Some(root), env_var).unwrap(),
+            temp_dir
+        );
+
+        assert!(expand_directory("/zed/nonexistent/directory", None, env_var).is_err());
+        assert!(expand_directory("$UNSET_VARIABLE", None, env_var).is_err());
+    }
+
+    #[test]
+    fn activity_indicator_sets_in_background() {
+        let has_activity = activity_after_output(false, true);
+        assert_eq!(tab_indicator(false, false, has_activity), None);
+
+        let has_activity = activity_after_output(has_activity, false);
+        assert_eq!(
+            tab_indicator(false, false, has_activity),
+            Some(TabIndicator::Activity)
+        );
+        let has_activity = activity_after_output(has_activity, false);
+        assert!(has_activity);
+
+        assert_eq!(tab_indicator(false, true, true), Some(TabIndicator::Bell));
+        assert_eq!(tab_indicator(true, true, true), Some(TabIndicator::Exited));
+    }
+
+    #[gpui::test]
+    async fn activity_indicator_clears_on_focus(cx: &mut TestAppContext) {
+        let (project, workspace) = init_test(cx).await;
+        let view = add_terminal_view(&project, &workspace, cx);
+        let terminal = view.read_with(cx, |view, _| view.terminal.clone());
+        let cx = &mut VisualTestContext::from_window(cx.windows()[0], cx);
+
+        cx.update(|cx| cx.blur());
+        cx.run_until_parked();
+        terminal.update(cx, |_, cx| cx.emit(Event::Wakeup));
+        cx.run_until_parked();
+        view.read_with(cx, |view, _| assert!(view.has_activity));
+
+        cx.focus_view(&view);
+        cx.run_until_parked();
+        view.read_with(cx, |view, _| assert!(!view.has_activity));
+    }
+
+    #[test]
+    fn spawn_terminal_command_action() {
+        let action: SpawnTerminalCommand =
+            serde_json::from_str(r#"{"command": "cargo build"}"#).unwrap();
+        assert_eq!(
+            action,
+            SpawnTerminalCommand {
+                command: "cargo build".into(),
+                working_directory: None,
+                reveal: true,
+            }
+        );
+        let action: SpawnTerminalCommand = serde_json::from_str(
+            r#"{"command": "make", "working_directory": "~/src", "reveal": false}"#,
+        )
+        .unwrap();
+        assert_eq!(action.working_directory.as_deref(), Some("~/src"));
+        assert!(!action.reveal);
+
+        // The command is run once, however it ends
+        assert_eq!(command_line("cargo build"), "cargo build\r");
+        assert_eq!(command_line("cargo build\n"), "cargo build\r");
+        assert_eq!(command_line("cargo build\r\n"), "cargo build\r");
+    }
+
+    #[test]
+    fn line_edits_send_control_bytes() {
+        let bytes = |keystroke: &str, mode| {
+            let keystroke = Keystroke::parse(&line_edit_keystroke(keystroke, mode)?).unwrap();
+            to_esc_str(&keystroke, &mode, false)
+        };
+        assert_eq!(bytes("ctrl-u", TermMode::NONE).as_deref(), Some("\x15"));
+        assert_eq!(bytes("ctrl-k", TermMode::NONE).as_deref(), Some("\x0b"));
+        // Full screen programs have their own uses for these
+        assert_eq!(bytes("ctrl-u", TermMode::ALT_SCREEN), None);
+        assert_eq!(bytes("ctrl-k", TermMode::ALT_SCREEN), None);
+    }
+
+    #[test]
+    fn pasted_text_is_erased_by_character() {
+        assert_eq!(erase_text("ls"), "\x7f\x7f");
+        assert_eq!(erase_text("café 🦀"), "\x7f".repeat(6));
+        assert_eq!(erase_text(""), "");
+    }
+
+    #[test]
+    fn range_ends_split_off_paths() {
+        assert_eq!(
+            split_range_end("src/main.rs:10:5-10:20"),
+            ("src/main.rs:10:5", Some((Some(10), 20)))
+        );
+        assert_eq!(
+            split_range_end("src/main.rs:10:5-12:1"),
+            ("src/main.rs:10:5", Some((Some(12), 1)))
+        );
+        assert_eq!(
+            split_range_end("src/main.rs:10:5-20"),
+            ("src/main.rs:10:5", Some((None, 20)))
+        );
+        // Dashes in file names aren't ranges
+        for path in [
+            "src/terminal-view.rs",
+            "src/terminal-view.rs:10:5",
+            "terminal-2.rs",
+            "src/main.rs:10-12",
+            "src/main.rs:10:5-",
+            "src/main.rs:10:5-a:b",
+        ] {
+            assert_eq!(split_range_end(path), (path, None));
+        }
+    }
+
+    #[test]
+    fn tab_descriptions_include_directory_with_detail() {
+        let first = Path::new("/home/me/zed");
+        let second = Path::new("/home/me/site");
+        assert_eq!(tab_description("zsh", Some(first), 0), "zsh");
+        assert_eq!(
+            tab_description("zsh", Some(first), 0),
+            tab_description("zsh", Some(second), 0)
+        );
+
+        assert_eq!(tab_description("zsh", Some(first), 1), "zsh zed");
+        assert_eq!(tab_description("zsh", Some(second), 1), "zsh site");
+        assert_eq!(tab_directory_name(Some(first), 0), None);
+        assert_eq!(tab_directory_name(Some(first), 1).as_deref(), Some("zed"));
+        assert_eq!(
+            tab_directory_name(Some(Path::new("/")), 1).as_deref(),
+            Some("/")
+        );
+        assert_eq!(tab_directory_name(None, 1), None);
+    }
+
+    #[test]
+    fn hover_focus_waits_for_the_delay() {
+        assert_eq!(
+            hover_focus_delay(true, false, false),
+            Some(FOCUS_FOLLOWS_MOUSE_DELAY)
+        );
+        assert!(FOCUS_FOLLOWS_MOUSE_DELAY > Duration::ZERO);
+        // Off by default, already focused, or a modal is open
+        assert_eq!(hover_focus_delay(false, false, false), None);
+        assert_eq!(hover_focus_delay(true, true, false), None);
+        assert_eq!(hover_focus_delay(true, false, true), None);
+    }
+
+    #[test]
+    fn focus_terminal_targets() {
+        let now = Instant::now();
+        let later = now + Duration::from_secs(1);
+        assert_eq!(
+            focus_terminal_target(false, &[]),
+            FocusTerminalTarget::Deploy
+        );
+        assert_eq!(
+            focus_terminal_target(false, &[Some(later), None, Some(now)]),
+            FocusTerminalTarget::Terminal(0)
+        );
+        // Terminals that were never focused can still be
+        assert!(matches!(
+            focus_terminal_target(false, &[None, None]),
+            FocusTerminalTarget::Terminal(_)
+        ));
+        assert_eq!(
+            focus_terminal_target(true, &[Some(now)]),
+            FocusTerminalTarget::ReturnFocus
+        );
+    }
+
+    #[test]
+    fn bell_behaviors() {
+        assert!(TerminalBell::Visual.is_visual() && !TerminalBell::Visual.is_audible());
+        assert!(!TerminalBell::Audible.is_visual() && TerminalBell::Audible.is_audible());
+        assert!(TerminalBell::Both.is_visual() && TerminalBell::Both.is_audible());
+        assert!(!TerminalBell::None.is_visual() && !TerminalBell::None.is_audible());
+    }
+
+    #[gpui::test]
+    async fn visual_bell_flashes_until_the_timer_ends(cx: &mut TestAppContext) {
+        let (project, workspace) = init_test(cx).await;
+        let view = add_terminal_view(&project, &workspace, cx);
+        let terminal = view.read_with(cx, |view, _| view.terminal.clone());
+
+        // By default, only the tab shows the bell
+        terminal.update(cx, |_, cx| cx.emit(Event::Bell));
+        cx.run_until_parked();
+        view.read_with(cx, |view, _| {
+            assert!(view.has_bell);
+            assert!(view.bell_flash.is_none());
+        });
+
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<TerminalSettings>(cx, |settings| {
+                settings.bell = Some(TerminalBell::Visual);
+            });
+        });
+        terminal.update(cx, |_, cx| cx.emit(Event::Bell));
+        cx.run_until_parked();
+        view.read_with(cx, |view, _| assert!(view.bell_flash.is_some()));
+
+        cx.executor().advance_clock(BELL_FLASH_DURATION);
+        cx.run_until_parked();
+        view.read_with(cx, |view, _| assert!(view.bell_flash.is_none()));
+    }
+
+    #[test]
+    fn context_menu_entries_dispatch_actions() {
+        let entries = |has_selection, has_errors| {
+            context_menu_entries(has_selection, has_errors)
+                .into_iter()
+                .map(|entry| entry.map(|(label, action)| (label, action.name())))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            entries(true, false),
+            [
+                Some(("Copy", "terminal::Copy")),
+                Some(("Paste", "terminal::Paste")),
+                Some(("Select All", "editor::SelectAll")),
+                None,
+                Some(("Clear", "terminal::Clear")),
+                None,
+                Some(("Close", "pane::CloseActiveItem")),
+            ]
+        );
+
+        // Without a selection there's nothing to copy
+        let without_selection = entries(false, true);
+        assert!(!without_selection.contains(&Some(("Copy", "terminal::Copy"))));
+        assert!(without_selection.contains(&Some((
+            "Go to First Error",
+            "terminal::GoToFirstErrorInOutput"
+        ))));
+    }
+
     #[test]
     fn escapes_only_special_characters() {
         assert_eq!(regex_to_literal(r"test(\w)"), r"test\(\\w\)".to_string());
@@ -1291,4 +3325,39 @@ mod tests {
     fn empty_string_stays_empty() {
         assert_eq!(regex_to_literal(""), "".to_string());
     }
+
+    #[test]
+    fn finds_terminal_settings_key() {
+        let settings = r#"{
+  "theme": "terminal",
+  "terminal" : {
+    "shell": "system"
+  }
+}"#;
+        assert_eq!(terminal_settings_offset(settings), Some(27));
+        assert_eq!(terminal_settings_offset(r#"{"theme": "terminal"}"#), None);
+        assert_eq!(terminal_settings_offset(""), None);
+    }
+
+    #[test]
+    fn formats_command_invocations() {
+        let roots = [PathBuf::from("/home/me/zed")];
+        assert_eq!(
+            command_invocation("cargo test", Some(Path::new("/home/me/zed")), &roots),
+            "cargo test"
+        );
+        assert_eq!(
+            command_invocation(
+                "cargo test",
+                Some(Path::new("/home/me/zed/crates/gpui")),
+                &roots
+            ),
+            "cd /home/me/zed/crates/gpui && cargo test"
+        );
+        assert_eq!(
+            command_invocation("ls", Some(Path::new("/home/me/My Project's")), &roots),
+            r"cd '/home/me/My Project'\''s' && ls"
+        );
+        assert_eq!(command_invocation("ls", None, &roots), "ls");
+    }
 }
@@ -63,6 +63,7 @@ lazy_static::lazy_static! {
     pub static ref OLD_LOG: PathBuf = LOGS_DIR.join("Zed.log.old");
     pub static ref LOCAL_SETTINGS_RELATIVE_PATH: &'static Path = Path::new(".zed/settings.json");
     pub static ref LOCAL_TASKS_RELATIVE_PATH: &'static Path = Path::new(".zed/tasks.json");
+    pub static ref LOCAL_ENV_RELATIVE_PATH: &'static Path = Path::new(".zed/env");
     pub static ref LOCAL_VSCODE_TASKS_RELATIVE_PATH: &'static Path = Path::new(".vscode/tasks.json");
     pub static ref TEMP_DIR: PathBuf = if cfg!(target_os = "widows") {
         dirs::data_local_dir()
@@ -167,6 +168,7 @@ impl<P> PathLikeWithPosition<P> {
     /// Parses a string that possibly has `:row:column` suffix.
     /// Ignores trailing `:`s, so `test.rs:22:` is parsed as `test.rs:22`.
     /// If any of the row/column component parsing fails, the whole string is then parsed as a path like.
+    /// The `:` after a Windows drive letter, as in `C:\test.rs:22`, is part of the path.
     pub fn parse_str<E>(
         s: &str,
         parse_path_like_str: impl Fn(&str) -> Result<P, E>,
@@ -179,9 +181,11 @@ impl<P> PathLikeWithPosition<P> {
             })
         };
 
-        match s.trim().split_once(FILE_ROW_COLUMN_DELIMITER) {
+        let trimmed = s.trim();
+        let drive_len = windows_drive_prefix_len(trimmed);
+        match trimmed[drive_len..].split_once(FILE_ROW_COLUMN_DELIMITER) {
             Some((path_like_str, maybe_row_and_col_str)) => {
-                let path_like_str = path_like_str.trim();
+                let path_like_str = trimmed[..drive_len + path_like_str.len()].trim();
                 let maybe_row_and_col_str = maybe_row_and_col_str.trim();
                 if path_like_str.is_empty() {
                     fallback(s)
@@ -301,6 +305,14 @@ impl PathMatcher {
     }
 }
 
+/// The length of the drive prefix of an absolute Windows path, like `C:\`, without its separator.
+fn windows_drive_prefix_len(path: &str) -> usize {
+    match path.as_bytes() {
+        [drive, b':', b'\\' | b'/', ..] if drive.is_ascii_alphabetic() => 2,
+        _ => 0,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -375,6 +387,40 @@ mod tests {
         }
     }
 
+    #[test]
+    fn path_with_position_parsing_windows_drive() {
+        let input_and_expected = [
+            (r"C:\test_file.rs", r"C:\test_file.rs", None, None),
+            (r"C:\test_file.rs:1", r"C:\test_file.rs", Some(1), None),
+            (
+                r"c:\src\test_file.rs:1:2",
+                r"c:\src\test_file.rs",
+                Some(1),
+                Some(2),
+            ),
+            (
+                "D:/src/test_file.rs:1:2:",
+                "D:/src/test_file.rs",
+                Some(1),
+                Some(2),
+            ),
+            (r"C:\test_file.rs:a", r"C:\test_file.rs:a", None, None),
+        ];
+
+        for (input, path_like, row, column) in input_and_expected {
+            let expected = PathLikeWithPosition {
+                path_like: path_like.to_string(),
+                row,
+                column,
+            };
+            assert_eq!(
+                parse_str(input),
+                expected,
+                "For Windows drive input str '{input}', got a parse mismatch"
+            );
+        }
+    }
+
     // Trim off trailing `:`s for otherwise valid input.
     #[test]
     fn path_with_position_parsing_special() {
@@ -0,0 +1,126 @@
+use gpui::Global;
+use std::collections::VecDeque;
+
+/// How many copied texts the ring remembers.
+const CAPACITY: usize = 16;
+
+/// The texts most recently copied in the editor and terminals, newest first. Right after
+/// pasting one, pasting can go back to older ones, like Emacs' yank-pop.
+///
+/// It's shared by every workspace, like the clipboard itself.
+#[derive(Debug, Default)]
+pub struct ClipboardRing {
+    entries: VecDeque<String>,
+    /// The index of the entry last pasted, until something else happens.
+    pasted: Option<usize>,
+}
+
+impl Global for ClipboardRing {}
+
+impl ClipboardRing {
+    /// Remembers copied text as the newest entry, and stops cycling through the ring.
+    pub fn record(&mut self, text: String) {
+        self.pasted = None;
+        if text.is_empty() {
+            return;
+        }
+        self.entries.retain(|entry| *entry != text);
+        self.entries.push_front(text);
+        self.entries.truncate(CAPACITY);
+    }
+
+    /// Starts cycling through the ring, after pasting `text` from the clipboard. It's recorded
+    /// too, in case it was copied outside of Zed.
+    pub fn paste(&mut self, text: &str) {
+        self.record(text.to_string());
+        if !text.is_empty() {
+            self.pasted = Some(0);
+        }
+    }
+
+    /// The entry older than the one last pasted, which is to replace it. After the oldest entry
+    /// comes the newest again. `None` unless the last thing done was pasting.
+    pub fn paste_previous(&mut self) -> Option<&str> {
+        let pasted = (self.pasted? + 1) % self.entries.len();
+        self.pasted = Some(pasted);
+        Some(&self.entries[pasted])
+    }
+
+    /// Stops cycling through the ring, after anything other than pasting, like typing.
+    pub fn reset(&mut self) {
+        self.pasted = None;
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cycling() {
+        let mut ring = ClipboardRing::default();
+        assert_eq!(ring.paste_previous(), None);
+
+        ring.record("one".into());
+        ring.record("two".into());
+        ring.record("three".into());
+        // Nothing was pasted yet
+        assert_eq!(ring.paste_previous(), None);
+
+        ring.paste("three");
+        assert_eq!(ring.paste_previous(), Some("two"));
+        assert_eq!(ring.paste_previous(), Some("one"));
+        assert_eq!(ring.paste_previous(), Some("three"));
+
+        // Text copied elsewhere joins the ring when it's pasted
+        ring.paste("four");
+        assert_eq!(
+            ring.entries().collect::<Vec<_>>(),
+            ["four", "three", "two", "one"]
+        );
+        assert_eq!(ring.paste_previous(), Some("three"));
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut ring = ClipboardRing::default();
+        ring.record("one".into());
+        ring.record("two".into());
+
+        ring.paste("two");
+        ring.reset();
+        assert_eq!(ring.paste_previous(), None);
+
+        // Copying also ends the cycle
+        ring.paste("two");
+        ring.record("three".into());
+        assert_eq!(ring.paste_previous(), None);
+
+        // Pasting again starts over from the newest entry
+        ring.paste("three");
+        assert_eq!(ring.paste_previous(), Some("two"));
+    }
+
+    #[test]
+    fn test_entries() {
+        let mut ring = ClipboardRing::default();
+        ring.record("one".into());
+        ring.record(String::new());
+        ring.record("two".into());
+        ring.record("one".into());
+        assert_eq!(ring.entries().collect::<Vec<_>>(), ["one", "two"]);
+
+        for i in 0..CAPACITY + 4 {
+            ring.record(i.to_string());
+        }
+        assert_eq!(ring.entries().count(), CAPACITY);
+        assert_eq!(
+            ring.entries().next(),
+            Some((CAPACITY + 3).to_string().as_str())
+        );
+    }
+}
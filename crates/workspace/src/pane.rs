@@ -1086,6 +1086,19 @@ impl Pane {
                     continue;
                 };
 
+                let close_confirmation = cx.update(|cx| item.close_confirmation(cx))?;
+                if let Some(message) =
+                    close_confirmation.filter(|_| save_intent != SaveIntent::Skip)
+                {
+                    let answer = pane.update(&mut cx, |pane, cx| {
+                        pane.activate_item(item_ix, true, true, cx);
+                        cx.prompt(PromptLevel::Warning, &message, None, &["Close", "Cancel"])
+                    })?;
+                    if !matches!(answer.await, Ok(0)) {
+                        break;
+                    }
+                }
+
                 // Check if this view has any project items that are not open anywhere else
                 // in the workspace, AND that the user has not already been prompted to save.
                 // If there are any such project entries, prompt the user to save this item.
@@ -2748,6 +2761,58 @@ mod tests {
         assert_item_labels(&pane, [], cx);
     }
 
+    #[gpui::test]
+    async fn test_close_item_with_close_confirmation(cx: &mut TestAppContext) {
+        init_test(cx);
+        let fs = FakeFs::new(cx.executor());
+
+        let project = Project::test(fs, None, cx).await;
+        let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project.clone(), cx));
+        let pane = workspace.update(cx, |workspace, _| workspace.active_pane().clone());
+
+        add_labeled_item(&pane, "A", false, cx);
+        pane.update(cx, |pane, cx| {
+            let item = cx.new_view(|cx| {
+                TestItem::new(cx)
+                    .with_label("B")
+                    .with_close_confirmation("A process is running")
+            });
+            pane.add_item(Box::new(item), false, false, None, cx);
+        });
+        assert_item_labels(&pane, ["A", "B*"], cx);
+
+        // Cancelling the prompt keeps the item open
+        let close = pane
+            .update(cx, |pane, cx| {
+                pane.close_active_item(&CloseActiveItem { save_intent: None }, cx)
+            })
+            .unwrap();
+        cx.executor().run_until_parked();
+        assert!(cx.has_pending_prompt());
+        cx.simulate_prompt_answer(1);
+        close.await.unwrap();
+        assert_item_labels(&pane, ["A", "B*"], cx);
+
+        let close = pane
+            .update(cx, |pane, cx| {
+                pane.close_active_item(&CloseActiveItem { save_intent: None }, cx)
+            })
+            .unwrap();
+        cx.executor().run_until_parked();
+        cx.simulate_prompt_answer(0);
+        close.await.unwrap();
+        assert_item_labels(&pane, ["A*"], cx);
+
+        // Items without a confirmation close without prompting
+        pane.update(cx, |pane, cx| {
+            pane.close_active_item(&CloseActiveItem { save_intent: None }, cx)
+        })
+        .unwrap()
+        .await
+        .unwrap();
+        assert_item_labels(&pane, [], cx);
+    }
+
     fn init_test(cx: &mut TestAppContext) {
         cx.update(|cx| {
             let settings_store = SettingsStore::test(cx);
@@ -1,3 +1,4 @@
+mod clipboard_ring;
 pub mod dock;
 pub mod item;
 mod modal_layer;
@@ -17,6 +18,7 @@ use client::{
     proto::{self, ErrorCode, PeerId},
     ChannelId, Client, ErrorExt, ProjectId, Status, TypedEnvelope, UserStore,
 };
+pub use clipboard_ring::ClipboardRing;
 use collections::{hash_map, HashMap, HashSet};
 use derive_more::{Deref, DerefMut};
 use dock::{Dock, DockPosition, Panel, PanelButtons, PanelHandle};